@@ -0,0 +1,172 @@
+// Accent colour for native chrome: the tray icon's in-call marker today, so
+// it matches the webview's highlight. Follows the OS accent unless the user
+// picks one; without an OS accent (most Linux desktops, older systems) it's
+// the brand blurple. Changes go out as `theme://accent`.
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::CommandError;
+use crate::prefs;
+use crate::tray;
+
+pub(crate) const ACCENT_PREF: &str = "accent_color";
+pub const DEFAULT_ACCENT: [u8; 3] = [0x58, 0x65, 0xf2];
+
+fn to_hex([r, g, b]: [u8; 3]) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// `#rrggbb` or `rrggbb`, any case.
+fn parse_hex(raw: &str) -> Result<[u8; 3], String> {
+    let digits = raw.trim().trim_start_matches('#');
+    if digits.len() != 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("not a #rrggbb colour: {raw}"));
+    }
+    let channel = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).map_err(|e| e.to_string());
+    Ok([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// DWM's accent, stored as 0xAABBGGRR.
+#[cfg(windows)]
+fn system_accent() -> Option<[u8; 3]> {
+    use windows_sys::Win32::Foundation::ERROR_SUCCESS;
+    use windows_sys::Win32::System::Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_DWORD};
+
+    let wide = |s: &str| s.encode_utf16().chain(Some(0)).collect::<Vec<u16>>();
+    let (key, name) = (wide(r"Software\Microsoft\Windows\DWM"), wide("AccentColor"));
+    let mut value = 0u32;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    // SAFETY: NUL-terminated key and value names; `value` is a DWORD-sized
+    // out-param matching `size`.
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            key.as_ptr(),
+            name.as_ptr(),
+            RRF_RT_REG_DWORD,
+            std::ptr::null_mut(),
+            (&mut value as *mut u32).cast(),
+            &mut size,
+        )
+    };
+    if status != ERROR_SUCCESS {
+        return None;
+    }
+    let [r, g, b, _] = value.to_le_bytes();
+    Some([r, g, b])
+}
+
+/// `AppleAccentColor` is an index into the fixed palette; absent means the
+/// default blue.
+#[cfg(target_os = "macos")]
+fn system_accent() -> Option<[u8; 3]> {
+    let output = std::process::Command::new("defaults")
+        .args(["read", "-g", "AppleAccentColor"])
+        .output()
+        .ok()?;
+    let index = String::from_utf8_lossy(&output.stdout).trim().parse::<i32>().ok();
+    Some(match index {
+        Some(-1) => [0x8c, 0x8c, 0x8c],
+        Some(0) => [0xff, 0x52, 0x57],
+        Some(1) => [0xf7, 0x82, 0x1b],
+        Some(2) => [0xff, 0xc6, 0x00],
+        Some(3) => [0x62, 0xba, 0x46],
+        Some(5) => [0xa5, 0x50, 0xa7],
+        Some(6) => [0xf7, 0x4f, 0x9e],
+        _ => [0x00, 0x7a, 0xff],
+    })
+}
+
+/// GNOME 47+ names its accent; other desktops don't expose one.
+#[cfg(target_os = "linux")]
+fn system_accent() -> Option<[u8; 3]> {
+    let output = std::process::Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.interface", "accent-color"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    match String::from_utf8_lossy(&output.stdout).trim().trim_matches('\'') {
+        "blue" => Some([0x35, 0x84, 0xe4]),
+        "teal" => Some([0x21, 0x90, 0xa4]),
+        "green" => Some([0x3a, 0x94, 0x4a]),
+        "yellow" => Some([0xc8, 0x88, 0x00]),
+        "orange" => Some([0xed, 0x5b, 0x00]),
+        "red" => Some([0xe6, 0x2d, 0x42]),
+        "pink" => Some([0xd5, 0x61, 0x99]),
+        "purple" => Some([0x91, 0x41, 0xac]),
+        "slate" => Some([0x6f, 0x83, 0x96]),
+        _ => None,
+    }
+}
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+fn system_accent() -> Option<[u8; 3]> {
+    None
+}
+
+fn saved(app: &AppHandle) -> Option<[u8; 3]> {
+    prefs::get::<String>(app, ACCENT_PREF).and_then(|hex| parse_hex(&hex).ok())
+}
+
+#[derive(Serialize, Clone)]
+pub struct AccentState {
+    /// `#rrggbb` in effect.
+    color: String,
+    /// `custom` when set with `set_accent_color`, else `system`.
+    source: &'static str,
+}
+
+fn current(app: &AppHandle) -> ([u8; 3], AccentState) {
+    let (rgb, source) = match saved(app) {
+        Some(rgb) => (rgb, "custom"),
+        None => (system_accent().unwrap_or(DEFAULT_ACCENT), "system"),
+    };
+    (rgb, AccentState { color: to_hex(rgb), source })
+}
+
+fn apply(app: &AppHandle) -> AccentState {
+    let (rgb, state) = current(app);
+    if let Err(e) = tray::set_accent(app, rgb) {
+        log::warn!("tray accent not updated: {e}");
+    }
+    state
+}
+
+/// Tint the tray with the saved or OS accent. Called from setup, after the
+/// tray exists.
+pub fn restore(app: &AppHandle) {
+    apply(app);
+}
+
+/// The OS accent may have moved with the theme; re-read it while following
+/// the OS, and tell the webview.
+pub fn on_theme_changed(app: &AppHandle) {
+    if saved(app).is_some() {
+        return;
+    }
+    let _ = app.emit("theme://accent", apply(app));
+}
+
+/// The OS accent as `#rrggbb`, or the app default where there isn't one.
+#[tauri::command]
+pub fn get_system_accent_color() -> String {
+    to_hex(system_accent().unwrap_or(DEFAULT_ACCENT))
+}
+
+#[tauri::command]
+pub fn get_accent_color(app: AppHandle) -> AccentState {
+    current(&app).1
+}
+
+/// Use `hex` (`#rrggbb`) as the accent, or follow the OS again with `None`.
+#[tauri::command]
+pub fn set_accent_color(app: AppHandle, hex: Option<String>) -> Result<AccentState, CommandError> {
+    let rgb = hex.as_deref().map(parse_hex).transpose().map_err(CommandError::invalid_input)?;
+    prefs::set(&app, ACCENT_PREF, &rgb.map(to_hex))?;
+    let state = apply(&app);
+    let _ = app.emit("theme://accent", state.clone());
+    Ok(state)
+}
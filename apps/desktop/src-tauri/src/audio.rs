@@ -0,0 +1,143 @@
+// Audio device enumeration for the voice settings picker. cpal has no stable
+// device ids across platforms, so the device name doubles as the id.
+
+use cpal::traits::{DeviceTrait, HostTrait};
+use serde::Serialize;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::CommandError;
+use crate::prefs;
+
+pub(crate) const PREFERRED_INPUT: &str = "audio_input";
+pub(crate) const PREFERRED_OUTPUT: &str = "audio_output";
+const WATCH_INTERVAL: Duration = Duration::from_secs(3);
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DeviceKind {
+    Input,
+    Output,
+}
+
+#[derive(Serialize, Clone)]
+pub struct AudioDevice {
+    id: String,
+    name: String,
+    kind: DeviceKind,
+    is_default: bool,
+}
+
+#[derive(Serialize, Clone)]
+struct DeviceChanged {
+    kind: DeviceKind,
+    /// The device now in use; `None` means the system default.
+    id: Option<String>,
+}
+
+fn enumerate() -> Result<Vec<AudioDevice>, CommandError> {
+    let host = cpal::default_host();
+    let default_in = host.default_input_device().and_then(|d| d.name().ok());
+    let default_out = host.default_output_device().and_then(|d| d.name().ok());
+
+    let mut devices = Vec::new();
+    let inputs = host.input_devices()?;
+    let outputs = host.output_devices()?;
+    let tagged = inputs
+        .map(|d| (d, DeviceKind::Input))
+        .chain(outputs.map(|d| (d, DeviceKind::Output)));
+    for (device, kind) in tagged {
+        let Ok(name) = device.name() else { continue };
+        let default = match kind {
+            DeviceKind::Input => &default_in,
+            DeviceKind::Output => &default_out,
+        };
+        devices.push(AudioDevice {
+            is_default: default.as_deref() == Some(name.as_str()),
+            id: name.clone(),
+            name,
+            kind,
+        });
+    }
+    Ok(devices)
+}
+
+/// The preferred input device if it's connected, otherwise the system
+/// default. Can block like `enumerate`.
+pub(crate) fn input_device(app: &AppHandle) -> Option<cpal::Device> {
+    let host = cpal::default_host();
+    if let Some(Some(preferred)) = prefs::get::<Option<String>>(app, PREFERRED_INPUT) {
+        let found = host
+            .input_devices()
+            .ok()
+            .and_then(|mut devices| devices.find(|d| d.name().ok().as_deref() == Some(preferred.as_str())));
+        if found.is_some() {
+            return found;
+        }
+    }
+    host.default_input_device()
+}
+
+/// `input_device` for output.
+pub(crate) fn output_device(app: &AppHandle) -> Option<cpal::Device> {
+    let host = cpal::default_host();
+    if let Some(Some(preferred)) = prefs::get::<Option<String>>(app, PREFERRED_OUTPUT) {
+        let found = host
+            .output_devices()
+            .ok()
+            .and_then(|mut devices| devices.find(|d| d.name().ok().as_deref() == Some(preferred.as_str())));
+        if found.is_some() {
+            return found;
+        }
+    }
+    host.default_output_device()
+}
+
+#[tauri::command]
+pub async fn list_audio_devices() -> Result<Vec<AudioDevice>, CommandError> {
+    // Device probing can block on some backends (ALSA especially).
+    tauri::async_runtime::spawn_blocking(enumerate).await?
+}
+
+#[tauri::command]
+pub fn set_preferred_input(app: AppHandle, id: Option<String>) -> Result<(), CommandError> {
+    prefs::set(&app, PREFERRED_INPUT, &id)?;
+    crate::vad::restart_if_running(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_preferred_output(app: AppHandle, id: Option<String>) -> Result<(), CommandError> {
+    prefs::set(&app, PREFERRED_OUTPUT, &id)
+}
+
+/// Watch for the preferred devices disappearing (e.g. a headset unplugged)
+/// and tell the webview to fall back to the system default, then switch
+/// back once the device returns. The saved preference is left untouched.
+pub fn start_watcher(app: &AppHandle) {
+    crate::shutdown::spawn(app, |app| async move {
+        let mut fallen_back = [false, false];
+        loop {
+            tokio::time::sleep(WATCH_INTERVAL).await;
+            if crate::throttle::engaged() {
+                continue;
+            }
+            let Ok(Ok(devices)) = tauri::async_runtime::spawn_blocking(enumerate).await else {
+                continue;
+            };
+            let watched = [(PREFERRED_INPUT, DeviceKind::Input), (PREFERRED_OUTPUT, DeviceKind::Output)];
+            for (i, (key, kind)) in watched.into_iter().enumerate() {
+                let Some(Some(preferred)) = prefs::get::<Option<String>>(&app, key) else {
+                    fallen_back[i] = false;
+                    continue;
+                };
+                let missing = !devices.iter().any(|d| d.kind == kind && d.id == preferred);
+                if missing != fallen_back[i] {
+                    fallen_back[i] = missing;
+                    let id = (!missing).then_some(preferred);
+                    let _ = app.emit("audio://device_changed", DeviceChanged { kind, id });
+                }
+            }
+        }
+    });
+}
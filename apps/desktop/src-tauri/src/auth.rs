@@ -0,0 +1,303 @@
+// Token validation. A revoked or expired token otherwise only shows up when
+// a real request 401s; checking it against `/api/auth/me` first lets startup
+// skip the join beacon and send the user back to login instead.
+//
+// A token that expires mid-session is renewed against `/api/auth/refresh`
+// with the refresh token kept in the keychain, and the request that hit the
+// 401 is retried once. When there's no refresh token or the server refuses
+// it, `auth://expired` asks the UI to sign in again.
+//
+// Either kind of rejection also checks the clock against the server's, since
+// a skewed clock fails token validation in the same way (see clock.rs).
+//
+// The account's other signed-in devices can be listed and revoked from here
+// too, for the security settings.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::CommandError;
+use crate::net::Traced;
+use crate::presence::{AuthInfo, AuthState};
+
+const VALIDATE_TIMEOUT: Duration = Duration::from_secs(5);
+const REFRESH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// For the HTTP paths that only have the credentials to hand.
+static APP: OnceLock<AppHandle> = OnceLock::new();
+/// One refresh at a time; a 401 that arrives meanwhile waits and uses its
+/// result.
+static REFRESHING: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+/// Set once `auth://expired` has gone out, until new credentials arrive, so
+/// every heartbeat after that doesn't try (and announce) again.
+static EXPIRED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Identity {
+    pub id: i64,
+    pub username: String,
+    pub display_name: String,
+}
+
+#[derive(Deserialize)]
+struct MeResponse {
+    user: Identity,
+}
+
+/// Ask `server_url` who `token` belongs to. Only `auth` (rejected with
+/// 401/403, or its user is gone: 404) says anything about the token. Error
+/// messages have the token scrubbed, since this may run on a token the log
+/// redactor doesn't know yet.
+pub async fn whoami(server_url: &str, token: &str) -> Result<Identity, CommandError> {
+    let scrub = |e: String| crate::logging::redact_token(&e, token);
+    let client = crate::net::client().map_err(CommandError::network)?;
+    let response = client
+        .get(format!("{server_url}/api/auth/me"))
+        .bearer_auth(token)
+        .timeout(VALIDATE_TIMEOUT)
+        .send_traced()
+        .await
+        .map_err(|e| CommandError::network(scrub(e.to_string())))?;
+    let status = response.status();
+    if !status.is_success() {
+        crate::net::record_error("GET", response).await;
+        return Err(match status.as_u16() {
+            401 | 403 | 404 => CommandError::auth(format!("token rejected ({status})")),
+            s => CommandError::Server { status: s, message: format!("unexpected status {status}") },
+        });
+    }
+    let body = response
+        .text()
+        .await
+        .map_err(|e| CommandError::network(scrub(e.to_string())))?;
+    let me: MeResponse =
+        serde_json::from_str(&body).map_err(|e| CommandError::Parse { message: e.to_string() })?;
+    Ok(me.user)
+}
+
+#[tauri::command]
+pub async fn validate_token(
+    app: AppHandle,
+    token: String,
+    server_url: Option<String>,
+) -> Result<Identity, CommandError> {
+    let server_url = crate::net::resolve_server_url(&app, server_url.as_deref())?;
+    let result = whoami(&server_url, &token).await;
+    if matches!(result, Err(CommandError::Auth { .. })) {
+        crate::clock::check_after_auth_failure(&app, &server_url);
+    }
+    result
+}
+
+#[derive(Deserialize)]
+struct RefreshResponse {
+    #[serde(alias = "access_token")]
+    token: String,
+    /// Servers that rotate refresh tokens send the next one here.
+    #[serde(default)]
+    refresh_token: Option<String>,
+}
+
+enum RefreshFailed {
+    /// No refresh token, or the server wouldn't take it: sign in again.
+    Rejected(String),
+    /// Couldn't tell; the next 401 will try again.
+    Unavailable(String),
+}
+
+/// Called from setup.
+pub fn init(app: &AppHandle) {
+    let _ = APP.set(app.clone());
+}
+
+/// New credentials from the webview; refreshing is worth trying again.
+pub fn clear_expired() {
+    EXPIRED.store(false, Ordering::Relaxed);
+}
+
+async fn refresh(server_url: &str) -> Result<String, RefreshFailed> {
+    let refresh_token = crate::secrets::read_refresh_token(server_url)
+        .map_err(|e| RefreshFailed::Unavailable(e.to_string()))?
+        .ok_or_else(|| RefreshFailed::Rejected("no refresh token".into()))?;
+    let scrub = |e: String| crate::logging::redact_token(&e, &refresh_token);
+    let client = crate::net::client().map_err(RefreshFailed::Unavailable)?;
+    let response = client
+        .post(format!("{server_url}/api/auth/refresh"))
+        .header("Content-Type", "application/json")
+        .body(serde_json::json!({ "refresh_token": refresh_token }).to_string())
+        .timeout(REFRESH_TIMEOUT)
+        .send_traced()
+        .await
+        .map_err(|e| RefreshFailed::Unavailable(scrub(e.to_string())))?;
+    let status = response.status();
+    if !status.is_success() {
+        crate::net::record_error("POST", response).await;
+        if matches!(status.as_u16(), 400 | 401 | 403) {
+            return Err(RefreshFailed::Rejected(format!("refresh refused ({status})")));
+        }
+        return Err(RefreshFailed::Unavailable(format!("refresh failed ({status})")));
+    }
+    let body = response
+        .text()
+        .await
+        .map_err(|e| RefreshFailed::Unavailable(scrub(e.to_string())))?;
+    let body: RefreshResponse =
+        serde_json::from_str(&body).map_err(|e| RefreshFailed::Unavailable(e.to_string()))?;
+    if let Some(next) = &body.refresh_token {
+        crate::secrets::save_refresh(server_url, next).map_err(|e| RefreshFailed::Unavailable(e.to_string()))?;
+    }
+    Ok(body.token)
+}
+
+/// `info` just got a 401. Returns the credentials to retry with, or `None`
+/// if there's nothing to retry. Only the signed-in token is renewed; a
+/// stale one (e.g. from an older webview sync) gets the current one.
+pub async fn refresh_after_401(info: &AuthInfo) -> Option<AuthInfo> {
+    let app = APP.get()?;
+    if EXPIRED.load(Ordering::Relaxed) {
+        return None;
+    }
+    let _guard = REFRESHING.lock().await;
+    let current = app.state::<AuthState>().0.lock().unwrap().clone()?;
+    if current.server_url != info.server_url {
+        return None;
+    }
+    if current.token != info.token {
+        return Some(current);
+    }
+    match refresh(&info.server_url).await {
+        Ok(token) => {
+            if let Err(e) = crate::secrets::store_token(app, &info.server_url, &token) {
+                log::warn!("refreshed token not saved: {e}");
+            }
+            log::info!("auth token refreshed");
+            Some(AuthInfo { token, server_url: info.server_url.clone() })
+        }
+        Err(RefreshFailed::Unavailable(e)) => {
+            log::warn!("token refresh failed: {e}");
+            None
+        }
+        Err(RefreshFailed::Rejected(e)) => {
+            log::warn!("token expired and can't be refreshed: {e}");
+            crate::clock::check_after_auth_failure(app, &info.server_url);
+            if let Err(e) = crate::secrets::delete_refresh_token(&info.server_url) {
+                log::warn!("failed to drop refresh token: {e}");
+            }
+            if !EXPIRED.swap(true, Ordering::Relaxed) {
+                let _ = app.emit("auth://expired", &info.server_url);
+            }
+            None
+        }
+    }
+}
+
+/// One signed-in device, as the server describes it, plus whether it's us.
+#[derive(Serialize, Deserialize)]
+pub struct Session {
+    /// String or number, whatever the server uses; passed back as-is to
+    /// `revoke_session`.
+    id: serde_json::Value,
+    #[serde(default)]
+    current: bool,
+    /// Device name, user agent, last seen and the like.
+    #[serde(flatten)]
+    details: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Either a bare list or `{ "sessions": [...], "current_session_id": ... }`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SessionsResponse {
+    List(Vec<Session>),
+    Wrapped {
+        sessions: Vec<Session>,
+        #[serde(default)]
+        current_session_id: Option<serde_json::Value>,
+    },
+}
+
+fn signed_in(app: &AppHandle) -> Result<AuthInfo, CommandError> {
+    let auth = app.state::<AuthState>().0.lock().unwrap().clone();
+    auth.ok_or_else(|| CommandError::auth("Not signed in"))
+}
+
+/// 401 is our own token; 403 means this token may not manage sessions
+/// (e.g. it isn't the account owner's).
+fn session_status(status: tauri_plugin_http::reqwest::StatusCode) -> Result<(), CommandError> {
+    match status.as_u16() {
+        401 => Err(CommandError::auth(format!("token rejected ({status})"))),
+        403 => Err(CommandError::permission("not allowed to manage sessions for this account")),
+        _ if !status.is_success() => Err(CommandError::Server {
+            status: status.as_u16(),
+            message: format!("sessions request failed ({status})"),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// This account's signed-in devices, with ours marked `current`.
+#[tauri::command]
+pub async fn list_sessions(app: AppHandle) -> Result<Vec<Session>, CommandError> {
+    let info = signed_in(&app)?;
+    let scrub = |e: String| crate::logging::redact_token(&e, &info.token);
+    let response = crate::net::client()
+        .map_err(CommandError::network)?
+        .get(format!("{}/api/auth/sessions", info.server_url))
+        .bearer_auth(&info.token)
+        .timeout(VALIDATE_TIMEOUT)
+        .send_traced()
+        .await
+        .map_err(|e| CommandError::network(scrub(e.to_string())))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        crate::net::record_error("GET", response).await;
+        return session_status(status).map(|()| Vec::new());
+    }
+    let body = response.text().await.map_err(|e| CommandError::network(scrub(e.to_string())))?;
+    let parsed: SessionsResponse = serde_json::from_str(&body)
+        .map_err(|e| CommandError::Other { message: format!("unexpected sessions response: {e}") })?;
+    Ok(match parsed {
+        SessionsResponse::List(sessions) => sessions,
+        SessionsResponse::Wrapped { mut sessions, current_session_id } => {
+            if let Some(current) = current_session_id {
+                for session in &mut sessions {
+                    session.current |= session.id == current;
+                }
+            }
+            sessions
+        }
+    })
+}
+
+/// Sign out another device. For this one, use `logout`, which also
+/// clears the local state.
+#[tauri::command]
+pub async fn revoke_session(app: AppHandle, id: serde_json::Value) -> Result<(), CommandError> {
+    let info = signed_in(&app)?;
+    let id = match id {
+        serde_json::Value::String(s) => s,
+        serde_json::Value::Number(n) => n.to_string(),
+        _ => return Err(CommandError::invalid_input("session id must be a string or number")),
+    };
+    let mut url = url::Url::parse(&info.server_url).map_err(CommandError::invalid_input)?;
+    url.path_segments_mut()
+        .map_err(|()| CommandError::invalid_input("server url can't take a path"))?
+        .extend(["api", "auth", "sessions", &id]);
+    let scrub = |e: String| crate::logging::redact_token(&e, &info.token);
+    let response = crate::net::client()
+        .map_err(CommandError::network)?
+        .delete(url)
+        .bearer_auth(&info.token)
+        .timeout(VALIDATE_TIMEOUT)
+        .send_traced()
+        .await
+        .map_err(|e| CommandError::network(scrub(e.to_string())))?;
+    let status = response.status();
+    if !status.is_success() {
+        crate::net::record_error("DELETE", response).await;
+    }
+    session_status(status)
+}
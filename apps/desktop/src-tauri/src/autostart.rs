@@ -0,0 +1,42 @@
+// Start on login via tauri-plugin-autostart. The login item passes
+// `--autostart`, and a launch carrying it stays in the tray until clicked.
+
+use tauri::{AppHandle, Manager};
+use tauri_plugin_autostart::ManagerExt;
+
+use crate::error::CommandError;
+
+pub const AUTOSTART_ARG: &str = "--autostart";
+
+pub fn launched_at_login() -> bool {
+    std::env::args().any(|a| a == AUTOSTART_ARG)
+}
+
+/// The main window is created hidden (tauri.conf.json) so a login launch
+/// never flashes it; show it now unless this is one. Called from setup once
+/// window state has been restored.
+pub fn apply(app: &AppHandle) {
+    if launched_at_login() {
+        return;
+    }
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+    }
+}
+
+#[tauri::command]
+pub fn set_autostart(app: AppHandle, enabled: bool) -> Result<(), CommandError> {
+    let launcher = app.autolaunch();
+    let result = if enabled {
+        launcher.enable()
+    } else {
+        launcher.disable()
+    };
+    result.map_err(CommandError::io)?;
+    crate::prefs::set(&app, crate::prefs::AUTOSTART, &enabled)
+}
+
+#[tauri::command]
+pub fn is_autostart_enabled(app: AppHandle) -> Result<bool, CommandError> {
+    app.autolaunch().is_enabled().map_err(CommandError::io)
+}
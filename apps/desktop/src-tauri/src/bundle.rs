@@ -0,0 +1,85 @@
+// How this copy was installed, read from the bundle type the bundler
+// patches into the binary, and the guard that keeps the updater from
+// fetching a package for a different installer: an NSIS update run over an
+// MSI install (or the reverse) leaves two registrations or fails outright.
+// A bare executable, a portable copy or a dev build has no installer to hand
+// the update to, so those get a manual-update answer instead.
+
+use std::fmt;
+use tauri::utils::config::BundleType;
+use tauri::utils::platform::bundle_type;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InstallFormat {
+    Nsis,
+    Msi,
+    Deb,
+    Rpm,
+    AppImage,
+    /// A macOS `.app`, from the DMG or the updater's tarball.
+    App,
+}
+
+impl fmt::Display for InstallFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            InstallFormat::Nsis => "nsis",
+            InstallFormat::Msi => "msi",
+            InstallFormat::Deb => "deb",
+            InstallFormat::Rpm => "rpm",
+            InstallFormat::AppImage => "appimage",
+            InstallFormat::App => "app",
+        })
+    }
+}
+
+/// `None` when not installed by any of our bundles (portable or dev build).
+pub fn current() -> Option<InstallFormat> {
+    Some(match bundle_type()? {
+        BundleType::Nsis => InstallFormat::Nsis,
+        BundleType::Msi => InstallFormat::Msi,
+        BundleType::Deb => InstallFormat::Deb,
+        BundleType::Rpm => InstallFormat::Rpm,
+        BundleType::AppImage => InstallFormat::AppImage,
+        BundleType::App | BundleType::Dmg => InstallFormat::App,
+    })
+}
+
+/// Whether updates have to be downloaded and installed by hand.
+pub fn manual_update_required() -> bool {
+    current().is_none()
+}
+
+/// The format of an update package, from its file name. `None` for names
+/// we don't recognise, which are let through.
+fn offered(url: &url::Url) -> Option<InstallFormat> {
+    let name = url.path_segments()?.next_back()?.to_ascii_lowercase();
+    if name.ends_with(".msi") || name.ends_with(".msi.zip") {
+        Some(InstallFormat::Msi)
+    } else if name.ends_with(".exe") || name.ends_with(".nsis.zip") {
+        Some(InstallFormat::Nsis)
+    } else if name.ends_with(".appimage") || name.ends_with(".appimage.tar.gz") {
+        Some(InstallFormat::AppImage)
+    } else if name.ends_with(".deb") {
+        Some(InstallFormat::Deb)
+    } else if name.ends_with(".rpm") {
+        Some(InstallFormat::Rpm)
+    } else if name.ends_with(".app.tar.gz") {
+        Some(InstallFormat::App)
+    } else {
+        None
+    }
+}
+
+/// Refuse an update this install can't apply, before downloading it.
+pub fn check(update: &tauri_plugin_updater::Update) -> Result<(), String> {
+    let Some(installed) = current() else {
+        return Err("manual_update_required: not installed by an installer (portable copy)".into());
+    };
+    match offered(&update.download_url) {
+        Some(format) if format != installed => Err(format!(
+            "install_format_mismatch: installed as {installed}, update is {format}"
+        )),
+        _ => Ok(()),
+    }
+}
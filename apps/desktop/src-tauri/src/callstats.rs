@@ -0,0 +1,151 @@
+// Connection stats for the current or most recent call, so "my call was
+// choppy" comes with numbers. The webview pushes what it reads from
+// `RTCPeerConnection.getStats()` every tick; this keeps the ticks for the
+// call (bounded) and summarizes them on request. A call starts and ends with
+// `set_in_call`, and the last one is kept until the next starts. The
+// summary says whether the call went through a relay and which transport
+// mode was set, for "calls never connect" reports.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+
+use crate::rtc::TransportMode;
+
+/// An hour at one tick a second.
+const TICK_CAP: usize = 3600;
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CandidateType {
+    Host,
+    Srflx,
+    Prflx,
+    Relay,
+}
+
+/// One `getStats()` reading for the selected candidate pair. Anything the
+/// browser didn't report is left out.
+#[derive(Deserialize, Clone)]
+pub struct CallTick {
+    local_candidate: Option<CandidateType>,
+    remote_candidate: Option<CandidateType>,
+    /// The TURN URL in use when the local candidate is `relay`.
+    relay_server: Option<String>,
+    jitter_ms: Option<f64>,
+    /// Fraction of inbound packets lost since the previous tick, 0–1.
+    packet_loss: Option<f64>,
+    rtt_ms: Option<f64>,
+}
+
+struct Call {
+    started: Instant,
+    /// Unix ms.
+    started_at: u64,
+    ended: Option<Instant>,
+    ticks: VecDeque<CallTick>,
+}
+
+static CALL: Mutex<Option<Call>> = Mutex::new(None);
+
+#[derive(Serialize)]
+pub struct Metric {
+    average: f64,
+    max: f64,
+}
+
+#[derive(Serialize)]
+pub struct CallStats {
+    /// Unix ms.
+    started_at: u64,
+    duration_secs: u64,
+    ongoing: bool,
+    /// Local candidate types the call used, in the order first seen.
+    local_candidates: Vec<CandidateType>,
+    remote_candidates: Vec<CandidateType>,
+    /// Some reading had a relay as the local candidate.
+    relayed: bool,
+    /// Last TURN server reported; `None` if the call never relayed.
+    relay_server: Option<String>,
+    /// The transport mode set now, which the call used unless it was
+    /// changed mid-call.
+    transport_mode: TransportMode,
+    jitter_ms: Option<Metric>,
+    packet_loss: Option<Metric>,
+    rtt_ms: Option<Metric>,
+    samples: usize,
+}
+
+fn metric(values: impl Iterator<Item = f64>) -> Option<Metric> {
+    let (mut sum, mut max, mut count) = (0.0, f64::MIN, 0usize);
+    for v in values.filter(|v| v.is_finite()) {
+        sum += v;
+        max = max.max(v);
+        count += 1;
+    }
+    (count > 0).then(|| Metric { average: sum / count as f64, max })
+}
+
+fn distinct(types: impl Iterator<Item = CandidateType>) -> Vec<CandidateType> {
+    let mut seen = Vec::new();
+    for t in types {
+        if !seen.contains(&t) {
+            seen.push(t);
+        }
+    }
+    seen
+}
+
+/// Called from `notify` as the call flag changes.
+pub fn on_call_changed(in_call: bool) {
+    let mut call = CALL.lock().unwrap();
+    if in_call {
+        *call = Some(Call {
+            started: Instant::now(),
+            started_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_millis() as u64),
+            ended: None,
+            ticks: VecDeque::new(),
+        });
+    } else if let Some(call) = call.as_mut() {
+        call.ended.get_or_insert_with(Instant::now);
+    }
+}
+
+/// Add a stats reading to the current call. Ignored outside a call.
+#[tauri::command]
+pub fn report_call_stats(tick: CallTick) {
+    let mut call = CALL.lock().unwrap();
+    let Some(call) = call.as_mut().filter(|c| c.ended.is_none()) else { return };
+    if call.ticks.len() == TICK_CAP {
+        call.ticks.pop_front();
+    }
+    call.ticks.push_back(tick);
+}
+
+/// Summary of the current call, or the last one; `None` before any call.
+#[tauri::command]
+pub fn get_call_stats(app: AppHandle) -> Option<CallStats> {
+    let call = CALL.lock().unwrap();
+    let call = call.as_ref()?;
+    let ticks = &call.ticks;
+    let end = call.ended.unwrap_or_else(Instant::now);
+    let local_candidates = distinct(ticks.iter().filter_map(|t| t.local_candidate));
+    Some(CallStats {
+        started_at: call.started_at,
+        duration_secs: end.duration_since(call.started).as_secs(),
+        ongoing: call.ended.is_none(),
+        relayed: local_candidates.contains(&CandidateType::Relay),
+        local_candidates,
+        remote_candidates: distinct(ticks.iter().filter_map(|t| t.remote_candidate)),
+        relay_server: ticks.iter().rev().find_map(|t| t.relay_server.clone()),
+        transport_mode: crate::rtc::transport_mode(&app),
+        jitter_ms: metric(ticks.iter().filter_map(|t| t.jitter_ms)),
+        packet_loss: metric(ticks.iter().filter_map(|t| t.packet_loss)),
+        rtt_ms: metric(ticks.iter().filter_map(|t| t.rtt_ms)),
+        samples: ticks.len(),
+    })
+}
@@ -0,0 +1,271 @@
+// What the connected server supports, from `/api/capabilities`, so native
+// features can skip endpoints a given server version doesn't have instead of
+// failing against them: update deltas, the event stream, TURN, and the join
+// and heartbeat beacons. Fetched once per server at sign-in and cached; a
+// server without the endpoint is assumed to have everything, which is how
+// the client behaved before it asked.
+//
+// A server can also name a `min_client_version`. Running anything older,
+// the beacons, heartbeat, event stream and outbox stay off for that server,
+// `client://outdated` goes out, and the update flow is started: a check,
+// then the native prompt (or the auto-install download, when that's on).
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::CommandError;
+use crate::net::Traced;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Copy)]
+pub enum Feature {
+    DeltaUpdates,
+    Sse,
+    Turn,
+    JoinBeacon,
+    Heartbeat,
+}
+
+/// Feature flags as the server reports them; anything it leaves out is off.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Reported {
+    version: Option<String>,
+    min_client_version: Option<String>,
+    delta_updates: bool,
+    sse: bool,
+    turn: bool,
+    join_beacon: bool,
+    heartbeat: bool,
+}
+
+/// Either flat or under `features`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Response {
+    Wrapped {
+        version: Option<String>,
+        min_client_version: Option<String>,
+        features: Reported,
+    },
+    Flat(Reported),
+}
+
+#[derive(Serialize, Clone)]
+pub struct Capabilities {
+    /// False when the server has no capabilities endpoint (an older
+    /// version), in which case everything is assumed to be there.
+    reported: bool,
+    version: Option<String>,
+    /// Oldest client the server will talk to.
+    min_client_version: Option<String>,
+    /// This build is older than `min_client_version`.
+    client_outdated: bool,
+    delta_updates: bool,
+    sse: bool,
+    turn: bool,
+    join_beacon: bool,
+    heartbeat: bool,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ClientVersion {
+    current_version: String,
+    min_client_version: Option<String>,
+    outdated: bool,
+}
+
+impl Capabilities {
+    fn legacy() -> Self {
+        Capabilities {
+            reported: false,
+            version: None,
+            min_client_version: None,
+            client_outdated: false,
+            delta_updates: true,
+            sse: true,
+            turn: true,
+            join_beacon: true,
+            heartbeat: true,
+        }
+    }
+
+    fn has(&self, feature: Feature) -> bool {
+        match feature {
+            Feature::DeltaUpdates => self.delta_updates,
+            Feature::Sse => self.sse,
+            Feature::Turn => self.turn,
+            Feature::JoinBeacon => self.join_beacon,
+            Feature::Heartbeat => self.heartbeat,
+        }
+    }
+}
+
+/// The server URL and what it reported.
+static CACHE: Mutex<Option<(String, Capabilities)>> = Mutex::new(None);
+
+/// Whether `server_url` has `feature`. True until its capabilities are known.
+pub fn supports(server_url: &str, feature: Feature) -> bool {
+    match CACHE.lock().unwrap().as_ref() {
+        Some((url, caps)) if url == server_url => caps.has(feature),
+        _ => true,
+    }
+}
+
+/// Whether this build is too old for `server_url`. False until its
+/// capabilities are known.
+pub fn client_outdated(server_url: &str) -> bool {
+    match CACHE.lock().unwrap().as_ref() {
+        Some((url, caps)) if url == server_url => caps.client_outdated,
+        _ => false,
+    }
+}
+
+/// `client_outdated` for the current server.
+pub fn outdated(app: &AppHandle) -> bool {
+    crate::net::server_url(app).is_some_and(|url| client_outdated(&url))
+}
+
+/// The running version against `min`. A minimum that isn't semver is logged
+/// and ignored rather than locking everyone out.
+fn below(app: &AppHandle, min: Option<&str>) -> bool {
+    let Some(min) = min else { return false };
+    match semver::Version::parse(min.trim().trim_start_matches('v')) {
+        Ok(min) => app.package_info().version < min,
+        Err(e) => {
+            log::warn!("ignoring unreadable min_client_version {min:?}: {e}");
+            false
+        }
+    }
+}
+
+fn client_version(app: &AppHandle, caps: &Capabilities) -> ClientVersion {
+    ClientVersion {
+        current_version: app.package_info().version.to_string(),
+        min_client_version: caps.min_client_version.clone(),
+        outdated: caps.client_outdated,
+    }
+}
+
+/// Too old for `server_url`: stop talking to it and get the update going.
+fn on_outdated(app: &AppHandle, server_url: &str, caps: &Capabilities) {
+    let version = client_version(app, caps);
+    log::warn!(
+        "{server_url} needs client {} or newer, this is {}",
+        version.min_client_version.as_deref().unwrap_or_default(),
+        version.current_version
+    );
+    crate::presence::stop_heartbeat(app);
+    crate::stream::stop(app);
+    let _ = app.emit("client://outdated", version);
+
+    let (app, server_url) = (app.clone(), server_url.to_string());
+    tauri::async_runtime::spawn(async move {
+        let token = app
+            .state::<crate::presence::AuthState>()
+            .0
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|info| info.token.clone());
+        let state = app.state::<crate::PendingUpdate>();
+        let options = crate::CheckOptions { server_url: Some(server_url), token, ..Default::default() };
+        match crate::check_for_update(app.clone(), state, Some(options)).await {
+            // Auto-install already has it downloading.
+            Ok(Some(info)) if !info.manual_update_required && !crate::auto_install_enabled(&app) => {
+                crate::update_prompt::prompt_now(&app);
+            }
+            Ok(Some(_)) => {}
+            Ok(None) => log::warn!("client is outdated but no update is being offered"),
+            Err(e) => log::warn!("update check for outdated client failed: {e:?}"),
+        }
+    });
+}
+
+async fn fetch(server_url: &str) -> Result<Capabilities, CommandError> {
+    let response = crate::net::client()
+        .map_err(CommandError::network)?
+        .get(format!("{server_url}/api/capabilities"))
+        .timeout(FETCH_TIMEOUT)
+        .send_traced()
+        .await?;
+    let status = response.status();
+    // Older servers: the route (or the whole /api/capabilities prefix) isn't
+    // there.
+    if status.as_u16() == 404 || status.as_u16() == 405 {
+        return Ok(Capabilities::legacy());
+    }
+    if !status.is_success() {
+        crate::net::record_error("GET", response).await;
+        return Err(CommandError::Server {
+            status: status.as_u16(),
+            message: format!("capabilities request failed: {status}"),
+        });
+    }
+    let body = response.text().await?;
+    let (version, min_client_version, reported) =
+        match serde_json::from_str(&body).map_err(|e| format!("invalid capabilities: {e}"))? {
+            Response::Wrapped {
+                version,
+                min_client_version,
+                mut features,
+            } => (
+                version.or(features.version.take()),
+                min_client_version.or(features.min_client_version.take()),
+                features,
+            ),
+            Response::Flat(mut reported) => (reported.version.take(), reported.min_client_version.take(), reported),
+        };
+    Ok(Capabilities {
+        reported: true,
+        version,
+        min_client_version,
+        client_outdated: false,
+        delta_updates: reported.delta_updates,
+        sse: reported.sse,
+        turn: reported.turn,
+        join_beacon: reported.join_beacon,
+        heartbeat: reported.heartbeat,
+    })
+}
+
+/// Fetch and cache `server_url`'s capabilities unless already known, and
+/// emit what was fetched as `server://capabilities`. A failed fetch
+/// leaves the cache alone, so everything stays assumed available. A fetch
+/// that finds this build too old starts the update flow (`on_outdated`).
+pub async fn refresh(app: &AppHandle, server_url: &str, force: bool) -> Result<Capabilities, CommandError> {
+    if !force {
+        if let Some((url, caps)) = CACHE.lock().unwrap().as_ref() {
+            if url == server_url {
+                return Ok(caps.clone());
+            }
+        }
+    }
+    let mut caps = fetch(server_url).await?;
+    caps.client_outdated = below(app, caps.min_client_version.as_deref());
+    *CACHE.lock().unwrap() = Some((server_url.to_string(), caps.clone()));
+    let _ = app.emit("server://capabilities", caps.clone());
+    if caps.client_outdated {
+        on_outdated(app, server_url, &caps);
+    }
+    Ok(caps)
+}
+
+/// The current server's capabilities, from cache unless `refresh`.
+#[tauri::command]
+pub async fn get_server_capabilities(app: AppHandle, refresh: Option<bool>) -> Result<Capabilities, CommandError> {
+    let server_url = crate::net::server_url(&app).ok_or_else(|| CommandError::invalid_input("no server url set"))?;
+    self::refresh(&app, &server_url, refresh == Some(true)).await
+}
+
+/// The current server's minimum client version against this build, from
+/// cache unless `refresh`.
+#[tauri::command]
+pub async fn get_min_client_version(app: AppHandle, refresh: Option<bool>) -> Result<ClientVersion, CommandError> {
+    let server_url = crate::net::server_url(&app).ok_or_else(|| CommandError::invalid_input("no server url set"))?;
+    let caps = self::refresh(&app, &server_url, refresh == Some(true)).await?;
+    Ok(client_version(&app, &caps))
+}
@@ -0,0 +1,219 @@
+// Screen-capture source enumeration for screen sharing. Thumbnails are small
+// base64 PNGs so the picker can render them straight into <img> tags. Also
+// grabs the app's own main window for bug reports.
+
+use base64::Engine;
+use image::RgbaImage;
+use serde::Serialize;
+use std::io::Cursor;
+use tauri::{AppHandle, Manager};
+use xcap::{Monitor, Window};
+
+use crate::error::CommandError;
+
+const THUMBNAIL_WIDTH: u32 = 320;
+/// Bounds for a full `capture_source` grab; bigger frames are downscaled.
+const MAX_CAPTURE_WIDTH: u32 = 1920;
+const MAX_CAPTURE_HEIGHT: u32 = 1080;
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+    Monitor,
+    Window,
+}
+
+#[derive(Serialize, Clone)]
+pub struct CaptureSource {
+    /// `monitor:<id>` or `window:<id>`.
+    id: String,
+    kind: SourceKind,
+    name: String,
+    thumbnail: Option<String>,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PermissionStatus {
+    Granted,
+    Denied,
+}
+
+#[cfg(target_os = "macos")]
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGPreflightScreenCaptureAccess() -> bool;
+    fn CGRequestScreenCaptureAccess() -> bool;
+}
+
+/// Only macOS gates capture behind a user permission (Screen Recording).
+fn screen_permission() -> PermissionStatus {
+    #[cfg(target_os = "macos")]
+    let granted = unsafe { CGPreflightScreenCaptureAccess() };
+    #[cfg(not(target_os = "macos"))]
+    let granted = true;
+    if granted {
+        PermissionStatus::Granted
+    } else {
+        PermissionStatus::Denied
+    }
+}
+
+fn ensure_permission() -> Result<(), CommandError> {
+    match screen_permission() {
+        PermissionStatus::Granted => Ok(()),
+        PermissionStatus::Denied => Err(CommandError::permission("permission_denied")),
+    }
+}
+
+/// Downscale (never upscale) to `max_width` and encode as base64 PNG.
+pub(crate) fn encode_png(img: &RgbaImage, max_width: u32) -> Result<String, String> {
+    let img = if img.width() > max_width {
+        let height = (img.height() as u64 * max_width as u64 / img.width() as u64).max(1) as u32;
+        image::imageops::thumbnail(img, max_width, height)
+    } else {
+        img.clone()
+    };
+    let mut png = Vec::new();
+    img.write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+        .map_err(|e| e.to_string())?;
+    Ok(base64::engine::general_purpose::STANDARD.encode(png))
+}
+
+fn enumerate() -> Result<Vec<CaptureSource>, CommandError> {
+    ensure_permission()?;
+    let mut sources = Vec::new();
+    for monitor in Monitor::all().map_err(CommandError::io)? {
+        let Ok(id) = monitor.id() else { continue };
+        sources.push(CaptureSource {
+            id: format!("monitor:{id}"),
+            kind: SourceKind::Monitor,
+            name: monitor.name().unwrap_or_else(|_| format!("Display {id}")),
+            thumbnail: monitor
+                .capture_image()
+                .ok()
+                .and_then(|img| encode_png(&img, THUMBNAIL_WIDTH).ok()),
+        });
+    }
+    for window in Window::all().map_err(CommandError::io)? {
+        if window.is_minimized().unwrap_or(false) {
+            continue;
+        }
+        let (Ok(id), Ok(title)) = (window.id(), window.title()) else { continue };
+        if title.is_empty() {
+            continue;
+        }
+        sources.push(CaptureSource {
+            id: format!("window:{id}"),
+            kind: SourceKind::Window,
+            name: title,
+            thumbnail: window
+                .capture_image()
+                .ok()
+                .and_then(|img| encode_png(&img, THUMBNAIL_WIDTH).ok()),
+        });
+    }
+    Ok(sources)
+}
+
+#[tauri::command]
+pub async fn list_capture_sources() -> Result<Vec<CaptureSource>, CommandError> {
+    // Grabbing a frame per source is slow; keep it off the async workers.
+    tauri::async_runtime::spawn_blocking(enumerate).await?
+}
+
+fn grab(id: &str) -> Result<RgbaImage, CommandError> {
+    ensure_permission()?;
+    let invalid = || CommandError::invalid_input(format!("invalid source id: {id}"));
+    let (kind, raw) = id.split_once(':').ok_or_else(invalid)?;
+    let num: u32 = raw.parse().map_err(|_| invalid())?;
+    let not_found = || CommandError::invalid_input(format!("capture source not found: {id}"));
+    match kind {
+        "monitor" => Monitor::all()
+            .map_err(CommandError::io)?
+            .into_iter()
+            .find(|m| m.id().ok() == Some(num))
+            .ok_or_else(not_found)?
+            .capture_image()
+            .map_err(CommandError::io),
+        "window" => Window::all()
+            .map_err(CommandError::io)?
+            .into_iter()
+            .find(|w| w.id().ok() == Some(num))
+            .ok_or_else(not_found)?
+            .capture_image()
+            .map_err(CommandError::io),
+        _ => Err(invalid()),
+    }
+}
+
+/// `encode_png` scaled to fit within MAX_CAPTURE_WIDTH × MAX_CAPTURE_HEIGHT.
+fn encode_capture(img: &RgbaImage) -> Result<String, String> {
+    // Width that also keeps the height within bounds.
+    let max_width = MAX_CAPTURE_WIDTH
+        .min((img.width() as u64 * MAX_CAPTURE_HEIGHT as u64 / img.height().max(1) as u64) as u32)
+        .max(1);
+    encode_png(img, max_width)
+}
+
+/// One frame of a source from `list_capture_sources` as a base64 PNG, scaled
+/// down to fit within MAX_CAPTURE_WIDTH × MAX_CAPTURE_HEIGHT.
+#[tauri::command]
+pub async fn capture_source(id: String) -> Result<String, CommandError> {
+    tauri::async_runtime::spawn_blocking(move || Ok(encode_capture(&grab(&id)?)?)).await?
+}
+
+/// The main window as the OS sees it: one of this process's windows, by
+/// title, or failing that the biggest.
+fn grab_main(title: &str) -> Result<RgbaImage, CommandError> {
+    ensure_permission()?;
+    let pid = std::process::id();
+    let mut ours: Vec<Window> = Window::all()
+        .map_err(CommandError::io)?
+        .into_iter()
+        .filter(|w| w.pid().ok() == Some(pid) && !w.is_minimized().unwrap_or(false))
+        .collect();
+    let area = |w: &Window| w.width().unwrap_or(0) as u64 * w.height().unwrap_or(0) as u64;
+    ours.sort_by_key(|w| std::cmp::Reverse((w.title().ok().as_deref() == Some(title), area(w))));
+    ours.first()
+        .ok_or("main window not found")?
+        .capture_image()
+        .map_err(CommandError::io)
+}
+
+/// The main window's contents as a base64 PNG, scaled like `capture_source`,
+/// for attaching to a bug report. Refused with `content_protected` (a
+/// permission error) when the user has hidden the app from capture, and
+/// `not_visible` when there's nothing on screen to grab.
+pub(crate) async fn capture_main(app: &AppHandle) -> Result<String, CommandError> {
+    if crate::window::content_protected(app) {
+        return Err(CommandError::permission("content_protected"));
+    }
+    let window = app.get_webview_window("main").ok_or("main window not found")?;
+    if !window.is_visible().unwrap_or(false) || window.is_minimized().unwrap_or(false) {
+        return Err("not_visible".into());
+    }
+    let title = window.title().unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || Ok(encode_capture(&grab_main(&title)?)?)).await?
+}
+
+#[tauri::command]
+pub async fn capture_window_screenshot(app: AppHandle) -> Result<String, CommandError> {
+    capture_main(&app).await
+}
+
+#[tauri::command]
+pub fn check_screen_permission() -> PermissionStatus {
+    screen_permission()
+}
+
+/// Trigger the macOS Screen Recording prompt. The OS only shows it once;
+/// after that the user has to flip the switch in System Settings.
+#[tauri::command]
+pub fn request_screen_permission() -> PermissionStatus {
+    #[cfg(target_os = "macos")]
+    unsafe {
+        CGRequestScreenCaptureAccess();
+    }
+    screen_permission()
+}
@@ -0,0 +1,38 @@
+// Native clipboard image access. Webview clipboard reads are unreliable for
+// images (notably WebKitGTK on Linux), so the composer reads pasted
+// screenshots through arboard instead.
+
+use image::RgbaImage;
+use serde::Serialize;
+
+use crate::error::CommandError;
+
+#[derive(Serialize, Clone)]
+pub struct ClipboardImage {
+    /// Base64-encoded PNG.
+    data: String,
+    width: u32,
+    height: u32,
+}
+
+fn read_image() -> Result<Option<ClipboardImage>, CommandError> {
+    let mut clipboard = arboard::Clipboard::new().map_err(CommandError::io)?;
+    let image = match clipboard.get_image() {
+        Ok(image) => image,
+        Err(arboard::Error::ContentNotAvailable) => return Ok(None),
+        Err(e) => return Err(CommandError::io(e)),
+    };
+    let (width, height) = (image.width as u32, image.height as u32);
+    let rgba = RgbaImage::from_raw(width, height, image.bytes.into_owned())
+        .ok_or_else(|| CommandError::io("clipboard image has an unexpected size"))?;
+    Ok(Some(ClipboardImage {
+        data: crate::capture::encode_png(&rgba, u32::MAX)?,
+        width,
+        height,
+    }))
+}
+
+#[tauri::command]
+pub async fn read_clipboard_image() -> Result<Option<ClipboardImage>, CommandError> {
+    tauri::async_runtime::spawn_blocking(read_image).await?
+}
@@ -0,0 +1,98 @@
+// Clock skew against the server. Tokens carry `iat`/`exp` and the server
+// checks them against its own clock, so a machine whose clock is minutes off
+// sees sign-in and refresh fail with nothing pointing at the cause. This
+// compares the `Date` header of a `/api/health` response with the local
+// clock, emits `clock://skew` when they're further apart than
+// `SKEW_THRESHOLD`, and is run when the server rejects a token.
+//
+// `Date` has one-second resolution and the request's own latency is split
+// evenly either side, so small deltas are noise.
+
+use serde::Serialize;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_http::reqwest::header;
+use time::format_description::well_known::Rfc2822;
+use time::OffsetDateTime;
+
+use crate::error::CommandError;
+use crate::net::Traced;
+
+const SKEW_THRESHOLD: Duration = Duration::from_secs(30);
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, Clone)]
+pub struct ClockSkew {
+    /// Local clock minus the server's, in seconds; positive when this
+    /// machine is ahead.
+    skew_secs: i64,
+    /// The server's time (Unix seconds).
+    server_time: i64,
+    threshold_secs: u64,
+    /// Further apart than the threshold.
+    skewed: bool,
+}
+
+fn unix_ms(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as i64)
+}
+
+/// Measure the skew against `server_url`.
+pub async fn measure(server_url: &str) -> Result<ClockSkew, CommandError> {
+    let client = crate::net::client().map_err(CommandError::network)?;
+    let sent = SystemTime::now();
+    let response = client
+        .get(format!("{server_url}/api/health"))
+        .timeout(CHECK_TIMEOUT)
+        .send_traced()
+        .await?;
+    let received = SystemTime::now();
+    let date = response
+        .headers()
+        .get(header::DATE)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| CommandError::network("server sent no Date header"))?;
+    let server = OffsetDateTime::parse(date, &Rfc2822)
+        .map_err(|e| CommandError::network(format!("unreadable Date header {date:?}: {e}")))?;
+
+    // The server stamped the response somewhere in the round trip; take the
+    // middle.
+    let local_ms = (unix_ms(sent) + unix_ms(received)) / 2;
+    let skew_secs = (local_ms - server.unix_timestamp() * 1000) / 1000;
+    Ok(ClockSkew {
+        skew_secs,
+        server_time: server.unix_timestamp(),
+        threshold_secs: SKEW_THRESHOLD.as_secs(),
+        skewed: skew_secs.unsigned_abs() > SKEW_THRESHOLD.as_secs(),
+    })
+}
+
+fn report(app: &AppHandle, skew: &ClockSkew) {
+    if skew.skewed {
+        log::warn!("local clock is {}s off the server's", skew.skew_secs);
+        let _ = app.emit("clock://skew", skew.clone());
+    }
+}
+
+/// The server just rejected a token: check whether the clock is why, in the
+/// background. Anything it finds goes out as `clock://skew`.
+pub fn check_after_auth_failure(app: &AppHandle, server_url: &str) {
+    let (app, server_url) = (app.clone(), server_url.to_string());
+    tauri::async_runtime::spawn(async move {
+        match measure(&server_url).await {
+            Ok(skew) => report(&app, &skew),
+            Err(e) => log::debug!("clock skew check failed: {e}"),
+        }
+    });
+}
+
+/// Compare the local clock with the server's (the synced one unless
+/// `server_url` is given). Emits `clock://skew` when they're too far apart.
+#[tauri::command]
+pub async fn check_clock_skew(app: AppHandle, server_url: Option<String>) -> Result<ClockSkew, CommandError> {
+    let server_url =
+        crate::net::resolve_server_url(&app, server_url.as_deref())?;
+    let skew = measure(&server_url).await?;
+    report(&app, &skew);
+    Ok(skew)
+}
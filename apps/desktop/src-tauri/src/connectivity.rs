@@ -0,0 +1,169 @@
+// Server reachability. Polls `/api/health` on the synced server and emits
+// `net://online` / `net://offline` on transitions. A state only flips after
+// it has been seen on consecutive probes, so a single dropped request
+// doesn't flap the UI's "Reconnecting…" banner.
+//
+// Latency is sampled separately against `/api/ping` and emitted as
+// `net://latency` with a rolling average for the call UI and status bar,
+// and fed to auto video quality (quality.rs).
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::CommandError;
+use crate::net::Traced;
+
+const POLL: Duration = Duration::from_secs(10);
+/// Faster re-probe while a transition is pending confirmation.
+const CONFIRM_POLL: Duration = Duration::from_secs(2);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Consecutive agreeing probes needed to change state.
+const CONFIRMATIONS: u32 = 2;
+
+const LATENCY_POLL: Duration = Duration::from_secs(5);
+/// A ping slower than this is reported as a timeout, not a latency.
+const PING_TIMEOUT: Duration = Duration::from_secs(3);
+/// Samples in the rolling average.
+const LATENCY_WINDOW: usize = 6;
+/// Average above which `net://latency` flags the connection as slow.
+const HIGH_LATENCY_MS: u64 = 300;
+
+pub struct Connectivity {
+    pub online: AtomicBool,
+}
+
+async fn probe(server_url: &str) -> bool {
+    let url = format!("{server_url}/api/health");
+    let Ok(client) = crate::net::client() else { return false };
+    client
+        .get(url)
+        .timeout(PROBE_TIMEOUT)
+        .send_traced()
+        .await
+        .is_ok_and(|r| r.status().is_success())
+}
+
+/// Start the monitor. Ticks without a synced server are skipped.
+pub fn start(app: &AppHandle) {
+    crate::shutdown::spawn(app, |app| async move {
+        let mut pending = 0;
+        loop {
+            tokio::time::sleep(if pending > 0 { CONFIRM_POLL } else { POLL }).await;
+            // Paused on purpose; not the same as offline.
+            if crate::net::paused() {
+                pending = 0;
+                continue;
+            }
+            let Some(server_url) = crate::net::server_url(&app) else { continue };
+
+            let reachable = probe(&server_url).await;
+            let state = app.state::<Connectivity>();
+            if reachable == state.online.load(Ordering::Relaxed) {
+                pending = 0;
+                continue;
+            }
+            pending += 1;
+            if pending >= CONFIRMATIONS {
+                pending = 0;
+                state.online.store(reachable, Ordering::Relaxed);
+                let name = if reachable { "net://online" } else { "net://offline" };
+                let _ = app.emit(name, ());
+                if reachable {
+                    crate::outbox::flush(&app);
+                }
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_connectivity(state: tauri::State<'_, Connectivity>) -> bool {
+    state.online.load(Ordering::Relaxed)
+}
+
+/// Round trip to `/api/ping` in milliseconds. Fails with `timeout` after
+/// `PING_TIMEOUT`, `unreachable` when the request fails outright (DNS,
+/// refused, TLS, no server set) or `server` for an error status.
+async fn ping(server_url: &str) -> Result<u64, CommandError> {
+    let client = crate::net::client().map_err(CommandError::unreachable)?;
+    let started = Instant::now();
+    let response = client
+        .get(format!("{server_url}/api/ping"))
+        .timeout(PING_TIMEOUT)
+        .send_traced()
+        .await
+        .map_err(|e| {
+            if e.is_timeout() {
+                CommandError::timeout("ping timed out")
+            } else {
+                CommandError::unreachable(e)
+            }
+        })?;
+    let rtt = started.elapsed().as_millis() as u64;
+    if !response.status().is_success() {
+        let status = response.status().as_u16();
+        crate::net::record_error("GET", response).await;
+        return Err(CommandError::Server { status, message: format!("ping failed: HTTP {status}") });
+    }
+    Ok(rtt)
+}
+
+#[tauri::command]
+pub async fn ping_server(app: AppHandle) -> Result<u64, CommandError> {
+    let server_url = crate::net::server_url(&app).ok_or_else(|| CommandError::unreachable("no server url set"))?;
+    ping(&server_url).await
+}
+
+#[derive(Serialize, Clone)]
+struct Latency {
+    /// This sample; `None` when it timed out or failed.
+    rtt_ms: Option<u64>,
+    /// Mean of the recent successful samples.
+    average_ms: Option<u64>,
+    timed_out: bool,
+    high: bool,
+}
+
+/// Start the `net://latency` sampler. Ticks without a synced server are
+/// skipped, and the window resets when the server changes.
+pub fn start_latency_sampler(app: &AppHandle) {
+    crate::shutdown::spawn(app, |app| async move {
+        let mut window: VecDeque<u64> = VecDeque::with_capacity(LATENCY_WINDOW);
+        let mut sampled: Option<String> = None;
+        loop {
+            tokio::time::sleep(LATENCY_POLL).await;
+            if crate::net::paused() {
+                continue;
+            }
+            let Some(server_url) = crate::net::server_url(&app) else { continue };
+            if sampled.as_deref() != Some(server_url.as_str()) {
+                window.clear();
+                sampled = Some(server_url.clone());
+            }
+
+            let result = ping(&server_url).await;
+            if let Ok(rtt) = result {
+                if window.len() == LATENCY_WINDOW {
+                    window.pop_front();
+                }
+                window.push_back(rtt);
+            }
+            let average_ms = (!window.is_empty())
+                .then(|| window.iter().sum::<u64>() / window.len() as u64);
+            let timed_out = matches!(result, Err(CommandError::Timeout { .. }));
+            crate::quality::on_latency(&app, average_ms, timed_out);
+            let _ = app.emit(
+                "net://latency",
+                Latency {
+                    rtt_ms: result.as_ref().ok().copied(),
+                    average_ms,
+                    timed_out,
+                    high: average_ms.is_some_and(|a| a > HIGH_LATENCY_MS),
+                },
+            );
+        }
+    });
+}
@@ -0,0 +1,163 @@
+// Panic capture. The hook only writes a local crash log (no HTTP, no app
+// state); the report is offered for upload on the next launch, and only sent
+// if the user has turned on the `send_crash_reports` preference.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::CommandError;
+use crate::net::Traced;
+use crate::presence::AuthState;
+use crate::prefs;
+
+pub const SEND_CRASH_REPORTS: &str = "send_crash_reports";
+
+const CRASH_FILE: &str = "crash.json";
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where the hook writes. Set in setup; until then panics go to the temp dir.
+static CRASH_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CrashReport {
+    message: String,
+    location: Option<String>,
+    backtrace: String,
+    version: String,
+    os: String,
+    arch: String,
+    timestamp: u64,
+}
+
+fn crash_path() -> PathBuf {
+    CRASH_DIR
+        .get()
+        .cloned()
+        .unwrap_or_else(std::env::temp_dir)
+        .join(CRASH_FILE)
+}
+
+/// Everywhere a report can be: the log dir, and the temp dir a panic
+/// before `init` went to.
+fn crash_paths() -> Vec<PathBuf> {
+    let mut paths = vec![crash_path()];
+    let early = std::env::temp_dir().join(CRASH_FILE);
+    if !paths.contains(&early) {
+        paths.push(early);
+    }
+    paths
+}
+
+/// Chain a hook in front of the default one. Everything in here is
+/// best-effort: a failed write is dropped rather than risking a double panic.
+pub fn install_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".into());
+        let report = CrashReport {
+            message,
+            location: info.location().map(|l| l.to_string()),
+            backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+            version: env!("CARGO_PKG_VERSION").into(),
+            os: std::env::consts::OS.into(),
+            arch: std::env::consts::ARCH.into(),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        };
+        if let Ok(json) = serde_json::to_string_pretty(&report) {
+            let path = crash_path();
+            if let Some(dir) = path.parent() {
+                let _ = std::fs::create_dir_all(dir);
+            }
+            let _ = std::fs::write(path, json);
+        }
+        previous(info);
+    }));
+}
+
+/// The most recent report in any of `crash_paths`.
+fn read_report() -> Option<CrashReport> {
+    crash_paths()
+        .iter()
+        .filter_map(|path| {
+            let json = std::fs::read_to_string(path).ok()?;
+            serde_json::from_str::<CrashReport>(&json).ok()
+        })
+        .max_by_key(|report| report.timestamp)
+}
+
+/// Point the hook at the app log dir and surface any report left by the
+/// previous run.
+pub fn init(app: &AppHandle) {
+    if let Ok(dir) = app.path().app_log_dir() {
+        let _ = CRASH_DIR.set(dir);
+    }
+    if !prefs::get::<bool>(app, SEND_CRASH_REPORTS).unwrap_or(false) {
+        return;
+    }
+    if let Some(report) = read_report() {
+        let _ = app.emit("crash://pending", report);
+    }
+}
+
+#[tauri::command]
+pub fn pending_crash_report() -> Option<CrashReport> {
+    read_report()
+}
+
+#[tauri::command]
+pub async fn submit_crash_report(app: AppHandle) -> Result<(), CommandError> {
+    if !prefs::get::<bool>(&app, SEND_CRASH_REPORTS).unwrap_or(false) {
+        return Err(CommandError::permission("crash_reports_disabled"));
+    }
+    let report = read_report().ok_or_else(|| CommandError::io("No crash report"))?;
+    let auth = app.state::<AuthState>().0.lock().unwrap().clone();
+    let info = auth.ok_or_else(|| CommandError::auth("Not signed in"))?;
+    let url = format!("{}/api/crash-reports", info.server_url);
+    let body = serde_json::to_string(&report)?;
+    let res = crate::net::client()
+        .map_err(CommandError::network)?
+        .post(&url)
+        .header("Content-Type", "application/json")
+        .bearer_auth(&info.token)
+        .body(body)
+        .timeout(UPLOAD_TIMEOUT)
+        .send_traced()
+        .await?;
+    let status = res.status();
+    if !status.is_success() {
+        crate::net::record_error("POST", res).await;
+        return Err(CommandError::Server {
+            status: status.as_u16(),
+            message: format!("upload failed: {status}"),
+        });
+    }
+    discard_crash_report()
+}
+
+#[tauri::command]
+pub fn discard_crash_report() -> Result<(), CommandError> {
+    for path in crash_paths() {
+        match std::fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(CommandError::io(format!("{}: {e}", path.display()))),
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_send_crash_reports(app: AppHandle, enabled: bool) -> Result<(), CommandError> {
+    prefs::set(&app, SEND_CRASH_REPORTS, &enabled)
+}
@@ -0,0 +1,55 @@
+// Deafen: silence everything coming in, and the mic with it. The mute state
+// from before is remembered, so undeafening puts it back rather than always
+// unmuting (an unmute in the meantime is kept). The state is managed next to
+// the tray's and not persisted; every launch starts hearing.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::CommandError;
+
+#[derive(Default)]
+pub struct DeafenState {
+    deafened: AtomicBool,
+    /// Whether the mic was muted when deafen went on.
+    muted_before: AtomicBool,
+}
+
+/// The one copy of the deafen flag; the tray and the volume watcher read it
+/// from here.
+pub fn deafened(app: &AppHandle) -> bool {
+    app.state::<DeafenState>().deafened.load(Ordering::Relaxed)
+}
+
+/// Turn deafen on or off: mic mute, the app's output (muted in the OS mixer
+/// on Windows; the webview handles its own playback everywhere via
+/// `audio://deafen`), tray tooltip, then the server. Setting the current
+/// state again is a no-op.
+#[tauri::command]
+pub async fn set_deafen(app: AppHandle, enabled: bool) -> Result<(), CommandError> {
+    let state = app.state::<DeafenState>();
+    if state.deafened.swap(enabled, Ordering::Relaxed) == enabled {
+        return Ok(());
+    }
+    if enabled {
+        state.muted_before.store(crate::shortcuts::muted(&app), Ordering::Relaxed);
+        crate::shortcuts::set_muted(&app, true);
+    } else if crate::shortcuts::muted(&app) {
+        // Unmuted from the tray or a shortcut while deafened: that stands.
+        crate::shortcuts::set_muted(&app, state.muted_before.load(Ordering::Relaxed));
+    }
+    let _ = app.emit("audio://deafen", enabled);
+    #[cfg(desktop)]
+    if let Err(e) = crate::tray::deafen_changed(&app) {
+        log::warn!("failed to update tray for deafen: {e}");
+    }
+    if let Err(e) = crate::volume::set_output_muted(enabled).await {
+        log::warn!("failed to mute app output: {e}");
+    }
+    crate::presence::report_deafened(&app, enabled).await
+}
+
+#[tauri::command]
+pub fn get_deafen(app: AppHandle) -> bool {
+    deafened(&app)
+}
@@ -0,0 +1,62 @@
+// distokoloshe:// invite links. With single-instance's deep-link feature a
+// link clicked while the app is running is forwarded here instead of
+// spawning a second process.
+
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_deep_link::DeepLinkExt;
+use url::Url;
+
+#[derive(Serialize, Clone)]
+pub struct DeepLink {
+    server: Option<String>,
+    channel: Option<String>,
+}
+
+/// Link the app was launched with, held until the webview asks for it since
+/// it can't have subscribed to the navigate event that early.
+pub struct PendingDeepLink(pub Mutex<Option<DeepLink>>);
+
+/// Parse `distokoloshe://join?server=<url>&channel=<id>`.
+fn parse(url: &Url) -> Option<DeepLink> {
+    if url.scheme() != "distokoloshe" || url.host_str() != Some("join") {
+        return None;
+    }
+    let mut link = DeepLink { server: None, channel: None };
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "server" => link.server = Some(value.into_owned()),
+            "channel" => link.channel = Some(value.into_owned()),
+            _ => {}
+        }
+    }
+    Some(link)
+}
+
+pub fn init(app: &AppHandle) {
+    // Installers register the scheme on Windows/macOS; AppImage and dev
+    // builds have to do it at runtime.
+    #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
+    if let Err(e) = app.deep_link().register_all() {
+        log::warn!("deep link registration failed: {e}");
+    }
+
+    if let Ok(Some(urls)) = app.deep_link().get_current() {
+        let link = urls.iter().find_map(parse);
+        *app.state::<PendingDeepLink>().0.lock().unwrap() = link;
+    }
+
+    let handle = app.clone();
+    app.deep_link().on_open_url(move |event| {
+        for link in event.urls().iter().filter_map(parse) {
+            crate::tray::show_main_window(&handle);
+            let _ = handle.emit("deeplink://navigate", link);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn take_pending_deep_link(state: tauri::State<'_, PendingDeepLink>) -> Option<DeepLink> {
+    state.0.lock().unwrap().take()
+}
@@ -0,0 +1,62 @@
+// Developer tools for support sessions. Debug builds always allow them;
+// release builds need the `devtools` cargo feature and, at runtime, the user
+// to have entered the support code once (persisted until turned off).
+
+use tauri::{AppHandle, WebviewWindow};
+
+use crate::error::CommandError;
+use crate::prefs;
+
+pub(crate) const ENABLED: &str = "devtools_enabled";
+
+/// Read out by support over the phone. Not a secret: it only stops users from
+/// wandering into devtools by accident.
+const SUPPORT_CODE: &str = "koloshe-support";
+
+fn allowed(app: &AppHandle) -> bool {
+    cfg!(debug_assertions) || prefs::get::<bool>(app, ENABLED) == Some(true)
+}
+
+#[tauri::command]
+pub fn enable_devtools(app: AppHandle, code: String) -> Result<(), CommandError> {
+    if code.trim() != SUPPORT_CODE {
+        return Err(CommandError::invalid_input("invalid support code"));
+    }
+    prefs::set(&app, ENABLED, &true)
+}
+
+#[tauri::command]
+pub fn disable_devtools(app: AppHandle, window: WebviewWindow) -> Result<(), CommandError> {
+    prefs::set(&app, ENABLED, &false)?;
+    close_devtools(window);
+    Ok(())
+}
+
+/// Open devtools for the calling window and emit `devtools://opened`, so the
+/// UI can warn against pasting anything into the console.
+#[tauri::command]
+pub fn open_devtools(app: AppHandle, window: WebviewWindow) -> Result<(), CommandError> {
+    if !allowed(&app) {
+        return Err(CommandError::permission("devtools_disabled"));
+    }
+    #[cfg(any(debug_assertions, feature = "devtools"))]
+    {
+        use tauri::Emitter;
+        window.open_devtools();
+        let _ = app.emit("devtools://opened", window.label());
+        Ok(())
+    }
+    #[cfg(not(any(debug_assertions, feature = "devtools")))]
+    {
+        let _ = window;
+        Err(CommandError::unsupported("devtools_unavailable"))
+    }
+}
+
+#[tauri::command]
+pub fn close_devtools(window: WebviewWindow) {
+    #[cfg(any(debug_assertions, feature = "devtools"))]
+    window.close_devtools();
+    #[cfg(not(any(debug_assertions, feature = "devtools")))]
+    let _ = window;
+}
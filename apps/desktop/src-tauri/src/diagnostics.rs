@@ -0,0 +1,78 @@
+// Environment summary for bug reports, returned as pretty JSON the UI can
+// put on the clipboard. Nothing secret goes in: the server URL is included,
+// the token is not. `get_bug_report` bundles it with a screenshot of the
+// main window for a one-click report.
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::error::CommandError;
+use crate::presence::AuthState;
+use crate::{LastCheck, PendingUpdate};
+
+#[derive(Serialize)]
+struct Diagnostics {
+    app_version: String,
+    tauri_version: &'static str,
+    webview_version: Option<String>,
+    os: &'static str,
+    os_family: &'static str,
+    arch: &'static str,
+    locale: Option<String>,
+    server_url: Option<String>,
+    signed_in: bool,
+    last_update_check: Option<LastCheck>,
+}
+
+#[derive(Serialize)]
+struct BugReport {
+    diagnostics: Diagnostics,
+    /// Base64 PNG of the main window.
+    screenshot: Option<String>,
+    /// Why there's no screenshot, when one was asked for.
+    screenshot_error: Option<CommandError>,
+}
+
+fn collect(app: &AppHandle) -> Diagnostics {
+    let auth = app.state::<AuthState>().0.lock().unwrap().clone();
+    Diagnostics {
+        app_version: app.package_info().version.to_string(),
+        tauri_version: tauri::VERSION,
+        webview_version: tauri::webview_version().ok(),
+        os: std::env::consts::OS,
+        os_family: std::env::consts::FAMILY,
+        arch: std::env::consts::ARCH,
+        locale: sys_locale::get_locale(),
+        server_url: crate::net::server_url(app),
+        signed_in: auth.is_some(),
+        // Includes the update channel that check used.
+        last_update_check: app.state::<PendingUpdate>().last_check.lock().unwrap().clone(),
+    }
+}
+
+#[tauri::command]
+pub fn get_diagnostics(app: AppHandle) -> Result<String, CommandError> {
+    Ok(serde_json::to_string_pretty(&collect(&app)).map_err(|e| e.to_string())?)
+}
+
+/// Diagnostics plus, unless `screenshot` is false, the main window as it
+/// looks now. A screenshot that can't be taken (content protection, window
+/// hidden, no permission) doesn't fail the report; the reason is included
+/// instead.
+#[tauri::command]
+pub async fn get_bug_report(app: AppHandle, screenshot: Option<bool>) -> Result<String, CommandError> {
+    let (screenshot, screenshot_error) = if screenshot == Some(false) {
+        (None, None)
+    } else {
+        match crate::capture::capture_main(&app).await {
+            Ok(png) => (Some(png), None),
+            Err(e) => (None, Some(e)),
+        }
+    };
+    let report = BugReport {
+        diagnostics: collect(&app),
+        screenshot,
+        screenshot_error,
+    };
+    Ok(serde_json::to_string(&report).map_err(|e| e.to_string())?)
+}
@@ -0,0 +1,605 @@
+// Resumable fetch of update bundles. The updater plugin downloads into memory
+// and starts from zero after any error, which hurts for large bundles on
+// flaky connections. Here the bytes are streamed to a `.part` file in the
+// cache dir instead, and a retry asks for the rest with a `Range` header.
+//
+// When the bundle the running version was installed from is still cached, a
+// delta against it is tried first (`/api/updates/delta/...`): a gzipped
+// bsdiff patch that rebuilds the new bundle locally. Anything wrong with the
+// delta falls back to the full download, and the rebuilt bundle has to pass
+// the same signature check as a downloaded one.
+//
+// Both can be held to a bandwidth cap so an update doesn't crowd out a call.
+//
+// Failures come back as `FetchError`, split by whether trying again (which
+// resumes from the `.part`) could help.
+//
+// Installing a new version moves the running version's bundle (and its
+// signature) aside as the rollback copy, kept in local data rather than the
+// cache so the OS doesn't clear it.
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_http::reqwest::{header, StatusCode};
+use tauri_plugin_updater::Update;
+
+use crate::net::Traced;
+
+/// Sidecar to a `.part` file, so bytes from a different bundle are never
+/// continued.
+#[derive(Serialize, Deserialize)]
+struct PartMeta {
+    url: String,
+    content_length: Option<u64>,
+}
+
+/// Why a fetch failed. A dropped connection or a server hiccup is
+/// `Transient`; anything another attempt would hit the same way (no space,
+/// a bad signature, a 404) is `Fatal`.
+#[derive(Debug)]
+pub enum FetchError {
+    Transient(String),
+    Fatal(String),
+}
+
+impl FetchError {
+    pub fn is_transient(&self) -> bool {
+        matches!(self, FetchError::Transient(_))
+    }
+}
+
+impl std::fmt::Display for FetchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FetchError::Transient(m) | FetchError::Fatal(m) => f.write_str(m),
+        }
+    }
+}
+
+/// Local failures (disk, client setup) aren't worth retrying.
+impl From<String> for FetchError {
+    fn from(message: String) -> Self {
+        FetchError::Fatal(message)
+    }
+}
+
+/// Statuses a later attempt might not get.
+fn transient_status(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::REQUEST_TIMEOUT || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+pub(crate) const BANDWIDTH_PREF: &str = "update_bandwidth_limit";
+/// Lowest cap accepted, so a typo can't stall an update for days.
+const MIN_BANDWIDTH: u64 = 16 * 1024;
+
+/// Paces a download to the saved cap by sleeping between chunks. The cap is
+/// re-read per chunk, so a change applies to a download in progress.
+struct Throttle {
+    started: Instant,
+    /// Bytes since `started`, reset whenever the cap changes.
+    bytes: u64,
+    limit: Option<u64>,
+}
+
+impl Throttle {
+    fn new() -> Self {
+        Throttle { started: Instant::now(), bytes: 0, limit: None }
+    }
+
+    async fn pace(&mut self, app: &AppHandle, len: usize) {
+        let limit = crate::prefs::get::<u64>(app, BANDWIDTH_PREF);
+        if limit != self.limit {
+            *self = Throttle { limit, ..Throttle::new() };
+        }
+        self.bytes += len as u64;
+        let Some(limit) = self.limit else { return };
+        let due = Duration::from_secs_f64(self.bytes as f64 / limit as f64);
+        if let Some(wait) = due.checked_sub(self.started.elapsed()) {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Cap update downloads at `bytes_per_sec` (at least 16 KiB/s), or lift the
+/// cap with `None`.
+#[tauri::command]
+pub fn set_download_bandwidth_limit(
+    app: AppHandle,
+    bytes_per_sec: Option<u64>,
+) -> Result<(), crate::error::CommandError> {
+    match bytes_per_sec {
+        Some(limit) => crate::prefs::set(&app, BANDWIDTH_PREF, &limit.max(MIN_BANDWIDTH)),
+        None => crate::prefs::remove(&app, BANDWIDTH_PREF),
+    }
+}
+
+fn updates_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_cache_dir()
+        .map(|d| d.join("updates"))
+        .map_err(|e| e.to_string())
+}
+
+fn base_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_cache_dir()
+        .map(|d| d.join("update-base"))
+        .map_err(|e| e.to_string())
+}
+
+fn rollback_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_local_data_dir()
+        .map(|d| d.join("update-rollback"))
+        .map_err(|e| e.to_string())
+}
+
+/// Upper bound on a rebuilt bundle, so a corrupt patch can't eat the disk.
+const MAX_BUNDLE_BYTES: u64 = 1 << 30;
+
+fn discard(part: &Path, meta: &Path) {
+    let _ = std::fs::remove_file(part);
+    let _ = std::fs::remove_file(meta);
+}
+
+/// Drop partials left behind by versions other than `keep`.
+fn prune(dir: &Path, keep: &str) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.file_stem().and_then(|s| s.to_str()) != Some(keep) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Free space available to us on the volume holding `dir`.
+#[cfg(windows)]
+fn free_space(dir: &Path) -> Result<u64, String> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+    let wide: Vec<u16> = dir.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut available = 0u64;
+    // SAFETY: `wide` is NUL-terminated and outlives the call; the other
+    // out-params are optional and passed as null.
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(wide.as_ptr(), &mut available, std::ptr::null_mut(), std::ptr::null_mut())
+    };
+    if ok == 0 {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+    Ok(available)
+}
+
+#[cfg(unix)]
+fn free_space(dir: &Path) -> Result<u64, String> {
+    use std::os::unix::ffi::OsStrExt;
+    let path = std::ffi::CString::new(dir.as_os_str().as_bytes()).map_err(|e| e.to_string())?;
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `path` is a valid C string and `stat` is only read on success.
+    if unsafe { libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+    let stat = unsafe { stat.assume_init() };
+    #[allow(clippy::unnecessary_cast)]
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+/// The least free space across where the bundle is downloaded and where the
+/// installer unpacks it (the temp dir).
+pub fn available_space(app: &AppHandle) -> Result<u64, String> {
+    let dir = updates_dir(app)?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(free_space(&dir)?.min(free_space(&std::env::temp_dir())?))
+}
+
+/// `Err("insufficient_disk_space: ...")` unless `required` bytes fit.
+pub fn ensure_space(app: &AppHandle, required: u64) -> Result<(), String> {
+    let available = available_space(app)?;
+    if available < required {
+        return Err(format!(
+            "insufficient_disk_space: need {} more bytes",
+            required - available
+        ));
+    }
+    Ok(())
+}
+
+/// Total size from a `Content-Range: bytes start-end/total` header.
+fn range_total(value: &header::HeaderValue) -> Option<u64> {
+    value.to_str().ok()?.rsplit('/').next()?.parse().ok()
+}
+
+/// Check `data` against the release's minisign signature, the same way the
+/// updater does before installing. Fails with `signature_invalid: ...`.
+fn verify(data: &[u8], signature: &str, pubkey: &str) -> Result<(), String> {
+    let decode = |s: &str| -> Result<String, String> {
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(s)
+            .map_err(|e| e.to_string())?;
+        String::from_utf8(bytes).map_err(|e| e.to_string())
+    };
+    let check = || -> Result<(), String> {
+        let key = minisign_verify::PublicKey::decode(&decode(pubkey)?).map_err(|e| e.to_string())?;
+        let sig = minisign_verify::Signature::decode(&decode(signature)?).map_err(|e| e.to_string())?;
+        key.verify(data, &sig, true).map_err(|e| e.to_string())
+    };
+    check().map_err(|e| format!("signature_invalid: {e}"))
+}
+
+/// Keep the bundle `version` is being installed from, and its signature, as
+/// the base for the next update's delta. Only the latest is kept.
+pub fn save_base(app: &AppHandle, version: &str, bytes: &[u8], signature: &str) -> Result<(), String> {
+    let dir = base_dir(app)?;
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    std::fs::write(dir.join(format!("{version}.bin")), bytes).map_err(|e| e.to_string())?;
+    std::fs::write(dir.join(format!("{version}.sig")), signature).map_err(|e| e.to_string())
+}
+
+/// Move the running `version`'s bundle from the delta base to the rollback
+/// slot, replacing whatever was there. `Ok(false)` if it isn't cached (the
+/// running version came from an installer, or predates keeping signatures).
+pub fn keep_for_rollback(app: &AppHandle, version: &str) -> Result<bool, String> {
+    let base = base_dir(app)?;
+    let (bin, sig) = (base.join(format!("{version}.bin")), base.join(format!("{version}.sig")));
+    if !bin.exists() || !sig.exists() {
+        return Ok(false);
+    }
+    let dir = rollback_dir(app)?;
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    // A rename can't cross volumes (cache and data dirs may differ), so copy.
+    std::fs::copy(&bin, dir.join(format!("{version}.bin"))).map_err(|e| e.to_string())?;
+    std::fs::copy(&sig, dir.join(format!("{version}.sig"))).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// The rollback copy of `version` with its signature, checked against
+/// `pubkey` so a tampered file is never installed.
+pub fn rollback_bundle(app: &AppHandle, version: &str, pubkey: &str) -> Result<(Vec<u8>, String), String> {
+    let dir = rollback_dir(app)?;
+    let bytes = std::fs::read(dir.join(format!("{version}.bin"))).map_err(|e| format!("rollback_missing: {e}"))?;
+    let signature =
+        std::fs::read_to_string(dir.join(format!("{version}.sig"))).map_err(|e| format!("rollback_missing: {e}"))?;
+    verify(&bytes, &signature, pubkey)?;
+    Ok((bytes, signature))
+}
+
+/// Drop the rollback copy once it's been used.
+pub fn clear_rollback(app: &AppHandle) {
+    if let Ok(dir) = rollback_dir(app) {
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
+
+/// Signed little-endian integer as bsdiff writes it: magnitude in the low 63
+/// bits, sign in the top one.
+fn offtin(buf: &[u8]) -> i64 {
+    let raw = u64::from_le_bytes(buf.try_into().unwrap());
+    let magnitude = (raw & !(1 << 63)) as i64;
+    if raw & (1 << 63) == 0 {
+        magnitude
+    } else {
+        -magnitude
+    }
+}
+
+/// Apply a raw (uncompressed) bsdiff patch to `old`. The patch is a run of
+/// `[mix_len, copy_len, seek]` headers, each followed by `mix_len` bytes to
+/// add to the old data and `copy_len` bytes to insert as-is.
+fn bspatch(old: &[u8], patch: &[u8]) -> Result<Vec<u8>, String> {
+    let corrupt = || "corrupt delta".to_string();
+    let mut new = Vec::with_capacity(old.len());
+    let mut old_pos: i64 = 0;
+    let mut rest = patch;
+    while !rest.is_empty() {
+        if rest.len() < 24 {
+            return Err(corrupt());
+        }
+        let mix_len = usize::try_from(offtin(&rest[0..8])).map_err(|_| corrupt())?;
+        let copy_len = usize::try_from(offtin(&rest[8..16])).map_err(|_| corrupt())?;
+        let seek = offtin(&rest[16..24]);
+        rest = &rest[24..];
+        if rest.len() < mix_len.saturating_add(copy_len)
+            || (new.len() + mix_len + copy_len) as u64 > MAX_BUNDLE_BYTES
+        {
+            return Err(corrupt());
+        }
+        let start = usize::try_from(old_pos).map_err(|_| corrupt())?;
+        let old_mix = old.get(start..start + mix_len).ok_or_else(corrupt)?;
+        new.extend(rest[..mix_len].iter().zip(old_mix).map(|(d, o)| d.wrapping_add(*o)));
+        new.extend_from_slice(&rest[mix_len..mix_len + copy_len]);
+        rest = &rest[mix_len + copy_len..];
+        old_pos = old_pos
+            .checked_add(mix_len as i64)
+            .and_then(|p| p.checked_add(seek))
+            .ok_or_else(corrupt)?;
+    }
+    Ok(new)
+}
+
+/// Try to rebuild `update`'s bundle from the cached base and a delta.
+/// `Ok(None)` means there's no base or the server has no delta for this pair;
+/// the caller falls back to the full download on any error too.
+async fn fetch_delta(
+    app: &AppHandle,
+    update: &Update,
+    pubkey: &str,
+    on_progress: &mut impl FnMut(u64, Option<u64>),
+) -> Result<Option<Vec<u8>>, String> {
+    let current = app.package_info().version.to_string();
+    let Ok(old) = std::fs::read(base_dir(app)?.join(format!("{current}.bin"))) else {
+        return Ok(None);
+    };
+    let Some(server) = crate::net::server_url(app) else { return Ok(None) };
+    if !crate::capabilities::supports(&server, crate::capabilities::Feature::DeltaUpdates) {
+        return Ok(None);
+    }
+    let (target, arch) = crate::update_target();
+    let url = format!("{server}/api/updates/delta/{target}/{arch}/{current}/{}", update.version);
+
+    let mut request = crate::net::client()?
+        .get(&url)
+        .headers(update.headers.clone())
+        .header(header::ACCEPT, "application/octet-stream");
+    if let Some(timeout) = update.timeout {
+        request = request.timeout(timeout);
+    }
+    let mut response = request.send_traced().await.map_err(|e| e.to_string())?;
+    match response.status() {
+        StatusCode::OK => {}
+        StatusCode::NO_CONTENT | StatusCode::NOT_FOUND => return Ok(None),
+        s => return Err(format!("delta download failed: {s}")),
+    }
+    let content_length = response.content_length();
+    let mut patch = Vec::new();
+    let mut throttle = Throttle::new();
+    on_progress(0, content_length);
+    while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+        patch.extend_from_slice(&chunk);
+        on_progress(patch.len() as u64, content_length);
+        throttle.pace(app, chunk.len()).await;
+    }
+
+    let mut raw = Vec::new();
+    flate2::read::GzDecoder::new(patch.as_slice())
+        .take(MAX_BUNDLE_BYTES)
+        .read_to_end(&mut raw)
+        .map_err(|e| format!("corrupt delta: {e}"))?;
+    let bytes = bspatch(&old, &raw)?;
+    verify(&bytes, &update.signature, pubkey)?;
+    log::info!(
+        "rebuilt {} bundle from a {} byte delta",
+        update.version,
+        patch.len()
+    );
+    Ok(Some(bytes))
+}
+
+const PREFLIGHT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What the bundle URL answered, without downloading it.
+#[derive(Serialize)]
+pub struct Preflight {
+    status: u16,
+    reachable: bool,
+    content_length: Option<u64>,
+    content_type: Option<String>,
+    /// The size the manifest gives, if any.
+    expected_length: Option<u64>,
+    /// Both sizes are known and agree.
+    size_matches: Option<bool>,
+}
+
+/// Ask for `update`'s bundle headers: a HEAD, or, for servers that refuse
+/// HEAD, a one-byte ranged GET whose `Content-Range` carries the size.
+pub async fn preflight(update: &Update, expected_length: Option<u64>) -> Result<Preflight, String> {
+    let client = crate::net::client()?;
+    let url = update.download_url.as_str();
+    let mut response = client
+        .head(url)
+        .headers(update.headers.clone())
+        .timeout(PREFLIGHT_TIMEOUT)
+        .send_traced()
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut content_length = response
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok()?.parse().ok());
+    let mut method = "HEAD";
+    if matches!(response.status(), StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED) {
+        method = "GET";
+        response = client
+            .get(url)
+            .headers(update.headers.clone())
+            .header(header::RANGE, "bytes=0-0")
+            .timeout(PREFLIGHT_TIMEOUT)
+            .send_traced()
+            .await
+            .map_err(|e| e.to_string())?;
+        content_length = match response.status() {
+            StatusCode::PARTIAL_CONTENT => response.headers().get(header::CONTENT_RANGE).and_then(range_total),
+            _ => response.content_length(),
+        };
+    }
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let reachable = status.is_success();
+    if !reachable {
+        crate::net::record_error(method, response).await;
+    }
+    Ok(Preflight {
+        status: status.as_u16(),
+        reachable,
+        content_length,
+        content_type,
+        expected_length,
+        size_matches: expected_length.zip(content_length).map(|(expected, got)| expected == got),
+    })
+}
+
+/// Download `update`'s bundle, continuing a previous partial if there is one,
+/// and return it once the signature checks out. `on_progress` gets the bytes
+/// so far (including any resumed prefix) and the total size when known.
+/// Dropping the future mid-download keeps the partial for next time.
+pub async fn fetch(
+    app: &AppHandle,
+    update: &Update,
+    pubkey: &str,
+    mut on_progress: impl FnMut(u64, Option<u64>),
+) -> Result<Vec<u8>, FetchError> {
+    let dir = updates_dir(app)?;
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    prune(&dir, &update.version);
+    let part = dir.join(format!("{}.part", update.version));
+    let meta_path = dir.join(format!("{}.json", update.version));
+
+    let url = update.download_url.as_str();
+    let saved = std::fs::read_to_string(&meta_path)
+        .ok()
+        .and_then(|s| serde_json::from_str::<PartMeta>(&s).ok())
+        .filter(|m| m.url == url);
+    let mut offset = match &saved {
+        Some(_) => std::fs::metadata(&part).map_or(0, |m| m.len()),
+        None => 0,
+    };
+    let mut content_length = saved.and_then(|m| m.content_length);
+
+    // A partial means the delta already failed for this version.
+    if offset == 0 {
+        match fetch_delta(app, update, pubkey, &mut on_progress).await {
+            Ok(Some(bytes)) => {
+                let _ = app.emit("update://finished", ());
+                return Ok(bytes);
+            }
+            Ok(None) => {}
+            Err(e) => log::warn!("delta update failed, downloading the full bundle: {e}"),
+        }
+    }
+
+    // A previous attempt may have got every byte and failed afterwards.
+    if offset == 0 || content_length != Some(offset) {
+        let mut request = crate::net::client()?
+            .get(url)
+            .headers(update.headers.clone())
+            .header(header::ACCEPT, "application/octet-stream");
+        if let Some(timeout) = update.timeout {
+            request = request.timeout(timeout);
+        }
+        if offset > 0 {
+            request = request.header(header::RANGE, format!("bytes={offset}-"));
+        }
+        let mut response = request
+            .send_traced()
+            .await
+            .map_err(|e| FetchError::Transient(e.to_string()))?;
+        match response.status() {
+            StatusCode::PARTIAL_CONTENT => {
+                if let Some(total) = response.headers().get(header::CONTENT_RANGE).and_then(range_total) {
+                    content_length = Some(total);
+                }
+            }
+            // The server ignored the range: start over.
+            s if s.is_success() => {
+                offset = 0;
+                content_length = response.content_length();
+            }
+            StatusCode::RANGE_NOT_SATISFIABLE => {
+                // Gone, so the next attempt starts from zero.
+                discard(&part, &meta_path);
+                return Err(FetchError::Transient(
+                    "partial download no longer matches the server, retry to start over".into(),
+                ));
+            }
+            s => {
+                crate::net::record_error("GET", response).await;
+                let message = format!("update download failed: {s}");
+                return Err(if transient_status(s) {
+                    FetchError::Transient(message)
+                } else {
+                    FetchError::Fatal(message)
+                });
+            }
+        }
+        let meta = PartMeta { url: url.to_string(), content_length };
+        std::fs::write(&meta_path, serde_json::to_string(&meta).map_err(|e| e.to_string())?)
+            .map_err(|e| e.to_string())?;
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(offset > 0)
+            .truncate(offset == 0)
+            .open(&part)
+            .map_err(|e| e.to_string())?;
+        let mut downloaded = offset;
+        let mut throttle = Throttle::new();
+        on_progress(downloaded, content_length);
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| FetchError::Transient(e.to_string()))?
+        {
+            file.write_all(&chunk).map_err(|e| e.to_string())?;
+            downloaded += chunk.len() as u64;
+            on_progress(downloaded, content_length);
+            throttle.pace(app, chunk.len()).await;
+        }
+        file.flush().map_err(|e| e.to_string())?;
+    }
+
+    let bytes = std::fs::read(&part).map_err(|e| e.to_string())?;
+    if let Some(total) = content_length {
+        if (bytes.len() as u64) < total {
+            return Err(FetchError::Transient("update download interrupted".into()));
+        }
+    }
+    let verified = verify(&bytes, &update.signature, pubkey);
+    discard(&part, &meta_path);
+    if let Err(e) = verified {
+        log::error!("update signature verification failed: {e}");
+        return Err(FetchError::Fatal(e));
+    }
+    let _ = app.emit("update://finished", ());
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::verify;
+
+    // A throwaway minisign key and its signature over `BUNDLE`, in the
+    // base64-wrapped form tauri.conf.json and the manifest carry them.
+    const PUBKEY: &str = "dW50cnVzdGVkIGNvbW1lbnQ6IG1pbmlzaWduIHB1YmxpYyBrZXkgMkE4M0JGQ0I5NDc3MTI0RgpSV1JQRW5lVXk3K0RLdHNxOFZOTHg0Zk13UkZPamFvcjJYeEIvTHp5TisxQnJhcUU5NkJ5QXNxQgo=";
+    const SIGNATURE: &str = "dW50cnVzdGVkIGNvbW1lbnQ6IHNpZ25hdHVyZSBmcm9tIHRhdXJpIHNlY3JldCBrZXkKUlVSUEVuZVV5NytES3J0VUhsc1ZxYVZUUmI3aVlyeDJxYkhzNE9aSkhvSG5aUnZUaGhPdEN0ZWdJbVdIcUwrUTBMQTZscFJDaFdCaHBVRHR5VWhaQy9jSUszWDVEQStRMXc0PQp0cnVzdGVkIGNvbW1lbnQ6IHRpbWVzdGFtcDoxNzYwMDAwMDAwCWZpbGU6YnVuZGxlLnRhci5negloYXNoZWQKcEt2Nk04RlZtVEZ0dEtjMnQ1Mm1oeUN0Y2tDQUlyQXZleFBmdkJ2TFMva2JtbDVvTFh5KzIxTUl1WlI5Qk9KOW1hWXEzWWtjVTJBRGNkbFFnbnY0QXc9PQo=";
+    const BUNDLE: &[u8] = b"distokoloshe test bundle\n";
+
+    #[test]
+    fn accepts_signed_bundle() {
+        assert_eq!(verify(BUNDLE, SIGNATURE, PUBKEY), Ok(()));
+    }
+
+    #[test]
+    fn rejects_modified_bundle() {
+        let mut tampered = BUNDLE.to_vec();
+        tampered[0] ^= 1;
+        let err = verify(&tampered, SIGNATURE, PUBKEY).unwrap_err();
+        assert!(err.starts_with("signature_invalid:"), "{err}");
+    }
+
+    #[test]
+    fn rejects_truncated_bundle() {
+        let err = verify(&BUNDLE[..BUNDLE.len() - 1], SIGNATURE, PUBKEY).unwrap_err();
+        assert!(err.starts_with("signature_invalid:"), "{err}");
+    }
+}
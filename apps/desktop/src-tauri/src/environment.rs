@@ -0,0 +1,210 @@
+// Best-effort detection of the environment the app is running in: virtual
+// machine, container, app sandbox, CI, and whether there's a display and an
+// audio server at all. All of it is heuristic (vendor strings, marker files,
+// environment variables) and meant for diagnostics and safe defaults, never
+// for anything security-related; a VM can look like bare metal and the
+// reverse. Headless runs skip the GPU path and the audio monitors, which are
+// what crash on automated runs.
+
+use serde::Serialize;
+use std::sync::OnceLock;
+
+static DETECTED: OnceLock<Environment> = OnceLock::new();
+
+#[derive(Serialize, Clone)]
+pub struct Environment {
+    /// Always true: everything below is a guess.
+    best_effort: bool,
+    /// The hypervisor, when one was recognised.
+    vm: Option<String>,
+    /// Docker, Podman, LXC or Kubernetes.
+    container: bool,
+    /// `flatpak`, `snap` or `windows_sandbox`.
+    sandbox: Option<&'static str>,
+    ci: bool,
+    display: bool,
+    /// An audio device was there at launch.
+    audio: bool,
+    /// No display, or running under CI.
+    headless: bool,
+}
+
+/// Hypervisor names to look for in firmware vendor/product strings.
+#[cfg_attr(target_os = "macos", allow(dead_code))]
+const VM_VENDORS: &[(&str, &str)] = &[
+    ("vmware", "VMware"),
+    ("virtualbox", "VirtualBox"),
+    ("innotek", "VirtualBox"),
+    ("qemu", "QEMU"),
+    ("kvm", "KVM"),
+    ("xen", "Xen"),
+    ("parallels", "Parallels"),
+    ("virtual machine", "Hyper-V"),
+    ("bochs", "Bochs"),
+    ("amazon ec2", "Amazon EC2"),
+    ("google compute engine", "Google Compute Engine"),
+];
+
+#[cfg_attr(target_os = "macos", allow(dead_code))]
+fn vm_from_strings(strings: &[String]) -> Option<String> {
+    let joined = strings.join(" ").to_ascii_lowercase();
+    VM_VENDORS
+        .iter()
+        .find(|(needle, _)| joined.contains(needle))
+        .map(|(_, name)| name.to_string())
+}
+
+fn ci() -> bool {
+    ["CI", "GITHUB_ACTIONS", "GITLAB_CI", "BUILDKITE", "TF_BUILD", "JENKINS_URL"]
+        .iter()
+        .any(|var| std::env::var_os(var).is_some_and(|v| !v.is_empty() && v != "false"))
+}
+
+#[cfg(target_os = "linux")]
+fn display() -> bool {
+    ["WAYLAND_DISPLAY", "DISPLAY"]
+        .iter()
+        .any(|var| std::env::var_os(var).is_some_and(|v| !v.is_empty()))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn display() -> bool {
+    true
+}
+
+#[cfg(target_os = "linux")]
+fn vm() -> Option<String> {
+    let read = |p: &str| std::fs::read_to_string(p).unwrap_or_default().trim().to_string();
+    let strings = ["sys_vendor", "product_name", "board_vendor", "bios_vendor"]
+        .map(|f| read(&format!("/sys/class/dmi/id/{f}")));
+    if let Some(name) = vm_from_strings(&strings) {
+        return Some(name);
+    }
+    // The CPU flag is set under any hypervisor, even unrecognised ones.
+    read("/proc/cpuinfo")
+        .lines()
+        .any(|l| l.starts_with("flags") && l.split_whitespace().any(|f| f == "hypervisor"))
+        .then(|| "unknown".to_string())
+}
+
+#[cfg(windows)]
+fn vm() -> Option<String> {
+    use windows_sys::Win32::Foundation::ERROR_SUCCESS;
+    use windows_sys::Win32::System::Registry::{RegGetValueW, HKEY_LOCAL_MACHINE, RRF_RT_REG_SZ};
+
+    let wide = |s: &str| s.encode_utf16().chain(Some(0)).collect::<Vec<u16>>();
+    let key = wide(r"HARDWARE\DESCRIPTION\System\BIOS");
+    let read = |name: &str| {
+        let name = wide(name);
+        let mut buf = [0u16; 128];
+        let mut size = std::mem::size_of_val(&buf) as u32;
+        // SAFETY: NUL-terminated key and value names; `size` is the buffer's
+        // length in bytes and comes back as the bytes written.
+        let status = unsafe {
+            RegGetValueW(
+                HKEY_LOCAL_MACHINE,
+                key.as_ptr(),
+                name.as_ptr(),
+                RRF_RT_REG_SZ,
+                std::ptr::null_mut(),
+                buf.as_mut_ptr().cast(),
+                &mut size,
+            )
+        };
+        if status != ERROR_SUCCESS {
+            return String::new();
+        }
+        let len = (size as usize / 2).saturating_sub(1);
+        String::from_utf16_lossy(&buf[..len.min(buf.len())])
+    };
+    vm_from_strings(&[read("SystemManufacturer"), read("SystemProductName")])
+}
+
+#[cfg(target_os = "macos")]
+fn vm() -> Option<String> {
+    let output = std::process::Command::new("sysctl")
+        .args(["-n", "kern.hv_vmm_present"])
+        .output()
+        .ok()?;
+    (String::from_utf8_lossy(&output.stdout).trim() == "1").then(|| "unknown".to_string())
+}
+
+#[cfg(not(any(windows, target_os = "linux", target_os = "macos")))]
+fn vm() -> Option<String> {
+    None
+}
+
+fn container() -> bool {
+    if cfg!(not(target_os = "linux")) {
+        return false;
+    }
+    if std::path::Path::new("/.dockerenv").exists() || std::path::Path::new("/run/.containerenv").exists() {
+        return true;
+    }
+    let cgroup = std::fs::read_to_string("/proc/1/cgroup").unwrap_or_default();
+    ["docker", "kubepods", "lxc", "containerd"].iter().any(|m| cgroup.contains(m))
+}
+
+fn sandbox() -> Option<&'static str> {
+    if std::env::var_os("FLATPAK_ID").is_some() || std::path::Path::new("/.flatpak-info").exists() {
+        Some("flatpak")
+    } else if std::env::var_os("SNAP").is_some() {
+        Some("snap")
+    } else if cfg!(windows) && std::env::var("USERNAME").is_ok_and(|u| u == "WDAGUtilityAccount") {
+        Some("windows_sandbox")
+    } else {
+        None
+    }
+}
+
+fn audio() -> bool {
+    use cpal::traits::HostTrait;
+    let host = cpal::default_host();
+    host.default_output_device().is_some() || host.default_input_device().is_some()
+}
+
+/// No display to draw on or running under CI. Cheap enough to call before
+/// the webview exists, which the GPU switch needs.
+pub fn headless() -> bool {
+    !display() || ci()
+}
+
+/// Run the full detection once and keep it. Called from setup.
+pub fn detect() -> &'static Environment {
+    DETECTED.get_or_init(|| {
+        let env = Environment {
+            best_effort: true,
+            vm: vm(),
+            container: container(),
+            sandbox: sandbox(),
+            ci: ci(),
+            display: display(),
+            audio: audio(),
+            headless: headless(),
+        };
+        if env.headless || !env.audio || env.vm.is_some() {
+            log::info!(
+                "environment: vm={:?} container={} ci={} display={} audio={}",
+                env.vm,
+                env.container,
+                env.ci,
+                env.display,
+                env.audio
+            );
+        }
+        env
+    })
+}
+
+/// Whether the native audio monitors (device watcher, volume watcher, VAD)
+/// should run. Only headless runs go without: a machine that started with
+/// no device can have one plugged in later, which the watcher is there to
+/// notice, and each monitor copes with there being none.
+pub fn audio_monitors_enabled() -> bool {
+    !detect().headless
+}
+
+#[tauri::command]
+pub fn get_environment() -> Environment {
+    detect().clone()
+}
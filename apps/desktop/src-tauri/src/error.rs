@@ -0,0 +1,236 @@
+// Shared command error, serialized as `{ kind, message, ... }` so the webview
+// can branch on `kind` instead of matching message text. Every command that
+// can fail returns it; helpers that still deal in strings convert with `?`,
+// landing in `other` until someone gives them a kind.
+
+use serde::Serialize;
+
+use crate::media::MediaKind;
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CommandError {
+    /// No response from the server: offline, DNS, TLS, timeout, proxy.
+    Network { message: String },
+    /// The server didn't answer in time.
+    Timeout { message: String },
+    /// Not signed in, or the server refused the token (401/403).
+    Auth { message: String },
+    /// The OS or a policy won't allow it.
+    Permission { message: String },
+    /// Microphone or camera access is turned off for us; only the user can
+    /// turn it back on, at `settings` (a URL the opener plugin can open).
+    MediaDenied { media: MediaKind, settings: &'static str, message: String },
+    /// Microphone or camera access is blocked by policy.
+    MediaRestricted { media: MediaKind, message: String },
+    /// A shortcut bound to another of our actions (`action` set) or grabbed
+    /// by another app.
+    AlreadyRegistered { action: Option<String>, message: String },
+    /// The OS refused a shortcut, e.g. a system-wide one.
+    ReservedByOs { message: String },
+    /// A Wayland session refused a shortcut grab; global keys there need the
+    /// compositor's GlobalShortcuts portal, which we don't drive yet.
+    WaylandPortalRequired { message: String },
+    /// Nothing usable answered: at a server URL being checked, or for the
+    /// ICE servers a call needs.
+    Unreachable { message: String },
+    /// A server URL with a scheme other than http or https.
+    BadScheme { message: String },
+    /// Something answered at a server URL, but not a distokoloshe server.
+    NotADistokolosheServer { message: String },
+    /// The server answered with some other error status.
+    Server { status: u16, message: String },
+    /// The server answered, but not with anything we understand.
+    Parse { message: String },
+    /// Held back by the beacon rate limit without reaching the network.
+    RateLimited { message: String },
+    /// Downloading, verifying or installing an update failed.
+    Updater { message: String },
+    /// A large update held back on a low battery; retry with `force`.
+    OnBattery { message: String },
+    /// The update is installed but relaunching into it can't work.
+    RestartFailed { message: String },
+    /// Not available on this OS or in this environment.
+    Unsupported { message: String },
+    /// Local files, prefs or devices.
+    Io { message: String },
+    /// No OS secret store to keep tokens in, e.g. Linux without a keyring
+    /// daemon.
+    SecretStoreUnavailable { message: String },
+    InvalidInput { message: String },
+    Other { message: String },
+}
+
+impl CommandError {
+    pub fn network(e: impl ToString) -> Self {
+        CommandError::Network { message: e.to_string() }
+    }
+
+    pub fn auth(e: impl ToString) -> Self {
+        CommandError::Auth { message: e.to_string() }
+    }
+
+    pub fn permission(e: impl ToString) -> Self {
+        CommandError::Permission { message: e.to_string() }
+    }
+
+    pub fn timeout(e: impl ToString) -> Self {
+        CommandError::Timeout { message: e.to_string() }
+    }
+
+    pub fn unreachable(e: impl ToString) -> Self {
+        CommandError::Unreachable { message: e.to_string() }
+    }
+
+    pub fn updater(e: impl ToString) -> Self {
+        CommandError::Updater { message: e.to_string() }
+    }
+
+    pub fn unsupported(e: impl ToString) -> Self {
+        CommandError::Unsupported { message: e.to_string() }
+    }
+
+    pub fn io(e: impl ToString) -> Self {
+        CommandError::Io { message: e.to_string() }
+    }
+
+    pub fn invalid_input(e: impl ToString) -> Self {
+        CommandError::InvalidInput { message: e.to_string() }
+    }
+
+    /// Only failures to reach the server are worth retrying as-is.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, CommandError::Network { .. } | CommandError::Timeout { .. })
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            CommandError::Network { message }
+            | CommandError::Timeout { message }
+            | CommandError::Auth { message }
+            | CommandError::Permission { message }
+            | CommandError::MediaDenied { message, .. }
+            | CommandError::MediaRestricted { message, .. }
+            | CommandError::AlreadyRegistered { message, .. }
+            | CommandError::ReservedByOs { message }
+            | CommandError::WaylandPortalRequired { message }
+            | CommandError::Unreachable { message }
+            | CommandError::BadScheme { message }
+            | CommandError::NotADistokolosheServer { message }
+            | CommandError::Server { message, .. }
+            | CommandError::Parse { message }
+            | CommandError::RateLimited { message }
+            | CommandError::Updater { message }
+            | CommandError::OnBattery { message }
+            | CommandError::RestartFailed { message }
+            | CommandError::Unsupported { message }
+            | CommandError::Io { message }
+            | CommandError::SecretStoreUnavailable { message }
+            | CommandError::InvalidInput { message }
+            | CommandError::Other { message } => message,
+        }
+    }
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+/// Anything not yet categorized.
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::Other { message }
+    }
+}
+
+impl From<&str> for CommandError {
+    fn from(message: &str) -> Self {
+        CommandError::Other { message: message.to_string() }
+    }
+}
+
+/// For callers that still deal in strings.
+impl From<CommandError> for String {
+    fn from(e: CommandError) -> Self {
+        e.to_string()
+    }
+}
+
+impl From<std::io::Error> for CommandError {
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::PermissionDenied => CommandError::permission(e),
+            _ => CommandError::io(e),
+        }
+    }
+}
+
+impl From<serde_json::Error> for CommandError {
+    fn from(e: serde_json::Error) -> Self {
+        CommandError::Parse { message: e.to_string() }
+    }
+}
+
+impl From<tauri::Error> for CommandError {
+    fn from(e: tauri::Error) -> Self {
+        match e {
+            tauri::Error::Io(e) => e.into(),
+            e => CommandError::Other { message: e.to_string() },
+        }
+    }
+}
+
+impl From<tauri_plugin_http::reqwest::Error> for CommandError {
+    fn from(e: tauri_plugin_http::reqwest::Error) -> Self {
+        match e.status() {
+            Some(s) if s.as_u16() == 401 || s.as_u16() == 403 => CommandError::auth(e),
+            Some(s) => CommandError::Server { status: s.as_u16(), message: e.to_string() },
+            None => CommandError::network(e),
+        }
+    }
+}
+
+impl From<tauri_plugin_updater::Error> for CommandError {
+    fn from(e: tauri_plugin_updater::Error) -> Self {
+        use tauri_plugin_updater::Error;
+        let message = e.to_string();
+        match e {
+            Error::Reqwest(e) if e.is_timeout() => CommandError::Timeout { message },
+            Error::Reqwest(e) if e.is_decode() => CommandError::Parse { message },
+            Error::Reqwest(_) | Error::Network(_) => CommandError::Network { message },
+            Error::Serialization(_) | Error::Semver(_) | Error::TargetNotFound(_) | Error::TargetsNotFound(_) => {
+                CommandError::Parse { message }
+            }
+            Error::Io(e) => e.into(),
+            _ => CommandError::Updater { message },
+        }
+    }
+}
+
+impl From<cpal::DevicesError> for CommandError {
+    fn from(e: cpal::DevicesError) -> Self {
+        CommandError::io(e)
+    }
+}
+
+/// The plugin flattens global-hotkey's errors into strings, so classify on
+/// global-hotkey's message prefixes.
+impl From<tauri_plugin_global_shortcut::Error> for CommandError {
+    fn from(e: tauri_plugin_global_shortcut::Error) -> Self {
+        let message = e.to_string();
+        if message.starts_with("HotKey already registered") {
+            CommandError::AlreadyRegistered {
+                action: None,
+                message: "shortcut already registered by another application".into(),
+            }
+        } else if message.starts_with("Unable to register hotkey") {
+            CommandError::ReservedByOs { message }
+        } else {
+            CommandError::Other { message }
+        }
+    }
+}
@@ -0,0 +1,83 @@
+// Native file drops. The webview's own drop events differ by platform (and
+// never expose real paths), so drops are taken from Tauri's window event and
+// forwarded to the UI as `files://dropped` once they pass the upload limits.
+
+use serde::Serialize;
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter};
+
+const MAX_DROP_FILES: usize = 10;
+const MAX_DROP_BYTES: u64 = 100 * 1024 * 1024;
+
+#[derive(Serialize, Clone)]
+struct DroppedFile {
+    path: String,
+    name: String,
+    size: u64,
+    mime: String,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "snake_case")]
+enum RejectReason {
+    TooManyFiles,
+    TooLarge,
+}
+
+#[derive(Serialize, Clone)]
+struct DropRejected {
+    reason: RejectReason,
+    count: usize,
+    total_bytes: u64,
+    max_files: usize,
+    max_bytes: u64,
+}
+
+/// Handle a completed drop. Directories and unreadable paths are ignored;
+/// the whole drop is rejected if what's left exceeds the limits.
+pub fn on_drop(app: &AppHandle, paths: &[PathBuf]) {
+    let files: Vec<DroppedFile> = paths
+        .iter()
+        .filter_map(|path| {
+            let meta = std::fs::metadata(path).ok().filter(|m| m.is_file())?;
+            Some(DroppedFile {
+                path: path.to_string_lossy().into_owned(),
+                name: path.file_name()?.to_string_lossy().into_owned(),
+                size: meta.len(),
+                mime: mime_guess::from_path(path)
+                    .first_or_octet_stream()
+                    .essence_str()
+                    .to_string(),
+            })
+        })
+        .collect();
+    if files.is_empty() {
+        return;
+    }
+
+    let total_bytes = files.iter().map(|f| f.size).sum();
+    let reason = if files.len() > MAX_DROP_FILES {
+        Some(RejectReason::TooManyFiles)
+    } else if total_bytes > MAX_DROP_BYTES {
+        Some(RejectReason::TooLarge)
+    } else {
+        None
+    };
+    match reason {
+        Some(reason) => {
+            let _ = app.emit(
+                "files://rejected",
+                DropRejected {
+                    reason,
+                    count: files.len(),
+                    total_bytes,
+                    max_files: MAX_DROP_FILES,
+                    max_bytes: MAX_DROP_BYTES,
+                },
+            );
+        }
+        None => {
+            let _ = app.emit("files://dropped", files);
+        }
+    }
+}
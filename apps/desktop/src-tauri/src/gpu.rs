@@ -0,0 +1,87 @@
+// Hardware acceleration switch for users whose GPU drivers glitch the
+// webview. The webview reads its flags once, when it starts, so the pref is
+// applied from `run` before the builder and a change only lands on restart.
+// Honoured on Windows (WebView2 `--disable-gpu`) and Linux (WebKitGTK's
+// DMA-BUF renderer and compositing off); WKWebView on macOS has no switch.
+// Headless and CI runs get acceleration off whatever the pref says.
+
+use serde::Serialize;
+use std::sync::OnceLock;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::CommandError;
+use crate::prefs;
+
+pub(crate) const HARDWARE_ACCELERATION: &str = "hardware_acceleration";
+
+/// Whether acceleration was on when this process started its webview.
+static APPLIED: OnceLock<bool> = OnceLock::new();
+
+#[derive(Serialize, Clone)]
+pub struct HardwareAcceleration {
+    /// The saved preference (on unless turned off).
+    enabled: bool,
+    /// Off for this run regardless, because there's no display or it's CI.
+    disabled_by_environment: bool,
+    /// Whether this platform's webview acts on it at all.
+    supported: bool,
+    /// The saved preference differs from what the running webview got.
+    restart_required: bool,
+}
+
+/// The pref, read straight from the prefs file since there's no `AppHandle`
+/// this early. Same location as `app_config_dir`, which `identifier` (from
+/// tauri.conf.json) names.
+fn saved(identifier: &str) -> Option<bool> {
+    let path = dirs::config_dir()?.join(identifier).join(prefs::PREFS_FILE);
+    prefs::read_with_backup(&path).get(HARDWARE_ACCELERATION)?.as_bool()
+}
+
+/// Set the webview's environment from the saved pref. Call first thing in
+/// `run`, before any thread or window exists.
+pub fn apply_before_webview(identifier: &str) {
+    let enabled = saved(identifier).unwrap_or(true);
+    let _ = APPLIED.set(enabled);
+    if enabled && !crate::environment::headless() {
+        return;
+    }
+    // The variable replaces the arguments Tauri would pass, so keep its
+    // defaults alongside ours.
+    #[cfg(windows)]
+    std::env::set_var(
+        "WEBVIEW2_ADDITIONAL_BROWSER_ARGUMENTS",
+        "--disable-features=msWebOOUI,msPdfOOUI,msSmartScreenProtection --disable-gpu",
+    );
+    #[cfg(target_os = "linux")]
+    {
+        std::env::set_var("WEBKIT_DISABLE_DMABUF_RENDERER", "1");
+        std::env::set_var("WEBKIT_DISABLE_COMPOSITING_MODE", "1");
+    }
+}
+
+fn status(enabled: bool) -> HardwareAcceleration {
+    HardwareAcceleration {
+        enabled,
+        disabled_by_environment: enabled && crate::environment::headless(),
+        supported: cfg!(any(windows, target_os = "linux")),
+        restart_required: APPLIED.get().is_some_and(|applied| *applied != enabled),
+    }
+}
+
+#[tauri::command]
+pub fn get_hardware_acceleration(app: AppHandle) -> HardwareAcceleration {
+    status(prefs::get::<bool>(&app, HARDWARE_ACCELERATION).unwrap_or(true))
+}
+
+/// Save the choice for the next launch. Emits `app://restart_required` when
+/// it differs from what the running webview was started with, so the UI can
+/// offer a restart.
+#[tauri::command]
+pub fn set_hardware_acceleration(app: AppHandle, enabled: bool) -> Result<HardwareAcceleration, CommandError> {
+    prefs::set(&app, HARDWARE_ACCELERATION, &enabled)?;
+    let status = status(enabled);
+    if status.restart_required {
+        let _ = app.emit("app://restart_required", HARDWARE_ACCELERATION);
+    }
+    Ok(status)
+}
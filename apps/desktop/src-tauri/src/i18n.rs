@@ -0,0 +1,119 @@
+// Strings for the native UI (tray menu and tooltip, our own toasts and
+// messages), bundled from `locales/*.json`. The locale follows the OS unless
+// the user picks one with `set_locale`; keys missing from a translation fall
+// back to English.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::CommandError;
+use crate::prefs;
+
+pub(crate) const LOCALE_PREF: &str = "locale";
+const FALLBACK: &str = "en";
+
+const CATALOGS: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.json")),
+    ("af", include_str!("../locales/af.json")),
+    ("de", include_str!("../locales/de.json")),
+    ("es", include_str!("../locales/es.json")),
+    ("fr", include_str!("../locales/fr.json")),
+];
+
+pub struct I18n {
+    locale: Mutex<&'static str>,
+    /// English overlaid with the current locale's strings.
+    strings: Mutex<HashMap<String, String>>,
+}
+
+fn catalog(locale: &str) -> HashMap<String, String> {
+    CATALOGS
+        .iter()
+        .find(|(l, _)| *l == locale)
+        .and_then(|(_, json)| serde_json::from_str(json).ok())
+        .unwrap_or_default()
+}
+
+/// Best bundled match for a BCP 47 tag: the tag itself, then its primary
+/// language (`de-AT` -> `de`), then English.
+fn resolve(tag: &str) -> &'static str {
+    let tag = tag.replace('_', "-").to_ascii_lowercase();
+    let primary = tag.split('-').next().unwrap_or_default();
+    CATALOGS
+        .iter()
+        .map(|(l, _)| *l)
+        .find(|l| *l == tag)
+        .or_else(|| CATALOGS.iter().map(|(l, _)| *l).find(|l| *l == primary))
+        .unwrap_or(FALLBACK)
+}
+
+fn strings_for(locale: &str) -> HashMap<String, String> {
+    let mut strings = catalog(FALLBACK);
+    if locale != FALLBACK {
+        strings.extend(catalog(locale));
+    }
+    strings
+}
+
+fn preferred(app: &AppHandle) -> &'static str {
+    let tag = prefs::get::<String>(app, LOCALE_PREF).or_else(sys_locale::get_locale);
+    tag.as_deref().map_or(FALLBACK, resolve)
+}
+
+/// Load strings for the saved or OS locale. Managed from setup before the
+/// tray is built.
+pub fn init(app: &AppHandle) -> I18n {
+    let locale = preferred(app);
+    I18n {
+        locale: Mutex::new(locale),
+        strings: Mutex::new(strings_for(locale)),
+    }
+}
+
+/// The string for `key`, or the key itself if no catalog has it.
+pub fn t(app: &AppHandle, key: &str) -> String {
+    let i18n = app.state::<I18n>();
+    let strings = i18n.strings.lock().unwrap();
+    strings.get(key).cloned().unwrap_or_else(|| key.to_string())
+}
+
+/// `t` with `{name}` placeholders filled in.
+pub fn t_with(app: &AppHandle, key: &str, args: &[(&str, &str)]) -> String {
+    args.iter()
+        .fold(t(app, key), |s, (name, value)| s.replace(&format!("{{{name}}}"), value))
+}
+
+/// `t` for before the app exists (no managed state, no prefs): always the
+/// OS locale.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub fn t_system(key: &str) -> String {
+    let locale = sys_locale::get_locale().as_deref().map_or(FALLBACK, resolve);
+    strings_for(locale).remove(key).unwrap_or_else(|| key.to_string())
+}
+
+/// Switch the native UI's language. `None` goes back to following the OS.
+/// Returns the bundled locale actually used.
+#[tauri::command]
+pub fn set_locale(app: AppHandle, tag: Option<String>) -> Result<String, CommandError> {
+    match &tag {
+        Some(tag) => prefs::set(&app, LOCALE_PREF, tag)?,
+        None => prefs::remove(&app, LOCALE_PREF)?,
+    }
+    let locale = preferred(&app);
+    let i18n = app.state::<I18n>();
+    *i18n.locale.lock().unwrap() = locale;
+    *i18n.strings.lock().unwrap() = strings_for(locale);
+    #[cfg(desktop)]
+    if let Err(e) = crate::tray::relocalize(&app) {
+        log::warn!("failed to relocalize tray: {e}");
+    }
+    crate::quickactions::refresh(&app);
+    let _ = app.emit("locale://changed", locale);
+    Ok(locale.to_string())
+}
+
+#[tauri::command]
+pub fn get_locale(state: tauri::State<'_, I18n>) -> String {
+    state.locale.lock().unwrap().to_string()
+}
@@ -0,0 +1,112 @@
+// Away detection from the OS idle timer (time since last keyboard/mouse
+// input anywhere on the system, not just in our window).
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager};
+use user_idle::UserIdle;
+
+use crate::error::CommandError;
+use crate::presence::{self, AuthState};
+
+pub const DEFAULT_IDLE_SECS: u64 = 5 * 60;
+const MIN_IDLE_SECS: u64 = 30;
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Seconds the main window must stay unfocused before counting as away.
+/// Unset (the default) means blur never marks the user away.
+pub(crate) const AWAY_ON_BLUR_PREF: &str = "away_on_blur_secs";
+
+pub struct IdleState {
+    pub threshold_secs: AtomicU64,
+    pub away: AtomicBool,
+    /// Set while the push-to-talk key is held.
+    pub ptt_held: AtomicBool,
+    /// Set by the webview while the mic is live.
+    pub transmitting: AtomicBool,
+    /// Set once the window has been unfocused for the away-on-blur delay.
+    pub blur_away: AtomicBool,
+    pub blur_task: Mutex<Option<JoinHandle<()>>>,
+}
+
+/// Report a presence transition to the server and the webview.
+async fn set_away(app: &AppHandle, away: bool) {
+    let (event, signal) = if away {
+        ("presence://away", "away")
+    } else {
+        ("presence://active", "active")
+    };
+    let _ = app.emit(event, ());
+    let auth = app.state::<AuthState>().0.lock().unwrap().clone();
+    if let Some(info) = auth {
+        let _ = presence::post_event(&info, signal).await;
+    }
+}
+
+pub fn start(app: &AppHandle) {
+    crate::shutdown::spawn(app, |app| async move {
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let state = app.state::<IdleState>();
+            // Holding push-to-talk or talking counts as activity even
+            // without keyboard/mouse input.
+            let busy = state.ptt_held.load(Ordering::Relaxed)
+                || state.transmitting.load(Ordering::Relaxed);
+            let idle_secs = match UserIdle::get_time() {
+                Ok(t) => t.as_seconds(),
+                Err(_) => continue,
+            };
+            let idle = idle_secs >= state.threshold_secs.load(Ordering::Relaxed);
+            let away = !busy && (idle || state.blur_away.load(Ordering::Relaxed));
+            if state.away.swap(away, Ordering::Relaxed) != away {
+                set_away(&app, away).await;
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn set_idle_threshold(state: tauri::State<'_, IdleState>, seconds: u64) -> u64 {
+    let secs = seconds.max(MIN_IDLE_SECS);
+    state.threshold_secs.store(secs, Ordering::Relaxed);
+    secs
+}
+
+#[tauri::command]
+pub fn set_transmitting(state: tauri::State<'_, IdleState>, active: bool) {
+    state.transmitting.store(active, Ordering::Relaxed);
+}
+
+/// Main window focus changes. Always emits `presence://focus` or
+/// `presence://blur`; with the away-on-blur pref set, a blur that lasts that
+/// long marks the user away (never a leave) until focus returns.
+pub fn on_main_focus_changed(app: &AppHandle, focused: bool) {
+    let _ = app.emit(if focused { "presence://focus" } else { "presence://blur" }, ());
+    let state = app.state::<IdleState>();
+    if let Some(task) = state.blur_task.lock().unwrap().take() {
+        task.abort();
+    }
+    if focused {
+        // The poll loop notices and reports active on its next tick.
+        state.blur_away.store(false, Ordering::Relaxed);
+        return;
+    }
+    let Some(delay) = crate::prefs::get::<u64>(app, AWAY_ON_BLUR_PREF) else {
+        return;
+    };
+    let task = crate::shutdown::spawn(app, |app_for_task| async move {
+        tokio::time::sleep(Duration::from_secs(delay)).await;
+        app_for_task
+            .state::<IdleState>()
+            .blur_away
+            .store(true, Ordering::Relaxed);
+    });
+    *state.blur_task.lock().unwrap() = Some(task);
+}
+
+/// Set (or clear, to disable) the away-on-blur delay in seconds.
+#[tauri::command]
+pub fn set_away_on_blur(app: AppHandle, seconds: Option<u64>) -> Result<(), CommandError> {
+    crate::prefs::set(&app, AWAY_ON_BLUR_PREF, &seconds)
+}
@@ -0,0 +1,106 @@
+// Post-install check of what actually landed on disk. The signature covers
+// the downloaded bundle, not the copy the installer wrote out, so a write
+// that went wrong (full disk, failing drive, something else touching the
+// file) would otherwise only show as an app that won't start. Manifests can
+// carry `installed_sha256`, the hash of the installed main executable (the
+// AppImage itself on Linux), per platform entry or at the top level; when
+// they do, it's compared before relaunching and a mismatch keeps the
+// running copy up instead. The server fills it in for AppImages, which are
+// installed as downloaded, and for any installer whose release publishes an
+// `<asset>.installed-sha256` next to it.
+//
+// On Windows the installer takes over and the app exits inside `install`,
+// so there's no point at which this can run there.
+
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+/// The hash the manifest gives for the installed executable, lowercase hex.
+fn expected(update: &tauri_plugin_updater::Update) -> Option<String> {
+    crate::manifest_field(update, "installed_sha256")
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim().to_ascii_lowercase())
+}
+
+/// The file the update replaced: the AppImage rather than the binary inside
+/// its mount, otherwise the executable itself.
+fn installed_path(app: &AppHandle) -> Result<PathBuf, String> {
+    if let Some(appimage) = std::env::var_os("APPIMAGE").filter(|_| cfg!(target_os = "linux")) {
+        return Ok(PathBuf::from(appimage));
+    }
+    tauri::process::current_binary(&app.env()).map_err(|e| e.to_string())
+}
+
+fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file = std::fs::File::open(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf).map_err(|e| format!("{}: {e}", path.display()))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher.finalize().iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Re-hash the installed executable against the manifest. Fails with
+/// `verify_failed: ...`; passes when the manifest has no hash to check.
+pub fn verify_installed(app: &AppHandle, update: &tauri_plugin_updater::Update) -> Result<(), String> {
+    let Some(expected) = expected(update) else {
+        log::info!("update manifest has no installed_sha256, not verifying the install");
+        return Ok(());
+    };
+    let path = installed_path(app).map_err(|e| format!("verify_failed: {e}"))?;
+    check_file(&path, &expected)?;
+    log::info!("verified installed {} ({expected})", path.display());
+    Ok(())
+}
+
+/// `verify_failed: ...` unless the file at `path` hashes to `expected`.
+fn check_file(path: &Path, expected: &str) -> Result<(), String> {
+    let actual = sha256_file(path).map_err(|e| format!("verify_failed: {e}"))?;
+    if actual != expected {
+        return Err(format!(
+            "verify_failed: {} hashes to {actual}, the manifest says {expected}",
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // sha256("distokoloshe")
+    const CONTENT: &[u8] = b"distokoloshe";
+    const HASH: &str = "b83081464a4aa3fd54eb70adf89a323cf475f09fc84745711fb0e25df20fc648";
+
+    #[test]
+    fn intact_file_passes() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), CONTENT).unwrap();
+        assert_eq!(check_file(file.path(), HASH), Ok(()));
+    }
+
+    #[test]
+    fn corrupted_file_fails() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let mut corrupted = CONTENT.to_vec();
+        corrupted[3] ^= 0x20;
+        std::fs::write(file.path(), &corrupted).unwrap();
+        let err = check_file(file.path(), HASH).unwrap_err();
+        assert!(err.starts_with("verify_failed:"), "{err}");
+    }
+
+    #[test]
+    fn missing_file_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = check_file(&dir.path().join("gone"), HASH).unwrap_err();
+        assert!(err.starts_with("verify_failed:"), "{err}");
+    }
+}
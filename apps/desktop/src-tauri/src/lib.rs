@@ -1,162 +1,1543 @@
+mod accent;
+mod audio;
+mod auth;
+mod autostart;
+mod bundle;
+mod callstats;
+mod capabilities;
+mod capture;
+mod clipboard;
+mod clock;
+mod connectivity;
+mod crash;
+mod deafen;
+mod deeplink;
+mod devtools;
+mod diagnostics;
+mod download;
+mod environment;
+mod error;
+mod files;
+mod gpu;
+mod i18n;
+mod idle;
+mod integrity;
+mod lifecycle;
+mod links;
+mod logging;
+mod media;
+mod net;
+mod notify;
+mod outbox;
+mod pinning;
 mod pipe;
+mod power;
+mod prefs;
+mod presence;
+mod quality;
+mod quickactions;
+mod rtc;
+mod safemode;
+mod secrets;
+mod servers;
+mod settings;
+mod shortcuts;
+mod shutdown;
+mod sounds;
+mod stats;
+mod storage;
+mod stream;
+mod theme;
+mod throttle;
+mod tray;
+mod typing;
+mod update_prompt;
+mod update_window;
+mod vad;
+mod volume;
+mod webview;
+mod window;
+mod zoom;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64};
 use std::sync::Mutex;
-use tauri::Manager;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{Emitter, Manager};
 use tauri_plugin_updater::UpdaterExt;
 use tokio::sync::Mutex as TokioMutex;
+use tokio_util::sync::CancellationToken;
 use url::Url;
 
-// ── Auth state (synced from JS so Rust can send leave on window close) ──
-#[derive(Clone)]
-struct AuthInfo {
-    token: String,
+use error::CommandError;
+use net::Traced;
+
+// ── Update state ─────────────────────────────────────────
+pub(crate) struct PendingUpdate {
+    update: Mutex<Option<tauri_plugin_updater::Update>>,
+    // Cancelled by cancel_update. A download takes a fresh one when it starts
+    // unless the current one is still live, and waits on it throughout, so a
+    // cancel can't slip in between two awaits and be missed.
+    cancel: Mutex<CancellationToken>,
+    // Bundle fetched by install_update_on_quit, applied on the way out by
+    // install_deferred_update.
+    downloaded: Mutex<Option<Vec<u8>>>,
+    // Outcome of the most recent check, for diagnostics.
+    pub(crate) last_check: Mutex<Option<LastCheck>>,
+    // Set while an auto-install download runs, so overlapping checks don't
+    // start a second one.
+    auto_installing: AtomicBool,
+    // Held for the duration of a check. A call that had to wait for it
+    // returns that check's result instead of running its own.
+    checking: TokioMutex<()>,
+    // Bumped as each check finishes, so a waiter can tell one completed.
+    check_generation: AtomicU64,
+    check_result: Mutex<Option<Result<Option<UpdateInfo>, CommandError>>>,
+}
+
+#[derive(Serialize, Clone)]
+pub(crate) struct LastCheck {
+    channel: Option<String>,
+    /// Unix seconds.
+    checked_at: u64,
+    /// The available version, `None` when up to date.
+    result: Result<Option<String>, CommandError>,
+}
+
+#[derive(Serialize, Clone)]
+struct UpdateInfo {
+    version: String,
+    body: Option<String>,
+    /// `body` rendered from markdown and sanitized, safe to inject as HTML.
+    body_html: Option<String>,
+    content_length: Option<u64>,
+    pub_date: Option<String>,
+    /// Set by a `"mandatory": true` field in the update manifest.
+    mandatory: bool,
+    /// Server URL of the mirror that answered the check.
+    mirror: Option<String>,
+    /// This copy wasn't installed by an installer (portable or dev build),
+    /// so the update has to be downloaded and installed by hand.
+    manual_update_required: bool,
+}
+
+/// check_for_update_status's answer: `{ status: "up_to_date", .. }` or
+/// `{ status: "update_available", ..UpdateInfo }`.
+#[derive(Serialize, Clone)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum UpdateStatus {
+    /// The check ran and found nothing newer than this.
+    UpToDate { current_version: String },
+    UpdateAvailable(UpdateInfo),
+}
+
+/// Release notes come from the server, so treat them as untrusted: render the
+/// markdown, then strip anything ammonia doesn't allow (scripts, handlers,
+/// javascript: links).
+fn render_release_notes(markdown: &str) -> String {
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, pulldown_cmark::Parser::new(markdown));
+    ammonia::clean(&html)
+}
+
+/// A manifest field outside the updater's schema, from the platform entry
+/// that was picked (matched by URL) or else the top level.
+pub(crate) fn manifest_field<'a>(u: &'a tauri_plugin_updater::Update, key: &str) -> Option<&'a serde_json::Value> {
+    let manifest = &u.raw_json;
+    manifest["platforms"]
+        .as_object()
+        .and_then(|platforms| {
+            platforms
+                .values()
+                .find(|p| p["url"].as_str() == Some(u.download_url.as_str()))
+        })
+        .and_then(|p| p.get(key))
+        .or_else(|| manifest.get(key))
+}
+
+impl UpdateInfo {
+    fn from_update(u: &tauri_plugin_updater::Update) -> Self {
+        let manifest = &u.raw_json;
+        let content_length = manifest_field(u, "size").and_then(|v| v.as_u64());
+        let pub_date = u
+            .date
+            .and_then(|d| d.format(&time::format_description::well_known::Rfc3339).ok())
+            .or_else(|| manifest["pub_date"].as_str().map(str::to_string));
+        Self {
+            version: u.version.clone(),
+            body: u.body.clone(),
+            body_html: u.body.as_deref().map(render_release_notes),
+            content_length,
+            pub_date,
+            mandatory: manifest["mandatory"].as_bool().unwrap_or(false),
+            mirror: None,
+            manual_update_required: bundle::manual_update_required(),
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+struct UpdateProgress {
+    downloaded: u64,
+    content_length: Option<u64>,
+    /// 1-based; above 1 after a transient failure.
+    attempt: u32,
+}
+
+#[derive(Serialize, Clone)]
+struct UpdateRetry {
+    /// The attempt that just failed.
+    attempt: u32,
+    max_attempts: u32,
+    delay_secs: u64,
+    error: String,
+}
+
+const UPDATE_CHANNELS: &[&str] = &["stable", "beta", "nightly"];
+/// Minisign key release bundles are signed with. Pinned in the binary so
+/// verification doesn't depend on runtime config; keep in step with
+/// `plugins.updater.pubkey` in tauri.conf.json.
+const UPDATER_PUBKEY: &str = "dW50cnVzdGVkIGNvbW1lbnQ6IG1pbmlzaWduIHB1YmxpYyBrZXk6IEM3OEY4RkJCNDdBNkQ3MEEKUldRSzE2Wkh1NCtQeDdxQUNSK1owSGRGcjBMUU1oSEprUndyOHZMT3g2T2w5a2Z5ZlhPMy9DNXYK";
+const DEFAULT_UPDATE_TIMEOUT_SECS: u64 = 10;
+const UPDATE_CHECK_ATTEMPTS: u32 = 3;
+const UPDATE_RETRY_BASE: Duration = Duration::from_secs(1);
+/// Download attempts before giving up; each resumes where the last stopped.
+const UPDATE_DOWNLOAD_ATTEMPTS: u32 = 5;
+const UPDATE_DOWNLOAD_RETRY_BASE: Duration = Duration::from_secs(2);
+const UPDATE_DOWNLOAD_RETRY_MAX: Duration = Duration::from_secs(30);
+/// Bundles at least this big wait for the charger when the battery is low.
+const LARGE_UPDATE_BYTES: u64 = 50 * 1024 * 1024;
+pub(crate) const UPDATE_BATTERY_PREF: &str = "update_min_battery";
+const DEFAULT_UPDATE_MIN_BATTERY: u8 = 50;
+pub(crate) const AUTO_INSTALL_PREF: &str = "auto_install";
+/// Version whose bundle was kept when the running one was installed.
+pub(crate) const ROLLBACK_PREF: &str = "rollback_version";
+/// `1`/`true` or `0`/`false` forces auto-install on or off regardless of the
+/// pref, for managed (MDM) deployments.
+const AUTO_INSTALL_ENV: &str = "DISTOKOLOSHE_AUTO_INSTALL";
+
+/// Placeholders the updater fills in; a custom endpoint template needs all of
+/// them to be able to serve the right bundle.
+const ENDPOINT_PLACEHOLDERS: &[&str] = &["{{target}}", "{{arch}}", "{{current_version}}"];
+
+/// Build the templated updater endpoint. Without a channel the legacy
+/// channel-less route is used so older servers keep working. A self-hosted
+/// `template` path replaces the default route; it may use `{{channel}}`.
+fn update_endpoint(server_url: &str, channel: Option<&str>, template: Option<&str>) -> Result<Url, String> {
+    let base = net::normalize_server_url(server_url)?;
+    // Allow-list only — the channel is spliced straight into the path.
+    if let Some(c) = channel {
+        if !UPDATE_CHANNELS.contains(&c) {
+            return Err(format!("unknown update channel: {c}"));
+        }
+    }
+    let endpoint = match (template, channel) {
+        (Some(t), _) => {
+            if !t.starts_with('/') || t.contains("://") {
+                return Err(format!("endpoint template must be a path: {t}"));
+            }
+            let missing: Vec<&str> = ENDPOINT_PLACEHOLDERS
+                .iter()
+                .copied()
+                .filter(|p| !t.contains(p))
+                .collect();
+            if !missing.is_empty() {
+                return Err(format!("endpoint template is missing {}", missing.join(", ")));
+            }
+            let path = match channel {
+                Some(c) => t.replace("{{channel}}", c),
+                None if t.contains("{{channel}}") => {
+                    return Err("endpoint template uses {{channel}} but no channel was given".into());
+                }
+                None => t.to_string(),
+            };
+            [&base, path.as_str()].concat()
+        }
+        (None, None) => [&base, "/api/updates/{{target}}/{{arch}}/{{current_version}}"].concat(),
+        (None, Some(c)) => {
+            format!("{base}/api/updates/{c}/{{{{target}}}}/{{{{arch}}}}/{{{{current_version}}}}")
+        }
+    };
+    Url::parse(&endpoint).map_err(|e| e.to_string())
+}
+
+#[derive(Serialize)]
+struct ResolvedEndpoint {
     server_url: String,
+    /// As built, placeholders and all.
+    template: String,
+    /// What the updater actually requests.
+    url: String,
 }
-struct AuthState(Mutex<Option<AuthInfo>>);
 
-#[tauri::command]
-fn set_auth_info(state: tauri::State<'_, AuthState>, token: String, server_url: String) {
-    *state.0.lock().unwrap() = Some(AuthInfo { token, server_url });
+#[derive(Serialize)]
+struct UpdateEndpointDebug {
+    /// `{{target}}`: the OS as the updater names it (`darwin` for macOS).
+    os: &'static str,
+    /// `{{arch}}`.
+    arch: &'static str,
+    /// `{{bundle_type}}`; `unknown` when not installed by an installer.
+    bundle_type: &'static str,
+    /// `{{current_version}}`, encoded as sent.
+    current_version: String,
+    channel: Option<String>,
+    /// Manifest `platforms` keys the updater looks for, in order.
+    platform_keys: Vec<String>,
+    endpoints: Vec<ResolvedEndpoint>,
 }
 
-#[tauri::command]
-fn clear_auth_info(state: tauri::State<'_, AuthState>) {
-    *state.0.lock().unwrap() = None;
+/// The installer name the updater substitutes for `{{bundle_type}}` and
+/// appends to platform keys. A DMG install has none.
+fn updater_bundle_type() -> &'static str {
+    use tauri::utils::config::BundleType;
+    match tauri::utils::platform::bundle_type() {
+        Some(BundleType::Deb) => "deb",
+        Some(BundleType::Rpm) => "rpm",
+        Some(BundleType::AppImage) => "appimage",
+        Some(BundleType::Msi) => "msi",
+        Some(BundleType::Nsis) => "nsis",
+        Some(BundleType::App) => "app",
+        _ => "unknown",
+    }
+}
+
+/// What to check for an update, and how; every field is optional. Passed
+/// from the webview as `{ options: { serverUrl, channel, .. } }`.
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct CheckOptions {
+    /// Default: the one set with `set_server_url`.
+    server_url: Option<String>,
+    /// Mirrors tried in order after `server_url`.
+    server_urls: Option<Vec<String>>,
+    /// Default: the `update_channel` pref.
+    channel: Option<String>,
+    token: Option<String>,
+    timeout_secs: Option<u64>,
+    endpoint_template: Option<String>,
+}
+
+/// Each server an update check tries, in order (`server_url`, then any
+/// mirrors not already listed), with its update endpoint for `channel`,
+/// placeholders left in for the updater.
+fn resolve_endpoints(
+    app: &tauri::AppHandle,
+    options: &CheckOptions,
+    channel: Option<&str>,
+) -> Result<Vec<(String, Url)>, CommandError> {
+    let mut mirrors =
+        vec![net::resolve_server_url(app, options.server_url.as_deref())?];
+    for url in options.server_urls.iter().flatten() {
+        if !mirrors.contains(url) {
+            mirrors.push(url.clone());
+        }
+    }
+    mirrors
+        .into_iter()
+        .map(|m| {
+            let url = update_endpoint(&m, channel, options.endpoint_template.as_deref())
+                .map_err(CommandError::invalid_input)?;
+            Ok((m, url))
+        })
+        .collect()
 }
 
+/// The URLs an update check would request, with every placeholder expanded
+/// the way the updater does it, for checking a feed's paths. Takes the same
+/// options as `check_for_update`, though only the server, mirror, channel
+/// and template matter; nothing is requested.
 #[tauri::command]
-async fn send_leave(token: String, server_url: String) -> Result<(), String> {
-    let url = format!("{}/api/events/leave", server_url);
-    let body = serde_json::json!({ "token": token }).to_string();
-    reqwest::Client::new()
-        .post(&url)
-        .header("Content-Type", "application/json")
-        .body(body)
-        .timeout(std::time::Duration::from_secs(2))
-        .send()
-        .await
-        .map_err(|e| e.to_string())?;
-    Ok(())
+fn debug_update_endpoint(
+    app: tauri::AppHandle,
+    options: Option<CheckOptions>,
+) -> Result<UpdateEndpointDebug, CommandError> {
+    let options = options.unwrap_or_default();
+    let (os, arch) = update_target();
+    let bundle_type = updater_bundle_type();
+    // The updater percent-encodes `+` (build metadata) and nothing else a
+    // version can contain.
+    let current_version = app.package_info().version.to_string().replace('+', "%2B");
+    let channel = options.channel.clone().or_else(|| prefs::known(&app).update_channel);
+
+    let endpoints = resolve_endpoints(&app, &options, channel.as_deref())?
+        .into_iter()
+        .map(|(server_url, template)| {
+            let template = template.to_string();
+            let url = template
+                .replace("%7B%7Bcurrent_version%7D%7D", &current_version)
+                .replace("%7B%7Btarget%7D%7D", os)
+                .replace("%7B%7Barch%7D%7D", arch)
+                .replace("%7B%7Bbundle_type%7D%7D", bundle_type)
+                .replace("{{current_version}}", &current_version)
+                .replace("{{target}}", os)
+                .replace("{{arch}}", arch)
+                .replace("{{bundle_type}}", bundle_type);
+            ResolvedEndpoint { server_url, template, url }
+        })
+        .collect::<Vec<_>>();
+
+    let target = format!("{os}-{arch}");
+    let mut platform_keys = Vec::new();
+    if bundle_type != "unknown" {
+        platform_keys.push(format!("{target}-{bundle_type}"));
+    }
+    platform_keys.push(target);
+    Ok(UpdateEndpointDebug {
+        os,
+        arch,
+        bundle_type,
+        current_version,
+        channel,
+        platform_keys,
+        endpoints,
+    })
 }
 
-// ── Update state ─────────────────────────────────────────
-struct PendingUpdate(Mutex<Option<tauri_plugin_updater::Update>>);
+/// `{{target}}` and `{{arch}}` as the updater fills them in.
+pub(crate) fn update_target() -> (&'static str, &'static str) {
+    let target = match std::env::consts::OS {
+        "macos" => "darwin",
+        os => os,
+    };
+    let arch = match std::env::consts::ARCH {
+        "x86" => "i686",
+        "arm" => "armv7",
+        arch => arch,
+    };
+    (target, arch)
+}
 
-#[derive(Serialize, Clone)]
-struct UpdateInfo {
-    version: String,
-    body: Option<String>,
+/// The updater reports any non-2xx as a bare `ReleaseNotFound`, so re-request
+/// the endpoint to learn the actual status code.
+async fn endpoint_status(app: &tauri::AppHandle, endpoint: &Url, token: Option<&str>) -> Option<u16> {
+    let (target, arch) = update_target();
+    let url = endpoint
+        .as_str()
+        .replace("%7B%7Btarget%7D%7D", target)
+        .replace("%7B%7Barch%7D%7D", arch)
+        .replace("%7B%7Bcurrent_version%7D%7D", &app.package_info().version.to_string());
+    let mut request = net::client().ok()?.get(url).timeout(Duration::from_secs(5));
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    let status = request.send_traced().await.ok()?.status();
+    (!status.is_success()).then_some(status.as_u16())
 }
 
+/// The options' `server_url` is checked first, then each of `server_urls`
+/// in order until one answers; the one that did is reported back as
+/// `mirror`. A call made while another check is in flight waits for it and
+/// returns its result, whatever options it was given. `Ok(None)` is the
+/// clean "no update" (including a 204).
 #[tauri::command]
 async fn check_for_update(
     app: tauri::AppHandle,
     state: tauri::State<'_, PendingUpdate>,
-    server_url: String,
-) -> Result<Option<UpdateInfo>, String> {
-    let base = server_url.trim_end_matches('/');
-    let endpoint =
-        [base, "/api/updates/{{target}}/{{arch}}/{{current_version}}"].concat();
-    let endpoint_url = Url::parse(&endpoint).map_err(|e| e.to_string())?;
-
-    let update = app
-        .updater_builder()
-        .endpoints(vec![endpoint_url])
-        .map_err(|e: tauri_plugin_updater::Error| e.to_string())?
-        .build()
-        .map_err(|e: tauri_plugin_updater::Error| e.to_string())?
-        .check()
-        .await
-        .map_err(|e: tauri_plugin_updater::Error| e.to_string())?;
-
-    match update {
-        Some(u) => {
-            let info = UpdateInfo {
-                version: u.version.clone(),
-                body: u.body.clone(),
-            };
-            *state.0.lock().unwrap() = Some(u);
-            Ok(Some(info))
+    options: Option<CheckOptions>,
+) -> Result<Option<UpdateInfo>, CommandError> {
+    let generation = state.check_generation.load(std::sync::atomic::Ordering::Acquire);
+    let _checking = state.checking.lock().await;
+    if state.check_generation.load(std::sync::atomic::Ordering::Acquire) != generation {
+        if let Some(result) = state.check_result.lock().unwrap().clone() {
+            return result;
         }
-        None => Ok(None),
     }
+    let result = check_for_update_locked(&app, &state, options.unwrap_or_default()).await;
+    *state.check_result.lock().unwrap() = Some(result.clone());
+    state.check_generation.fetch_add(1, std::sync::atomic::Ordering::Release);
+    result
 }
 
+/// check_for_update, but with "nothing newer" spelled out along with the
+/// running version, for a manual "Check for updates" to report. A failed
+/// check is still an error, never `up_to_date`.
 #[tauri::command]
-async fn install_update(
+async fn check_for_update_status(
     app: tauri::AppHandle,
     state: tauri::State<'_, PendingUpdate>,
-) -> Result<(), String> {
+    options: Option<CheckOptions>,
+) -> Result<UpdateStatus, CommandError> {
+    let current_version = app.package_info().version.to_string();
+    let found = check_for_update(app, state, options).await?;
+    Ok(match found {
+        Some(info) => UpdateStatus::UpdateAvailable(info),
+        None => UpdateStatus::UpToDate { current_version },
+    })
+}
+
+/// check_for_update's body, run with `checking` held.
+async fn check_for_update_locked(
+    app: &tauri::AppHandle,
+    state: &PendingUpdate,
+    options: CheckOptions,
+) -> Result<Option<UpdateInfo>, CommandError> {
+    let channel = options.channel.clone().or_else(|| prefs::known(app).update_channel);
+    let result = match resolve_endpoints(app, &options, channel.as_deref()) {
+        Ok(endpoints) => run_update_check(app, state, endpoints, options.token, options.timeout_secs).await,
+        Err(e) => Err(e),
+    };
+    *state.last_check.lock().unwrap() = Some(LastCheck {
+        channel,
+        checked_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs()),
+        result: match &result {
+            Ok(Some(info)) => Ok(Some(info.version.clone())),
+            Ok(None) => Ok(None),
+            Err(e) => Err(e.clone()),
+        },
+    });
+    if let Ok(Some(info)) = &result {
+        if !info.manual_update_required {
+            if auto_install_enabled(app) {
+                tauri::async_runtime::spawn(auto_install(app.clone(), info.version.clone()));
+            } else {
+                update_prompt::arm(app);
+            }
+        }
+    }
+    result
+}
+
+/// An updater for `endpoint_url` with our key, proxy, TLS and token.
+fn updater_builder(
+    app: &tauri::AppHandle,
+    endpoint_url: &Url,
+    token: Option<&str>,
+) -> Result<tauri_plugin_updater::UpdaterBuilder, CommandError> {
+    let mut builder = app
+        .updater_builder()
+        .pubkey(UPDATER_PUBKEY)
+        .endpoints(vec![endpoint_url.clone()])?
+        .header("User-Agent", net::user_agent())?;
+    if let Some(proxy) = net::proxy() {
+        builder = builder.proxy(proxy);
+    }
+    if let Some(tls) = pinning::tls_config() {
+        builder = builder.configure_client(move |client| client.use_preconfigured_tls(tls.clone()));
+    } else {
+        let certs = net::custom_ca_updater_certs();
+        if !certs.is_empty() {
+            builder = builder.configure_client(move |client| {
+                certs.iter().cloned().fold(client, |client, cert| client.add_root_certificate(cert))
+            });
+        }
+    }
+    if let Some(token) = token {
+        builder = builder.header("Authorization", format!("Bearer {token}"))?;
+    }
+    Ok(builder)
+}
+
+/// Check a single endpoint, retrying transient failures.
+async fn check_endpoint(
+    app: &tauri::AppHandle,
+    endpoint_url: Url,
+    token: Option<&str>,
+    timeout_secs: Option<u64>,
+) -> Result<Option<tauri_plugin_updater::Update>, CommandError> {
+    let updater = updater_builder(app, &endpoint_url, token)?.build()?;
+
+    // The updater sets no timeout of its own, so a stalled connection would
+    // hang forever. Bound each attempt and back off between transient
+    // failures; only the last failure is reported.
+    let per_attempt = Duration::from_secs(timeout_secs.unwrap_or(DEFAULT_UPDATE_TIMEOUT_SECS));
+    let mut attempt = 0;
+    loop {
+        let err = match tokio::time::timeout(per_attempt, updater.check()).await {
+            Ok(Ok(update)) => return Ok(update),
+            Ok(Err(tauri_plugin_updater::Error::ReleaseNotFound)) => {
+                match endpoint_status(app, &endpoint_url, token).await {
+                    Some(status) => CommandError::Server {
+                        status,
+                        message: format!("update server responded with HTTP {status}"),
+                    },
+                    None => CommandError::Parse {
+                        message: "no valid release manifest".into(),
+                    },
+                }
+            }
+            Ok(Err(e)) => e.into(),
+            Err(_) => CommandError::Timeout {
+                message: "update check timed out".into(),
+            },
+        };
+        attempt += 1;
+        if !err.is_transient() || attempt >= UPDATE_CHECK_ATTEMPTS {
+            return Err(err);
+        }
+        tokio::time::sleep(UPDATE_RETRY_BASE * 2u32.pow(attempt - 1)).await;
+    }
+}
+
+/// Try each mirror in turn. The updater can take several endpoints itself but
+/// doesn't say which one answered, so the failover is done here instead. A
+/// mirror saying "no update" is believed; only failures move on.
+async fn run_update_check(
+    app: &tauri::AppHandle,
+    state: &PendingUpdate,
+    endpoints: Vec<(String, Url)>,
+    token: Option<String>,
+    timeout_secs: Option<u64>,
+) -> Result<Option<UpdateInfo>, CommandError> {
+    let mut last_err = None;
+    for (mirror, endpoint_url) in endpoints {
+        let update = match check_endpoint(app, endpoint_url, token.as_deref(), timeout_secs).await {
+            Ok(update) => update,
+            Err(e) => {
+                log::warn!("update check against {mirror} failed: {e}");
+                last_err = Some(e);
+                continue;
+            }
+        };
+        let Some(u) = update else { return Ok(None) };
+        let mut info = UpdateInfo::from_update(&u);
+        info.mirror = Some(mirror);
+        let previous = state.update.lock().unwrap().replace(u);
+        // A different version makes any already-downloaded bundle stale.
+        if previous.map(|p| p.version) != Some(info.version.clone()) {
+            *state.downloaded.lock().unwrap() = None;
+        }
+        return Ok(Some(info));
+    }
+    Err(last_err.unwrap_or_else(|| "no update endpoints".to_string().into()))
+}
+
+/// Fetch the pending update's bundle, emitting progress as it goes. Returns
+/// `Err("cancelled")` if cancel_update fires first; the partial download is
+/// kept so the next attempt resumes rather than starting over.
+///
+/// Transient failures are retried with backoff, each announced as
+/// `update://retrying`; running out of attempts fails with
+/// `retries_exhausted: ...`. Anything else (a bad signature, a 404, a full
+/// disk) fails straight away with its own message.
+async fn download_update(
+    app: &tauri::AppHandle,
+    state: &PendingUpdate,
+) -> Result<Vec<u8>, String> {
+    // Clone rather than take so a cancelled download can be retried without
+    // re-running check_for_update.
     let update = state
-        .0
+        .update
         .lock()
         .unwrap()
-        .take()
+        .clone()
         .ok_or("No pending update")?;
+    let cancel = {
+        let mut current = state.cancel.lock().unwrap();
+        // A spent token is from an earlier download; share a live one with
+        // the download already running.
+        if current.is_cancelled() {
+            *current = CancellationToken::new();
+        }
+        current.clone()
+    };
+    bundle::check(&update)?;
+
+    // Fail up front rather than with an I/O error halfway through the write.
+    if let Some(required) = UpdateInfo::from_update(&update).content_length {
+        download::ensure_space(app, required)?;
+    }
+
+    let mut attempt = 1;
+    let result = loop {
+        let app_for_progress = app.clone();
+        let download = download::fetch(app, &update, UPDATER_PUBKEY, move |downloaded, content_length| {
+            let _ = app_for_progress.emit(
+                "update://progress",
+                UpdateProgress { downloaded, content_length, attempt },
+            );
+        });
+        let err = tokio::select! {
+            biased;
+            _ = cancel.cancelled() => return Err("cancelled".into()),
+            r = download => match r {
+                Ok(bytes) => break Ok(bytes),
+                Err(e) => e,
+            },
+        };
+        if !err.is_transient() {
+            break Err(err.to_string());
+        }
+        if attempt >= UPDATE_DOWNLOAD_ATTEMPTS {
+            break Err(format!("retries_exhausted: {err}"));
+        }
+        let delay = (UPDATE_DOWNLOAD_RETRY_BASE * 2u32.pow(attempt - 1)).min(UPDATE_DOWNLOAD_RETRY_MAX);
+        log::warn!("update download attempt {attempt} failed, retrying in {delay:?}: {err}");
+        let _ = app.emit(
+            "update://retrying",
+            UpdateRetry {
+                attempt,
+                max_attempts: UPDATE_DOWNLOAD_ATTEMPTS,
+                delay_secs: delay.as_secs(),
+                error: err.to_string(),
+            },
+        );
+        tokio::select! {
+            biased;
+            _ = cancel.cancelled() => return Err("cancelled".into()),
+            _ = tokio::time::sleep(delay) => {}
+        }
+        attempt += 1;
+    };
 
-    if let Err(e) = update.download_and_install(|_, _| {}, || {}).await {
-        // Clear stale state so a fresh check can be performed
-        *state.0.lock().unwrap() = None;
-        return Err(e.to_string());
+    result.inspect_err(|e| {
+        // A bad signature means this release can't be trusted at all; drop it
+        // so a fresh check is needed. Anything else is worth retrying as-is.
+        if e.starts_with("signature_invalid") {
+            *state.update.lock().unwrap() = None;
+        }
+    })
+}
+
+fn apply_update(app: &tauri::AppHandle, state: &PendingUpdate, bytes: &[u8]) -> Result<(), String> {
+    let update = state
+        .update
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or("No pending update")?;
+    // Saved first: on Windows install() hands over to the installer and exits.
+    let current = app.package_info().version.to_string();
+    match download::keep_for_rollback(app, &current) {
+        Ok(true) => {
+            if let Err(e) = prefs::set(app, ROLLBACK_PREF, &current) {
+                log::warn!("failed to record rollback target: {e}");
+            }
+        }
+        Ok(false) => {}
+        Err(e) => log::warn!("failed to keep {current} for rollback: {e}"),
+    }
+    if let Err(e) = download::save_base(app, &update.version, bytes, &update.signature) {
+        log::warn!("failed to keep update bundle for future deltas: {e}");
     }
+    update.install(bytes).map_err(|e| e.to_string())?;
+    // Only reached where install() returns (not Windows). A bad copy on disk
+    // must not be relaunched into; the running one stays usable.
+    integrity::verify_installed(app, &update).inspect_err(|e| {
+        log::error!("installed update failed verification: {e}");
+        let _ = app.emit("update://verify_failed", e.clone());
+    })
+}
+
+#[derive(Serialize)]
+struct RollbackTarget {
+    version: String,
+}
 
+/// The version `rollback_update` would reinstall, if a copy was kept when
+/// the current one was installed.
+#[tauri::command]
+fn get_rollback_target(app: tauri::AppHandle) -> Option<RollbackTarget> {
+    let version = prefs::get::<String>(&app, ROLLBACK_PREF)?;
+    (version != app.package_info().version.to_string()).then_some(RollbackTarget { version })
+}
+
+/// Reinstall the version that was replaced by the last update, from the copy
+/// kept at the time, and restart into it. The copy's signature is checked
+/// first. The updater only hands out an installer through a check, so the
+/// server has to be reachable. The check goes to
+/// `/api/updates/installer/...`, which answers with the latest manifest even
+/// when that's what's running (the normal endpoint would say "up to date");
+/// whatever it offers is ignored and only used to run our own bundle.
+#[tauri::command]
+async fn rollback_update(app: tauri::AppHandle) -> Result<(), CommandError> {
+    let target = get_rollback_target(app.clone()).ok_or_else(|| CommandError::updater("no_rollback_target"))?;
+    let (bytes, signature) =
+        download::rollback_bundle(&app, &target.version, UPDATER_PUBKEY).map_err(CommandError::updater)?;
+
+    let server_url = net::resolve_server_url(&app, None)?;
+    let endpoint = Url::parse(&format!(
+        "{server_url}/api/updates/installer/{{{{target}}}}/{{{{arch}}}}/{{{{current_version}}}}"
+    ))
+    .map_err(CommandError::invalid_input)?;
+    let token = app.state::<presence::AuthState>().0.lock().unwrap().as_ref().map(|i| i.token.clone());
+    let updater = updater_builder(&app, &endpoint, token.as_deref())
+        .and_then(|b| Ok(b.version_comparator(|_, _| true).build()?))?;
+    let mut update = updater
+        .check()
+        .await?
+        .ok_or_else(|| CommandError::updater("no installer available for rollback"))?;
+    update.version = target.version.clone();
+
+    log::warn!("rolling back to {}", target.version);
+    // Saved first: on Windows install() hands over to the installer and exits.
+    if let Err(e) = download::save_base(&app, &target.version, &bytes, &signature) {
+        log::warn!("failed to keep rollback bundle for future deltas: {e}");
+    }
+    let _ = prefs::remove(&app, ROLLBACK_PREF);
+    download::clear_rollback(&app);
+    update.install(&bytes).map_err(CommandError::updater)?;
+    ensure_relaunchable(&app, "rollback")?;
     app.restart();
 }
 
+/// `CommandError::OnBattery` if a large download would start on a battery below
+/// the `update_min_battery` pref (0 turns the check off).
+fn check_battery(app: &tauri::AppHandle, state: &PendingUpdate) -> Result<(), CommandError> {
+    let min = prefs::get::<u8>(app, UPDATE_BATTERY_PREF).unwrap_or(DEFAULT_UPDATE_MIN_BATTERY);
+    let size = state
+        .update
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|u| UpdateInfo::from_update(u).content_length);
+    // Unknown size counts as large: desktop bundles usually are.
+    if min == 0 || size.is_some_and(|s| s < LARGE_UPDATE_BYTES) {
+        return Ok(());
+    }
+    let power = power::power_state();
+    match power.battery_percent {
+        Some(percent) if power.on_battery && percent < min => Err(CommandError::OnBattery {
+            message: format!("battery at {percent}%, below the {min}% set for large updates"),
+        }),
+        _ => Ok(()),
+    }
+}
+
+#[tauri::command]
+fn set_update_min_battery(app: tauri::AppHandle, percent: u8) -> Result<(), CommandError> {
+    prefs::set(&app, UPDATE_BATTERY_PREF, &percent.min(100))
+}
+
+/// Check the pending update's bundle URL answers, and with the size the
+/// manifest promises, without downloading it.
+#[tauri::command]
+async fn preflight_update(state: tauri::State<'_, PendingUpdate>) -> Result<download::Preflight, CommandError> {
+    let update = state
+        .update
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| CommandError::updater("No pending update"))?;
+    let expected = UpdateInfo::from_update(&update).content_length;
+    download::preflight(&update, expected).await.map_err(CommandError::network)
+}
+
+/// `force` skips the low-battery check, for when the user insists.
+#[tauri::command]
+async fn install_update(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, PendingUpdate>,
+    force: Option<bool>,
+) -> Result<(), CommandError> {
+    update_prompt::disarm();
+    // The user is being asked natively; this is the webview catching up.
+    if update_prompt::showing() {
+        return Err(CommandError::updater("prompting: waiting on the update dialog"));
+    }
+    // Reuse a bundle already fetched for install-on-quit.
+    let cached = state.downloaded.lock().unwrap().take();
+    let bytes = match cached {
+        Some(bytes) => bytes,
+        None => {
+            if force != Some(true) {
+                check_battery(&app, &state)?;
+            }
+            download_update(&app, &state).await.map_err(CommandError::updater)?
+        }
+    };
+    apply_update(&app, &state, &bytes).map_err(CommandError::updater)?;
+    ensure_relaunchable(&app, "update")?;
+    app.restart();
+}
+
+/// Check the relaunch can work before calling `restart`. Tauri only logs a
+/// failed spawn and exits anyway, which would leave the user with no app at
+/// all, so catch the likely causes (binary moved or no longer executable)
+/// while we can still back out.
+pub(crate) fn restart_preflight(app: &tauri::AppHandle) -> Result<(), String> {
+    let path = tauri::process::current_binary(&app.env()).map_err(|e| e.to_string())?;
+    check_relaunchable(&path)
+}
+
+/// `restart_preflight` for a known binary path.
+fn check_relaunchable(path: &std::path::Path) -> Result<(), String> {
+    let meta = std::fs::metadata(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if meta.permissions().mode() & 0o111 == 0 {
+            return Err(format!("{} is not executable", path.display()));
+        }
+    }
+    if !meta.is_file() {
+        return Err(format!("{} is not a file", path.display()));
+    }
+    Ok(())
+}
+
+/// After installing: the update is on disk and only the relaunch is in
+/// doubt. If it can't work, stay running and tell the UI (through `emit`, given
+/// the event name) so it can ask the user to reopen the app themselves.
+fn relaunch_gate(preflight: Result<(), String>, after: &str, emit: impl FnOnce(&str)) -> Result<(), CommandError> {
+    preflight.map_err(|e| {
+        log::error!("not restarting after {after}: {e}");
+        emit("update://restart_failed");
+        CommandError::RestartFailed { message: e }
+    })
+}
+
+fn ensure_relaunchable(app: &tauri::AppHandle, after: &str) -> Result<(), CommandError> {
+    relaunch_gate(restart_preflight(app), after, |event| {
+        let _ = app.emit(event, i18n::t(app, "update.restart_failed"));
+    })
+}
+
+async fn download_for_quit(app: &tauri::AppHandle, state: &PendingUpdate) -> Result<(), CommandError> {
+    if state.downloaded.lock().unwrap().is_some() {
+        return Ok(());
+    }
+    let bytes = download_update(app, state).await.map_err(CommandError::updater)?;
+    *state.downloaded.lock().unwrap() = Some(bytes);
+    let _ = app.emit("update://ready", ());
+    Ok(())
+}
+
+/// Download now, install when the app quits (see `teardown`) so the user
+/// isn't restarted mid-conversation.
+#[tauri::command]
+async fn install_update_on_quit(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, PendingUpdate>,
+) -> Result<(), CommandError> {
+    update_prompt::disarm();
+    download_for_quit(&app, &state).await
+}
+
+fn auto_install_enforced() -> Option<bool> {
+    match std::env::var(AUTO_INSTALL_ENV).ok()?.to_ascii_lowercase().as_str() {
+        "1" | "true" => Some(true),
+        "0" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+fn auto_install_enabled(app: &tauri::AppHandle) -> bool {
+    auto_install_enforced().unwrap_or_else(|| prefs::get(app, AUTO_INSTALL_PREF).unwrap_or(false))
+}
+
+#[derive(Serialize)]
+struct AutoInstall {
+    enabled: bool,
+    /// Set by the environment; `set_auto_install` can't change it.
+    enforced: bool,
+}
+
+#[tauri::command]
+fn get_auto_install(app: tauri::AppHandle) -> AutoInstall {
+    AutoInstall {
+        enabled: auto_install_enabled(&app),
+        enforced: auto_install_enforced().is_some(),
+    }
+}
+
+#[tauri::command]
+fn set_auto_install(app: tauri::AppHandle, enabled: bool) -> Result<(), CommandError> {
+    if auto_install_enforced().is_some() {
+        return Err(CommandError::permission("auto_install_enforced"));
+    }
+    prefs::set(&app, AUTO_INSTALL_PREF, &enabled)
+}
+
+/// Unattended install-on-quit for a freshly found update. Goes through the
+/// same battery and disk-space guards as a manual install; a guard that trips
+/// just postpones it to the next check. Tells the user only once it's ready.
+async fn auto_install(app: tauri::AppHandle, version: String) {
+    let state = app.state::<PendingUpdate>();
+    if state.auto_installing.swap(true, std::sync::atomic::Ordering::AcqRel) {
+        return;
+    }
+    if !update_window::allows(&app, &version) {
+        update_window::defer(&app, &version);
+        state.auto_installing.store(false, std::sync::atomic::Ordering::Release);
+        return;
+    }
+    let result = match check_battery(&app, &state) {
+        Ok(()) => download_for_quit(&app, &state).await,
+        Err(e) => Err(e),
+    };
+    state.auto_installing.store(false, std::sync::atomic::Ordering::Release);
+    match result {
+        // The window scheduler applies it once the user is away.
+        Ok(()) if update_window::is_set(&app) => {}
+        Ok(()) => {
+            #[cfg(desktop)]
+            {
+                use tauri_plugin_notification::NotificationExt;
+                let _ = app
+                    .notification()
+                    .builder()
+                    .title(i18n::t(&app, "update.ready.title"))
+                    .body(i18n::t_with(&app, "update.ready.body", &[("version", &version)]))
+                    .show();
+            }
+        }
+        Err(e) => log::info!("auto-install of {version} postponed: {e}"),
+    }
+}
+
+/// Apply the bundle fetched for install-on-quit and relaunch into it, for
+/// the maintenance window. Nothing is shown; if the relaunch can't work the
+/// bundle is still installed and takes effect on the next start.
+async fn install_unattended(app: &tauri::AppHandle) {
+    let state = app.state::<PendingUpdate>();
+    let Some(bytes) = state.downloaded.lock().unwrap().take() else { return };
+    if let Err(e) = apply_update(app, &state, &bytes) {
+        log::error!("scheduled update install failed: {e}");
+        return;
+    }
+    update_window::clear(app);
+    if let Err(e) = restart_preflight(app) {
+        log::error!("not restarting after scheduled update: {e}");
+        return;
+    }
+    log::info!("restarting into the scheduled update");
+    app.restart();
+}
+
+/// Free bytes where updates are downloaded and unpacked, so the UI can warn
+/// before starting when `required_bytes` won't fit.
+#[tauri::command]
+fn check_update_space(app: tauri::AppHandle, required_bytes: u64) -> Result<u64, CommandError> {
+    let available = download::available_space(&app).map_err(CommandError::io)?;
+    if available < required_bytes {
+        log::warn!("update needs {required_bytes} bytes but only {available} are free");
+    }
+    Ok(available)
+}
+
+#[tauri::command]
+fn get_app_version(app: tauri::AppHandle) -> String {
+    app.package_info().version.to_string()
+}
+
+#[tauri::command]
+fn cancel_update(state: tauri::State<'_, PendingUpdate>) {
+    state.cancel.lock().unwrap().cancel();
+}
+
 // ── App entry ────────────────────────────────────────────
+/// Upper bound on how long closing waits for the leave beacon to go out.
+pub(crate) const CLOSE_BEACON_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Shutdown work shared by window close, the tray's Quit item and OS
+/// session end. Runs at most once.
+pub(crate) async fn teardown(app: &tauri::AppHandle) {
+    if !shutdown::begin() {
+        return;
+    }
+    // Tear down any active pipe before the runtime exits so the
+    // server's pipe lock is released and the radio resumes.
+    pipe::force_stop(app).await;
+    presence::stop_heartbeat(app);
+    window::close_popouts(app);
+
+    // Send leave signal via native HTTP, bypassing webview CORS restrictions.
+    let _ = tokio::time::timeout(CLOSE_BEACON_TIMEOUT, presence::leave(app)).await;
+    shutdown::stop_tasks(app).await;
+    install_deferred_update(app);
+}
+
+/// Apply an update deferred by install_update_on_quit. The app is on its way
+/// out, so the new version starts on the next launch rather than now. Run at
+/// the end of both `teardown` and `shutdown::last_chance`.
+pub(crate) fn install_deferred_update(app: &tauri::AppHandle) {
+    let state = app.state::<PendingUpdate>();
+    let deferred = state.downloaded.lock().unwrap().take();
+    if let Some(bytes) = deferred {
+        match apply_update(app, &state, &bytes) {
+            Ok(()) => update_window::clear(app),
+            Err(e) => log::error!("deferred update install failed: {e}"),
+        }
+    }
+}
+
+/// Exit for real, bypassing close-to-tray. Shared by `quit_app` and the
+/// tray's Quit item; teardown's guard keeps the leave beacon to one send even
+/// if a window close races it.
+pub(crate) async fn quit(app: &tauri::AppHandle) {
+    teardown(app).await;
+    app.exit(0);
+}
+
+#[tauri::command]
+async fn quit_app(app: tauri::AppHandle) {
+    quit(&app).await;
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tauri::Builder::default()
+    webview::ensure_runtime();
+    let context = tauri::generate_context!();
+    gpu::apply_before_webview(&context.config().identifier);
+    crash::install_hook();
+
+    let mut builder = tauri::Builder::default();
+
+    // Must be the first plugin: a second launch exits here, before any
+    // window (and so any join beacon or heartbeat) exists.
+    #[cfg(desktop)]
+    {
+        builder = builder.plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+            if let Some(action) = quickactions::from_args(&argv) {
+                quickactions::run(app, action);
+                return;
+            }
+            // The window is already placed by now, so reset it live.
+            if argv.iter().any(|a| a == window::RESET_WINDOW_ARG) {
+                if let Err(e) = window::reset_window_state(app.clone()) {
+                    log::warn!("failed to reset window state: {e}");
+                }
+            }
+            tray::show_main_window(app);
+            let _ = app.emit("single-instance://args", argv);
+        }));
+    }
+
+    builder
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_process::init())
-        .manage(AuthState(Mutex::new(None)))
-        .manage(PendingUpdate(Mutex::new(None)))
+        .plugin(tauri_plugin_notification::init())
+        .manage(presence::AuthState(Mutex::new(None)))
+        .manage(net::ServerUrl(Mutex::new(None)))
+        .manage(PendingUpdate {
+            update: Mutex::new(None),
+            cancel: Mutex::new(CancellationToken::new()),
+            downloaded: Mutex::new(None),
+            last_check: Mutex::new(None),
+            auto_installing: AtomicBool::new(false),
+            checking: TokioMutex::new(()),
+            check_generation: AtomicU64::new(0),
+            check_result: Mutex::new(None),
+        })
+        .manage(presence::HeartbeatState {
+            interval_secs: AtomicU64::new(presence::DEFAULT_HEARTBEAT_SECS),
+            task: Mutex::new(None),
+            reconnecting: AtomicBool::new(false),
+            suspended: AtomicBool::new(false),
+            last_beat: Mutex::new(None),
+        })
+        .manage(idle::IdleState {
+            threshold_secs: AtomicU64::new(idle::DEFAULT_IDLE_SECS),
+            away: AtomicBool::new(false),
+            ptt_held: AtomicBool::new(false),
+            transmitting: AtomicBool::new(false),
+            blur_away: AtomicBool::new(false),
+            blur_task: Mutex::new(None),
+        })
+        .manage(shortcuts::Bindings(Mutex::new(HashMap::new())))
+        .manage(shortcuts::MuteState(AtomicBool::new(false)))
+        .manage(tray::CloseToTray(AtomicBool::new(false)))
+        .manage(tray::TrayState {
+            status: Mutex::new(tray::TrayStatus::Offline),
+            unread: AtomicU32::new(0),
+            dnd: AtomicBool::new(false),
+            in_call: AtomicBool::new(false),
+            invisible: AtomicBool::new(false),
+            accent: Mutex::new(accent::DEFAULT_ACCENT),
+        })
+        .manage(deafen::DeafenState::default())
+        .manage(deeplink::PendingDeepLink(Mutex::new(None)))
+        .manage(window::Popouts(Mutex::new(HashSet::new())))
+        .manage(notify::NotifyState::default())
+        .manage(typing::TypingState::default())
+        .manage(stream::StreamState::default())
+        .manage(shutdown::Tasks::default())
+        .manage(rtc::IceCache::default())
+        .manage(zoom::ZoomState(Mutex::new(1.0)))
+        .manage(connectivity::Connectivity {
+            online: AtomicBool::new(true),
+        })
         .manage(pipe::PipeState(TokioMutex::new(None)))
         .setup(|app| {
+            // Safe mode: no saved prefs, shortcuts or window state, and the
+            // webview gets the recovery screen instead of the full app.
+            let safe_mode = safemode::take(app.handle());
+            app.manage(safemode::SafeMode(safe_mode));
+            app.manage(if safe_mode {
+                prefs::ephemeral()
+            } else {
+                prefs::load(app.handle())
+            });
+            app.manage(i18n::init(app.handle()));
+            outbox::init(app.handle(), !safe_mode);
+            app.manage(vad::init(app.handle()));
+            theme::restore(app.handle());
+            logging::init(app.handle())?;
+            net::restore(app.handle());
+            auth::init(app.handle());
+            pinning::restore(app.handle());
+            presence::restore_rate_limit(app.handle());
+            servers::restore(app.handle());
+            crash::init(app.handle());
+            if safe_mode {
+                log::info!("starting in safe mode");
+                safemode::load_recovery_route(app.handle());
+            }
+
             #[cfg(desktop)]
             {
-                app.handle()
-                    .plugin(tauri_plugin_global_shortcut::Builder::new().build())?;
-                app.handle()
-                    .plugin(tauri_plugin_window_state::Builder::default().build())?;
+                shortcuts::init(app.handle());
+                let reset_window = window::take_reset_arg(app.handle());
+                if !safe_mode {
+                    // Without it the window just opens at its default size.
+                    let window_state = app.handle().plugin(
+                        tauri_plugin_window_state::Builder::default()
+                            .with_filename(window::state_filename(app.handle()))
+                        // Visibility is ours to decide (see autostart::apply).
+                        .with_state_flags(
+                            tauri_plugin_window_state::StateFlags::all()
+                                - tauri_plugin_window_state::StateFlags::VISIBLE,
+                        )
+                            .build(),
+                    );
+                    if let Err(e) = window_state {
+                        log::error!("window state plugin failed to load, window position won't be kept: {e}");
+                    }
+                    shortcuts::restore(app.handle());
+                }
+                app.handle().plugin(tauri_plugin_autostart::init(
+                    tauri_plugin_autostart::MacosLauncher::LaunchAgent,
+                    Some(vec![autostart::AUTOSTART_ARG]),
+                ))?;
+                tray::init(app.handle())?;
+                accent::restore(app.handle());
+                tray::restore_close_to_tray(app.handle());
+                deeplink::init(app.handle());
+                window::restore(app.handle());
+                if reset_window {
+                    if let Err(e) = window::recenter(app.handle()) {
+                        log::warn!("failed to recenter after {}: {e}", window::RESET_WINDOW_ARG);
+                    }
+                }
+                autostart::apply(app.handle());
+                zoom::restore(app.handle());
+            }
+            notify::restore(app.handle());
+            presence::restore_invisible(app.handle());
+            quickactions::run_launch_args(app.handle());
+            quickactions::refresh(app.handle());
+
+            presence::start_heartbeat(app.handle());
+            if !power::watch_sleep(app.handle()) {
+                presence::start_wake_watcher(app.handle());
+            }
+            shutdown::init(app.handle());
+            connectivity::start(app.handle());
+            connectivity::start_latency_sampler(app.handle());
+            stats::start(app.handle());
+            idle::start(app.handle());
+            update_window::start(app.handle());
+            throttle::start(app.handle());
+            // These are what fall over on headless CI runners.
+            if environment::audio_monitors_enabled() {
+                audio::start_watcher(app.handle());
+                volume::start_watcher(app.handle());
             }
 
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             check_for_update,
+            check_for_update_status,
             install_update,
-            set_auth_info,
-            clear_auth_info,
-            send_leave,
+            update_prompt::acknowledge_update,
+            update_prompt::show_update_dialog,
+            preflight_update,
+            debug_update_endpoint,
+            install_update_on_quit,
+            quit_app,
+            get_auto_install,
+            set_auto_install,
+            get_rollback_target,
+            rollback_update,
+            update_window::get_update_window,
+            update_window::set_update_window,
+            cancel_update,
+            check_update_space,
+            set_update_min_battery,
+            download::set_download_bandwidth_limit,
+            power::get_power_state,
+            get_app_version,
+            presence::set_auth_info,
+            presence::clear_auth_info,
+            presence::logout,
+            presence::set_invisible,
+            typing::set_typing,
+            rtc::get_ice_servers,
+            rtc::prewarm_rtc,
+            rtc::get_transport_mode,
+            rtc::set_transport_mode,
+            capabilities::get_server_capabilities,
+            capabilities::get_min_client_version,
+            callstats::get_call_stats,
+            callstats::report_call_stats,
+            stream::start_event_stream,
+            stream::stop_event_stream,
+            auth::validate_token,
+            auth::list_sessions,
+            auth::revoke_session,
+            presence::set_beacon_rate_limit,
+            presence::send_leave,
+            presence::send_join_beacon,
+            presence::get_heartbeat_status,
+            quality::list_quality_presets,
+            quality::get_quality_preset,
+            quality::set_quality_preset,
+            presence::set_heartbeat_interval,
+            presence::reconnect,
+            presence::set_leave_grace,
+            presence::export_presence_log,
+            idle::set_idle_threshold,
+            idle::set_transmitting,
+            idle::set_away_on_blur,
+            secrets::save_token,
+            secrets::save_refresh_token,
+            secrets::load_token,
+            secrets::clear_token,
+            servers::list_servers,
+            servers::normalize_server_url,
+            servers::add_server,
+            servers::remove_server,
+            servers::switch_server,
+            shortcuts::register_push_to_talk,
+            shortcuts::unregister_push_to_talk,
+            shortcuts::register_mute_toggle,
+            shortcuts::unregister_mute_toggle,
+            shortcuts::list_shortcuts,
+            shortcuts::get_shortcut_backend,
+            gpu::get_hardware_acceleration,
+            gpu::set_hardware_acceleration,
+            webview::check_webview_runtime,
+            shortcuts::reset_shortcuts,
+            shortcuts::export_keybindings,
+            shortcuts::import_keybindings,
+            tray::set_tray_status,
+            tray::set_close_to_tray,
+            tray::set_badge_count,
+            deeplink::take_pending_deep_link,
+            window::set_always_on_top,
+            window::set_decorations,
+            window::set_content_protected,
+            window::recenter_window,
+            window::reset_window_state,
+            window::request_user_attention,
+            media::check_media_permissions,
+            media::request_media_permissions,
+            stats::get_runtime_stats,
+            environment::get_environment,
+            stats::export_runtime_stats,
+            accent::get_accent_color,
+            accent::get_system_accent_color,
+            accent::set_accent_color,
+            theme::get_system_theme,
+            theme::get_theme,
+            theme::set_theme,
+            throttle::get_background_throttle,
+            throttle::set_background_throttle,
+            window::minimize_window,
+            window::maximize_window,
+            window::close_window,
+            window::open_popout,
+            window::close_popout,
+            notify::notify,
+            notify::set_active_channel,
+            notify::set_dnd,
+            notify::send_test_notification,
+            sounds::set_notification_sound,
+            sounds::play_notification_sound,
+            sounds::list_notification_sounds,
+            notify::set_in_call,
+            audio::list_audio_devices,
+            audio::set_preferred_input,
+            audio::set_preferred_output,
+            deafen::set_deafen,
+            deafen::get_deafen,
+            vad::start_vad,
+            vad::stop_vad,
+            vad::set_vad_threshold,
+            vad::get_vad_threshold,
+            volume::set_app_volume,
+            volume::get_app_volume,
+            autostart::set_autostart,
+            autostart::is_autostart_enabled,
+            capture::list_capture_sources,
+            capture::capture_source,
+            capture::capture_window_screenshot,
+            capture::check_screen_permission,
+            capture::request_screen_permission,
+            clipboard::read_clipboard_image,
+            connectivity::get_connectivity,
+            connectivity::ping_server,
+            crash::pending_crash_report,
+            crash::submit_crash_report,
+            crash::discard_crash_report,
+            crash::set_send_crash_reports,
+            diagnostics::get_diagnostics,
+            diagnostics::get_bug_report,
+            devtools::enable_devtools,
+            devtools::disable_devtools,
+            devtools::open_devtools,
+            devtools::close_devtools,
+            links::open_external,
+            logging::get_log_path,
+            logging::log_from_webview,
+            clock::check_clock_skew,
+            logging::open_log_folder,
+            storage::open_data_dir,
+            storage::get_data_dir_size,
+            storage::clear_cache,
+            net::set_proxy,
+            net::set_custom_ca,
+            net::set_user_agent,
+            net::get_user_agent,
+            net::get_recent_errors,
+            net::set_network_trace,
+            net::get_network_trace,
+            presence::set_network_paused,
+            presence::get_network_paused,
+            pinning::get_pinned_cert_fingerprint,
+            pinning::set_pinned_cert_fingerprint,
+            net::set_server_url,
+            net::get_server_url,
+            prefs::get_prefs,
+            i18n::set_locale,
+            i18n::get_locale,
+            prefs::get_pref,
+            prefs::set_pref,
+            settings::export_settings,
+            settings::import_settings,
+            safemode::restart_safe_mode,
+            safemode::is_safe_mode,
+            zoom::set_zoom,
+            zoom::get_zoom,
             pipe::pipe_start,
             pipe::pipe_stop,
         ])
+        .on_page_load(|webview, payload| {
+            // A reload drops the webview's call state along with everything else.
+            if webview.label() == "main" && payload.event() == tauri::webview::PageLoadEvent::Started {
+                notify::clear_in_call(webview.app_handle());
+            }
+        })
         .on_window_event(|window, event| {
-            if let tauri::WindowEvent::CloseRequested { .. } = event {
-                // Tear down any active pipe before the runtime exits so the
-                // server's pipe lock is released and the radio resumes.
-                pipe::force_stop_blocking(window.app_handle());
-
-                // Send leave signal via native HTTP, bypassing webview CORS restrictions.
-                let auth = window.app_handle().state::<AuthState>().0.lock().unwrap().clone();
-                if let Some(info) = auth {
-                    let url = format!("{}/api/events/leave", info.server_url);
-                    let body = serde_json::json!({ "token": info.token }).to_string();
-                    let _ = reqwest::blocking::Client::new()
-                        .post(&url)
-                        .header("Content-Type", "application/json")
-                        .body(body)
-                        .timeout(std::time::Duration::from_secs(2))
-                        .send();
+            if let tauri::WindowEvent::DragDrop(tauri::DragDropEvent::Drop { paths, .. }) = event {
+                files::on_drop(window.app_handle(), paths);
+            }
+            if let tauri::WindowEvent::Focused(focused) = event {
+                if window.label() == "main" {
+                    #[cfg(desktop)]
+                    zoom::on_focus_changed(window.app_handle(), *focused);
+                    idle::on_main_focus_changed(window.app_handle(), *focused);
+                    #[cfg(mobile)]
+                    lifecycle::on_main_focus_changed(window.app_handle(), *focused);
+                    if *focused {
+                        notify::on_main_focused(window.app_handle());
+                        window::clear_attention(window.app_handle());
+                    }
+                }
+            }
+            if let tauri::WindowEvent::ThemeChanged(theme) = event {
+                if window.label() == "main" {
+                    theme::on_theme_changed(window.app_handle(), *theme);
+                    accent::on_theme_changed(window.app_handle());
+                }
+            }
+            if let tauri::WindowEvent::Destroyed = event {
+                if window::is_popout(window.label()) {
+                    window::popout_destroyed(window.app_handle(), window.label());
                 }
             }
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                // Only the main window carries the session; popouts just close.
+                if window.label() != "main" {
+                    return;
+                }
+                notify::clear_in_call(window.app_handle());
+                // Already quitting: teardown has sent (or is sending) the
+                // beacon, so let the window go rather than hide or re-run it.
+                if shutdown::started() {
+                    return;
+                }
+                // Hidden, not closed: the user is still connected, so no leave beacon.
+                if tray::close_to_tray(window.app_handle()) {
+                    api.prevent_close();
+                    let _ = window.hide();
+                    return;
+                }
+                // Hold the window open until the beacon has actually been
+                // sent (or timed out), then destroy it ourselves.
+                api.prevent_close();
+                let window = window.clone();
+                tauri::async_runtime::spawn(async move {
+                    teardown(window.app_handle()).await;
+                    let _ = window.destroy();
+                });
+            }
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(context)
+        .expect("error while running tauri application")
+        .run(|app, event| {
+            // Covers exits that bypass the close handler, e.g. Cmd+Q or the
+            // macOS logout/power-off quit request. No-op if teardown ran.
+            if let tauri::RunEvent::Exit = event {
+                shutdown::last_chance(app);
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relaunchable_binary_passes() {
+        let exe = std::env::current_exe().unwrap();
+        assert_eq!(check_relaunchable(&exe), Ok(()));
+    }
+
+    #[test]
+    fn missing_binary_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(check_relaunchable(&dir.path().join("gone")).is_err());
+    }
+
+    #[test]
+    fn directory_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = check_relaunchable(dir.path()).unwrap_err();
+        // A directory has the execute bit, so it gets past the unix check.
+        assert!(err.ends_with("is not a file"), "{err}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_executable_binary_fails() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let err = check_relaunchable(file.path()).unwrap_err();
+        assert!(err.ends_with("is not executable"), "{err}");
+    }
+
+    #[test]
+    fn failed_preflight_reports_restart_failed() {
+        let mut emitted = Vec::new();
+        let err = relaunch_gate(Err("moved".into()), "update", |event| emitted.push(event.to_string())).unwrap_err();
+        assert_eq!(emitted, ["update://restart_failed"]);
+        assert!(matches!(err, CommandError::RestartFailed { .. }));
+        assert_eq!(err.message(), "moved");
+    }
+
+    #[test]
+    fn passing_preflight_emits_nothing() {
+        let mut emitted = false;
+        assert!(relaunch_gate(Ok(()), "update", |_| emitted = true).is_ok());
+        assert!(!emitted);
+    }
 }
@@ -0,0 +1,52 @@
+// Presence on mobile, where the app is backgrounded and then suspended by
+// the OS rather than closed, so the close-requested path that sends the
+// leave beacon on desktop never runs. Going to the background stops the
+// heartbeat and sends the leave (with the usual server-side grace, so a
+// quick app switch doesn't show up as leaving); coming back sends a join
+// and restarts it. A call keeps running in the background, so nothing is
+// sent while in one.
+//
+// Tauri has no background/foreground event of its own; the main window
+// losing and regaining focus is what both mobile runtimes report.
+#![cfg_attr(desktop, allow(dead_code))]
+
+use tauri::AppHandle;
+
+/// Keeps a join from overtaking a leave that's still in flight.
+static BEACONS: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+fn on_background(app: &AppHandle) {
+    if crate::notify::in_call(app) || crate::shutdown::started() || crate::presence::suspended(app) {
+        return;
+    }
+    crate::presence::suspend(app);
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let _guard = BEACONS.lock().await;
+        if let Err(e) = crate::presence::leave(&app).await {
+            log::warn!("leave beacon on background failed: {e}");
+        }
+    });
+}
+
+fn on_foreground(app: &AppHandle) {
+    // Nothing was sent for this stint (in a call, or already quitting).
+    if !crate::presence::suspended(app) {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let _guard = BEACONS.lock().await;
+        // Join and a fresh heartbeat, as after sleep.
+        crate::presence::resume(&app);
+    });
+}
+
+/// Called from the window event handler for the main window.
+pub fn on_main_focus_changed(app: &AppHandle, focused: bool) {
+    if focused {
+        on_foreground(app);
+    } else {
+        on_background(app);
+    }
+}
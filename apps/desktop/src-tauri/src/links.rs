@@ -0,0 +1,65 @@
+// Opening message links in the system browser. Only http(s) and mailto get
+// through; anything that looks like it could mislead the user comes back
+// flagged so the UI can confirm first, then call again with `confirmed`.
+
+use serde::Serialize;
+use tauri::AppHandle;
+use tauri_plugin_opener::OpenerExt;
+use url::{Host, Url};
+
+use crate::error::CommandError;
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum Risk {
+    /// Plain http: contents can be tampered with in transit.
+    Insecure,
+    /// Host is a bare IP address rather than a name.
+    IpHost,
+    /// Internationalised domain that may impersonate another.
+    Punycode,
+    /// `user@host` style URL that hides the real destination.
+    Credentials,
+}
+
+#[derive(Serialize, Clone)]
+pub struct OpenResult {
+    opened: bool,
+    /// Set when the link wasn't opened pending the user's confirmation.
+    confirm: Option<Risk>,
+}
+
+fn risk(url: &Url) -> Option<Risk> {
+    if url.scheme() == "mailto" {
+        return None;
+    }
+    if !url.username().is_empty() || url.password().is_some() {
+        return Some(Risk::Credentials);
+    }
+    match url.host() {
+        Some(Host::Ipv4(_)) | Some(Host::Ipv6(_)) => return Some(Risk::IpHost),
+        Some(Host::Domain(d)) if d.split('.').any(|l| l.starts_with("xn--")) => {
+            return Some(Risk::Punycode)
+        }
+        _ => {}
+    }
+    (url.scheme() == "http").then_some(Risk::Insecure)
+}
+
+#[tauri::command]
+pub fn open_external(app: AppHandle, url: String, confirmed: Option<bool>) -> Result<OpenResult, CommandError> {
+    let parsed = Url::parse(&url).map_err(|e| CommandError::invalid_input(format!("invalid url: {e}")))?;
+    // file:// and custom schemes would hand the link to a local handler.
+    if !matches!(parsed.scheme(), "http" | "https" | "mailto") {
+        return Err(CommandError::invalid_input(format!("unsupported scheme: {}", parsed.scheme())));
+    }
+    if let Some(risk) = risk(&parsed) {
+        if confirmed != Some(true) {
+            return Ok(OpenResult { opened: false, confirm: Some(risk) });
+        }
+    }
+    app.opener()
+        .open_url(parsed.as_str(), None::<&str>)
+        .map_err(CommandError::io)?;
+    Ok(OpenResult { opened: true, confirm: None })
+}
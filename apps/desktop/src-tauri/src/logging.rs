@@ -0,0 +1,201 @@
+// File logging through tauri-plugin-log. One `distokoloshe.log` in the app
+// log dir, rotated when it passes the size cap or was last written on an
+// earlier day; a week of archives is kept. Anything that looks like the auth
+// token is redacted before it reaches disk. The webview's console errors are
+// relayed into the same file under the `webview` target, rate limited so an
+// error thrown every frame can't fill it.
+
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_log::{FileOpenStrategy, RotationStrategy, Target, TargetKind};
+use tauri_plugin_opener::OpenerExt;
+
+use crate::error::CommandError;
+
+const LOG_NAME: &str = "distokoloshe";
+const MAX_LOG_BYTES: u128 = 5 * 1024 * 1024;
+const KEEP_LOGS: usize = 7;
+const REDACTED: &str = "[redacted]";
+/// Webview messages allowed per window; the rest are counted and dropped.
+const WEBVIEW_BURST: u32 = 50;
+const WEBVIEW_WINDOW: Duration = Duration::from_secs(10);
+/// Longer messages (usually a stack plus a serialized object) are cut.
+const WEBVIEW_MAX_CHARS: usize = 4096;
+
+/// The token currently in use, so it can be scrubbed verbatim.
+static TOKEN: RwLock<Option<String>> = RwLock::new(None);
+
+/// Start of the current window, messages logged in it, and dropped since.
+static WEBVIEW_RATE: Mutex<Option<(Instant, u32, u32)>> = Mutex::new(None);
+
+pub fn remember_token(token: Option<&str>) {
+    *TOKEN.write().unwrap() = token.map(str::to_string);
+}
+
+/// Replace the known token and any `Bearer <x>` credential.
+pub(crate) fn redact(message: &str) -> String {
+    let mut out = message.to_string();
+    if let Some(token) = TOKEN.read().ok().and_then(|t| t.clone()) {
+        if !token.is_empty() {
+            out = out.replace(&token, REDACTED);
+        }
+    }
+    let mut rest = out.as_str();
+    let mut scrubbed = String::with_capacity(out.len());
+    while let Some(i) = rest.find("Bearer ") {
+        let start = i + "Bearer ".len();
+        scrubbed.push_str(&rest[..start]);
+        scrubbed.push_str(REDACTED);
+        let end = rest[start..]
+            .find(|c: char| c.is_whitespace() || c == '"' || c == '\'')
+            .map_or(rest.len(), |n| start + n);
+        rest = &rest[end..];
+    }
+    scrubbed.push_str(rest);
+    scrubbed
+}
+
+/// Scrub a specific `token` from `message`, for tokens that aren't the
+/// remembered one (e.g. one still being validated).
+pub fn redact_token(message: &str, token: &str) -> String {
+    if token.is_empty() {
+        return message.to_string();
+    }
+    message.replace(token, REDACTED)
+}
+
+fn log_dir(app: &AppHandle) -> Result<PathBuf, CommandError> {
+    Ok(app.path().app_log_dir()?)
+}
+
+fn log_path(app: &AppHandle) -> Result<PathBuf, CommandError> {
+    Ok(log_dir(app)?.join(LOG_NAME).with_extension("log"))
+}
+
+fn unix_day(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() / 86_400)
+}
+
+/// Start a fresh file if the existing one is from a previous (UTC) day.
+fn open_strategy(app: &AppHandle) -> FileOpenStrategy {
+    let modified = log_path(app)
+        .ok()
+        .and_then(|p| std::fs::metadata(p).ok())
+        .and_then(|m| m.modified().ok());
+    match modified {
+        Some(m) if unix_day(m) < unix_day(SystemTime::now()) => FileOpenStrategy::Rotate,
+        _ => FileOpenStrategy::Append,
+    }
+}
+
+/// Register the log plugin. Done from setup because picking the open
+/// strategy needs the resolved log dir.
+pub fn init(app: &AppHandle) -> tauri::Result<()> {
+    let plugin = tauri_plugin_log::Builder::new()
+        .clear_targets()
+        .targets([
+            Target::new(TargetKind::Stdout),
+            Target::new(TargetKind::LogDir {
+                file_name: Some(LOG_NAME.into()),
+            }),
+        ])
+        .level(log::LevelFilter::Info)
+        .max_file_size(MAX_LOG_BYTES)
+        .rotation_strategy(RotationStrategy::KeepSome(KEEP_LOGS))
+        .file_open_strategy(open_strategy(app))
+        .format(|out, message, record| {
+            let now = time::OffsetDateTime::now_utc()
+                .format(&time::format_description::well_known::Rfc3339)
+                .unwrap_or_default();
+            out.finish(format_args!(
+                "{now} [{}][{}] {}",
+                record.level(),
+                record.target(),
+                redact(&message.to_string())
+            ))
+        })
+        .build();
+    app.plugin(plugin)
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum WebviewLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl From<WebviewLevel> for log::Level {
+    fn from(level: WebviewLevel) -> Self {
+        match level {
+            WebviewLevel::Error => log::Level::Error,
+            WebviewLevel::Warn => log::Level::Warn,
+            WebviewLevel::Info => log::Level::Info,
+            WebviewLevel::Debug => log::Level::Debug,
+        }
+    }
+}
+
+/// Whether another webview message fits in the current window. Returns the
+/// count dropped in the previous window when a new one opens, so the gap
+/// shows up in the log.
+fn webview_admit() -> (bool, u32) {
+    let mut rate = WEBVIEW_RATE.lock().unwrap();
+    let now = Instant::now();
+    match rate.as_mut() {
+        Some((start, count, dropped)) if now.duration_since(*start) < WEBVIEW_WINDOW => {
+            if *count < WEBVIEW_BURST {
+                *count += 1;
+                (true, 0)
+            } else {
+                *dropped += 1;
+                (false, 0)
+            }
+        }
+        previous => {
+            let dropped = previous.map_or(0, |(_, _, d)| *d);
+            *rate = Some((now, 1, 0));
+            (true, dropped)
+        }
+    }
+}
+
+/// Write a message from the webview's error handler into the native log,
+/// under the `webview` target.
+#[tauri::command]
+pub fn log_from_webview(level: WebviewLevel, message: String) {
+    let (admitted, dropped) = webview_admit();
+    if dropped > 0 {
+        log::warn!(target: "webview", "{dropped} messages dropped by the rate limit");
+    }
+    if !admitted {
+        return;
+    }
+    let message = match message.char_indices().nth(WEBVIEW_MAX_CHARS) {
+        Some((cut, _)) => format!("{}… (truncated)", &message[..cut]),
+        None => message,
+    };
+    log::log!(target: "webview", level.into(), "{message}");
+}
+
+#[tauri::command]
+pub fn get_log_path(app: AppHandle) -> Result<String, CommandError> {
+    Ok(log_path(&app)?.to_string_lossy().into_owned())
+}
+
+#[tauri::command]
+pub fn open_log_folder(app: AppHandle) -> Result<(), CommandError> {
+    let path = log_path(&app)?;
+    // Reveal the file itself when it exists so it's selected in the file manager.
+    let result = if path.exists() {
+        app.opener().reveal_item_in_dir(&path)
+    } else {
+        app.opener().open_path(log_dir(&app)?.to_string_lossy(), None::<&str>)
+    };
+    result.map_err(CommandError::io)
+}
@@ -0,0 +1,221 @@
+// OS-level microphone and camera access, so the call UI can check before
+// connecting instead of failing halfway. macOS asks through TCC and can
+// prompt; Windows has privacy switches the app can read but never prompt
+// for; Linux has no such gate (the webview's own per-origin prompt still
+// applies everywhere).
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::CommandError;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaKind {
+    Microphone,
+    Camera,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionStatus {
+    Granted,
+    Denied,
+    /// Blocked by policy (parental controls, MDM); the user can't change it.
+    Restricted,
+    /// Never asked; `request_media_permissions` will prompt. macOS only.
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    NotDetermined,
+}
+
+#[derive(Serialize, Clone)]
+pub struct MediaPermissions {
+    microphone: PermissionStatus,
+    camera: PermissionStatus,
+}
+
+fn label(media: MediaKind) -> &'static str {
+    match media {
+        MediaKind::Microphone => "Microphone",
+        MediaKind::Camera => "Camera",
+    }
+}
+
+/// The privacy settings page for `media`.
+fn settings_url(media: MediaKind) -> &'static str {
+    match media {
+        #[cfg(target_os = "macos")]
+        MediaKind::Microphone => "x-apple.systempreferences:com.apple.preference.security?Privacy_Microphone",
+        #[cfg(target_os = "macos")]
+        MediaKind::Camera => "x-apple.systempreferences:com.apple.preference.security?Privacy_Camera",
+        #[cfg(not(target_os = "macos"))]
+        MediaKind::Microphone => "ms-settings:privacy-microphone",
+        #[cfg(not(target_os = "macos"))]
+        MediaKind::Camera => "ms-settings:privacy-webcam",
+    }
+}
+
+/// Where `settings_url` leads, spelled out for the message.
+fn settings_path(media: MediaKind) -> String {
+    #[cfg(target_os = "macos")]
+    let root = "System Settings > Privacy & Security";
+    #[cfg(not(target_os = "macos"))]
+    let root = "Settings > Privacy & security";
+    format!("{root} > {}", label(media))
+}
+
+#[cfg(target_os = "macos")]
+mod tcc {
+    use super::{MediaKind, PermissionStatus};
+    use objc2::runtime::Bool;
+    use objc2_av_foundation::{AVAuthorizationStatus, AVCaptureDevice, AVMediaType, AVMediaTypeAudio, AVMediaTypeVideo};
+    use std::sync::Mutex;
+
+    fn media_type(media: MediaKind) -> Option<&'static AVMediaType> {
+        // SAFETY: AVFoundation's constant strings, valid for the process.
+        unsafe {
+            match media {
+                MediaKind::Microphone => AVMediaTypeAudio,
+                MediaKind::Camera => AVMediaTypeVideo,
+            }
+        }
+    }
+
+    pub fn status(media: MediaKind) -> PermissionStatus {
+        let Some(media_type) = media_type(media) else { return PermissionStatus::Granted };
+        // SAFETY: audio and video are the two media types this accepts.
+        match unsafe { AVCaptureDevice::authorizationStatusForMediaType(media_type) } {
+            AVAuthorizationStatus::Authorized => PermissionStatus::Granted,
+            AVAuthorizationStatus::Denied => PermissionStatus::Denied,
+            AVAuthorizationStatus::Restricted => PermissionStatus::Restricted,
+            _ => PermissionStatus::NotDetermined,
+        }
+    }
+
+    /// Show the TCC prompt and wait for the answer.
+    pub async fn request(media: MediaKind) -> bool {
+        let Some(media_type) = media_type(media) else { return true };
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let tx = Mutex::new(Some(tx));
+        let handler = block2::RcBlock::new(move |granted: Bool| {
+            if let Some(tx) = tx.lock().unwrap().take() {
+                let _ = tx.send(granted.as_bool());
+            }
+        });
+        // SAFETY: as in `status`; the block is retained by AVFoundation
+        // until it has been called.
+        unsafe { AVCaptureDevice::requestAccessForMediaType_completionHandler(media_type, &handler) };
+        rx.await.unwrap_or(false)
+    }
+}
+
+#[cfg(windows)]
+mod consent {
+    use super::{MediaKind, PermissionStatus};
+    use windows_sys::Win32::Foundation::ERROR_SUCCESS;
+    use windows_sys::Win32::System::Registry::{
+        RegGetValueW, HKEY, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, RRF_RT_REG_SZ,
+    };
+
+    const STORE: &str = r"Software\Microsoft\Windows\CurrentVersion\CapabilityAccessManager\ConsentStore";
+
+    fn wide(s: &str) -> Vec<u16> {
+        s.encode_utf16().chain(Some(0)).collect()
+    }
+
+    /// The `Value` string ("Allow"/"Deny") under `root\STORE\sub`.
+    fn read(root: HKEY, sub: &str) -> Option<String> {
+        let (key, name) = (wide(&format!(r"{STORE}\{sub}")), wide("Value"));
+        let mut buf = [0u16; 16];
+        let mut size = std::mem::size_of_val(&buf) as u32;
+        // SAFETY: NUL-terminated key and value names; `size` is the buffer's
+        // length in bytes and comes back as the bytes written.
+        let status = unsafe {
+            RegGetValueW(
+                root,
+                key.as_ptr(),
+                name.as_ptr(),
+                RRF_RT_REG_SZ,
+                std::ptr::null_mut(),
+                buf.as_mut_ptr().cast(),
+                &mut size,
+            )
+        };
+        if status != ERROR_SUCCESS {
+            return None;
+        }
+        let len = (size as usize / 2).saturating_sub(1);
+        Some(String::from_utf16_lossy(&buf[..len.min(buf.len())]))
+    }
+
+    pub fn status(media: MediaKind) -> PermissionStatus {
+        let capability = match media {
+            MediaKind::Microphone => "microphone",
+            MediaKind::Camera => "webcam",
+        };
+        let denied = |v: Option<String>| v.as_deref() == Some("Deny");
+        if denied(read(HKEY_LOCAL_MACHINE, capability)) {
+            return PermissionStatus::Restricted;
+        }
+        // The device-wide switch, then the one for desktop (unpackaged) apps.
+        if denied(read(HKEY_CURRENT_USER, capability))
+            || denied(read(HKEY_CURRENT_USER, &format!(r"{capability}\NonPackaged")))
+        {
+            return PermissionStatus::Denied;
+        }
+        PermissionStatus::Granted
+    }
+}
+
+fn status(media: MediaKind) -> PermissionStatus {
+    #[cfg(target_os = "macos")]
+    return tcc::status(media);
+    #[cfg(windows)]
+    return consent::status(media);
+    #[cfg(not(any(target_os = "macos", windows)))]
+    {
+        let _ = media;
+        PermissionStatus::Granted
+    }
+}
+
+#[tauri::command]
+pub fn check_media_permissions() -> MediaPermissions {
+    MediaPermissions {
+        microphone: status(MediaKind::Microphone),
+        camera: status(MediaKind::Camera),
+    }
+}
+
+/// Ask for each of `kinds` that hasn't been decided yet (macOS only; there's
+/// nothing to prompt elsewhere). Fails on the first one that ends up denied
+/// or restricted, with where to fix it.
+#[tauri::command]
+pub async fn request_media_permissions(kinds: Vec<MediaKind>) -> Result<MediaPermissions, CommandError> {
+    for media in kinds {
+        #[cfg(target_os = "macos")]
+        if status(media) == PermissionStatus::NotDetermined {
+            tcc::request(media).await;
+        }
+        match status(media) {
+            PermissionStatus::Denied => {
+                return Err(CommandError::MediaDenied {
+                    media,
+                    settings: settings_url(media),
+                    message: format!(
+                        "{} access is turned off for disTokoloshe. Allow it in {}, then try again.",
+                        label(media),
+                        settings_path(media)
+                    ),
+                });
+            }
+            PermissionStatus::Restricted => {
+                return Err(CommandError::MediaRestricted {
+                    media,
+                    message: format!("{} access is blocked by a system policy on this device.", label(media)),
+                });
+            }
+            PermissionStatus::Granted | PermissionStatus::NotDetermined => {}
+        }
+    }
+    Ok(check_media_permissions())
+}
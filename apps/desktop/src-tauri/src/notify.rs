@@ -0,0 +1,229 @@
+// Native toasts for incoming messages via tauri-plugin-notification.
+//
+// The desktop backend has no click callback, but clicking a toast activates
+// the app on every platform we ship. So a main-window focus shortly after a
+// notification is treated as the click, and routed to that toast's channel.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::error::CommandError;
+use crate::presence::{AuthInfo, AuthState};
+use crate::prefs;
+use crate::sounds::{self, Choice, SoundKind};
+
+pub(crate) const DND: &str = "dnd";
+
+/// How long after showing a toast a focus event still counts as its click.
+const CLICK_WINDOW: Duration = Duration::from_secs(8);
+
+/// Platform sound for toasts outside a call: a toast sound name on Windows,
+/// a system sound on macOS, a freedesktop sound-theme name on Linux.
+#[cfg(windows)]
+const TOAST_SOUND: &str = "Default";
+#[cfg(target_os = "macos")]
+const TOAST_SOUND: &str = "Ping";
+#[cfg(not(any(windows, target_os = "macos")))]
+const TOAST_SOUND: &str = "message-new-instant";
+
+#[derive(Default)]
+pub struct NotifyState {
+    /// Channel the UI is currently showing, set via `set_active_channel`.
+    pub active_channel: Mutex<Option<String>>,
+    /// Channel of the most recent toast and when it was shown.
+    pub last: Mutex<Option<(String, Instant)>>,
+    pub dnd: AtomicBool,
+    /// Set by the webview for the duration of a voice call; toasts still
+    /// show but without a sound.
+    pub in_call: AtomicBool,
+}
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    #[default]
+    Normal,
+    /// Still shown under DND.
+    Critical,
+}
+
+pub fn dnd_enabled(app: &AppHandle) -> bool {
+    app.state::<NotifyState>().dnd.load(Ordering::Relaxed)
+}
+
+pub fn in_call(app: &AppHandle) -> bool {
+    app.state::<NotifyState>().in_call.load(Ordering::Relaxed)
+}
+
+pub(crate) async fn report_dnd(info: &AuthInfo, enabled: bool) -> Result<(), CommandError> {
+    let mut fields = serde_json::Map::new();
+    fields.insert("enabled".into(), enabled.into());
+    crate::presence::post_event_with(info, "dnd", fields).await
+}
+
+fn apply_dnd(app: &AppHandle, enabled: bool) {
+    app.state::<NotifyState>().dnd.store(enabled, Ordering::Relaxed);
+    // The webview plays its own sounds too; tell it to silence them.
+    let _ = app.emit("dnd://changed", enabled);
+    #[cfg(desktop)]
+    if let Err(e) = crate::tray::set_dnd(app, enabled) {
+        log::warn!("failed to update tray for dnd: {e}");
+    }
+}
+
+/// Re-apply persisted DND. Called from setup after the tray exists.
+pub fn restore(app: &AppHandle) {
+    if prefs::get::<bool>(app, DND) == Some(true) {
+        apply_dnd(app, true);
+    }
+}
+
+#[tauri::command]
+pub async fn set_dnd(app: AppHandle, enabled: bool) -> Result<(), CommandError> {
+    prefs::set(&app, DND, &enabled)?;
+    apply_dnd(&app, enabled);
+    let auth = app.state::<AuthState>().0.lock().unwrap().clone();
+    if let Some(info) = auth {
+        report_dnd(&info, enabled).await?;
+    }
+    Ok(())
+}
+
+fn apply_in_call(app: &AppHandle, in_call: bool) {
+    let was = app.state::<NotifyState>().in_call.swap(in_call, Ordering::Relaxed);
+    if was == in_call {
+        return;
+    }
+    crate::callstats::on_call_changed(in_call);
+    let _ = app.emit("call://changed", in_call);
+    #[cfg(desktop)]
+    if let Err(e) = crate::tray::set_in_call(app, in_call) {
+        log::warn!("failed to update tray for call state: {e}");
+    }
+}
+
+/// Mark the user as in (or out of) a voice call.
+#[tauri::command]
+pub fn set_in_call(app: AppHandle, in_call: bool) {
+    apply_in_call(&app, in_call);
+}
+
+/// Drop the call flag when the webview that set it goes away (closed or
+/// reloaded), so a call that crashed doesn't leave toasts muted for good.
+pub fn clear_in_call(app: &AppHandle) {
+    apply_in_call(app, false);
+}
+
+fn main_focused(app: &AppHandle) -> bool {
+    app.get_webview_window("main")
+        .map(|w| w.is_focused().unwrap_or(false) && w.is_visible().unwrap_or(false))
+        .unwrap_or(false)
+}
+
+/// Why a toast went out differently from a plain one.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Suppressed {
+    /// Not shown: do-not-disturb is on.
+    Dnd,
+    /// Not shown: the user is already looking at the channel.
+    Viewing,
+    /// Shown, but without a sound because of a call.
+    InCall,
+}
+
+/// The shared path for every toast: DND (short of critical), then the
+/// viewing check when there's a `channel_id`, then call muting. The sound is
+/// `kind`'s choice (see sounds.rs). `None` means it was shown normally.
+fn show(
+    app: &AppHandle,
+    title: String,
+    body: String,
+    channel_id: Option<String>,
+    severity: Severity,
+    kind: SoundKind,
+) -> Result<Option<Suppressed>, CommandError> {
+    let state = app.state::<NotifyState>();
+    if state.dnd.load(Ordering::Relaxed) && severity != Severity::Critical {
+        return Ok(Some(Suppressed::Dnd));
+    }
+    if let Some(channel_id) = &channel_id {
+        let viewing = state.active_channel.lock().unwrap().as_deref() == Some(channel_id.as_str());
+        if viewing && main_focused(app) {
+            return Ok(Some(Suppressed::Viewing));
+        }
+    }
+    let in_call = state.in_call.load(Ordering::Relaxed);
+    let sound = (!in_call).then(|| sounds::choice(app, kind));
+    let mut builder = app.notification().builder().title(title).body(body);
+    if let Some(Choice::System) = sound {
+        builder = builder.sound(TOAST_SOUND);
+    }
+    builder.show().map_err(|e| e.to_string())?;
+    if let Some(Choice::Play(pcm)) = sound {
+        sounds::play(app, pcm);
+    }
+    // Only toasts for a channel can be routed on click.
+    if let Some(channel_id) = channel_id {
+        *state.last.lock().unwrap() = Some((channel_id, Instant::now()));
+    }
+    Ok(in_call.then_some(Suppressed::InCall))
+}
+
+/// Show a toast unless the user is already looking at `channel_id` or DND
+/// is on (for anything short of critical). Toasts during a call are silent;
+/// otherwise `mention` picks which sound plays, and a mention also flashes
+/// the taskbar or bounces the dock while the window is in the background.
+/// Returns whether one was shown.
+#[tauri::command]
+pub fn notify(
+    app: AppHandle,
+    title: String,
+    body: String,
+    channel_id: String,
+    severity: Option<Severity>,
+    mention: Option<bool>,
+) -> Result<bool, CommandError> {
+    let mention = mention == Some(true);
+    let kind = if mention { SoundKind::Mention } else { SoundKind::Message };
+    let severity = severity.unwrap_or_default();
+    let outcome = show(&app, title, body, Some(channel_id), severity, kind)?;
+    let shown = !matches!(outcome, Some(Suppressed::Dnd | Suppressed::Viewing));
+    if shown && mention {
+        if let Err(e) = crate::window::request_attention(&app, severity == Severity::Critical) {
+            log::warn!("failed to request attention: {e}");
+        }
+    }
+    Ok(shown)
+}
+
+/// Preview a message toast from settings, under the current DND and call
+/// state. Returns what changed it, if anything, so the UI can explain a
+/// missing (or silent) preview.
+#[tauri::command]
+pub fn send_test_notification(app: AppHandle) -> Result<Option<Suppressed>, CommandError> {
+    let title = crate::i18n::t(&app, "notify.test.title");
+    let body = crate::i18n::t(&app, "notify.test.body");
+    show(&app, title, body, None, Severity::Normal, SoundKind::Message)
+}
+
+#[tauri::command]
+pub fn set_active_channel(state: tauri::State<'_, NotifyState>, channel_id: Option<String>) {
+    *state.active_channel.lock().unwrap() = channel_id;
+}
+
+/// Called when the main window gains focus; emits `notification://clicked`
+/// if a toast was shown recently enough that this is probably its click.
+pub fn on_main_focused(app: &AppHandle) {
+    let last = app.state::<NotifyState>().last.lock().unwrap().take();
+    if let Some((channel_id, shown)) = last {
+        if shown.elapsed() <= CLICK_WINDOW {
+            crate::tray::show_main_window(app);
+            let _ = app.emit("notification://clicked", channel_id);
+        }
+    }
+}
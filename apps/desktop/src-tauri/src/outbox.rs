@@ -0,0 +1,200 @@
+// Beacons that never reached the server (offline, DNS, timeout) wait here
+// and go out in order on `net://online`, so presence catches up after a blip
+// instead of drifting. Only the latest entry per subject is kept (a join and
+// a leave, or a typing ping per channel), and old ones are dropped unsent.
+// Persisted without the token, which is read from the current sign-in when
+// flushing.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+use crate::presence::{self, AuthState, Undelivered};
+
+const OUTBOX_FILE: &str = "outbox.json";
+/// Oldest entries are dropped past this.
+const MAX_QUEUED: usize = 50;
+/// Past this, a join, leave or presence change no longer says anything true.
+const MAX_AGE: Duration = Duration::from_secs(10 * 60);
+/// Typing is only interesting for a few seconds.
+const TYPING_MAX_AGE: Duration = Duration::from_secs(30);
+
+static QUEUE: Mutex<Vec<Queued>> = Mutex::new(Vec::new());
+static PATH: OnceLock<PathBuf> = OnceLock::new();
+static FLUSHING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Queued {
+    server_url: String,
+    event: String,
+    fields: Map<String, Value>,
+    /// Unix ms when it first failed.
+    queued_at: u64,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// What an event says something about. A newer event with the same subject
+/// replaces any queued one.
+fn subject(event: &str, fields: &Map<String, Value>) -> String {
+    match event {
+        "join" | "leave" => "membership".into(),
+        "typing" | "typing_stop" => {
+            let channel = fields.get("channel_id").and_then(Value::as_str).unwrap_or_default();
+            format!("typing:{channel}")
+        }
+        // `presence` carries one flag at a time (dnd, invisible, deafened).
+        _ => {
+            let mut keys: Vec<&str> = fields.keys().map(String::as_str).collect();
+            keys.sort_unstable();
+            format!("{event}:{}", keys.join(","))
+        }
+    }
+}
+
+impl Queued {
+    fn subject(&self) -> String {
+        subject(&self.event, &self.fields)
+    }
+
+    fn is_stale(&self, now: u64) -> bool {
+        let max_age = if self.event.starts_with("typing") { TYPING_MAX_AGE } else { MAX_AGE };
+        now.saturating_sub(self.queued_at) > max_age.as_millis() as u64
+    }
+}
+
+fn save(queue: &[Queued]) {
+    let Some(path) = PATH.get() else { return };
+    let result = if queue.is_empty() {
+        match std::fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+            _ => Ok(()),
+        }
+    } else {
+        serde_json::to_string(queue)
+            .map_err(|e| e.to_string())
+            .and_then(|json| std::fs::write(path, json).map_err(|e| e.to_string()))
+    };
+    if let Err(e) = result {
+        log::warn!("failed to save outbox: {e}");
+    }
+}
+
+/// Load what's left from the last run. Called from setup; in safe mode the
+/// outbox stays in memory.
+pub fn init(app: &AppHandle, persist: bool) {
+    if !persist {
+        return;
+    }
+    let Ok(dir) = app.path().app_config_dir() else { return };
+    let path = dir.join(OUTBOX_FILE);
+    let loaded: Vec<Queued> = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default();
+    let _ = PATH.set(path);
+    let now = now_ms();
+    let mut queue = QUEUE.lock().unwrap();
+    *queue = loaded.into_iter().filter(|q| !q.is_stale(now)).collect();
+    save(&queue);
+}
+
+/// Queue an event that got no response. Heartbeats aren't worth replaying;
+/// the next one covers it.
+pub fn enqueue(server_url: &str, event: &str, fields: Map<String, Value>) {
+    if event == "ping" {
+        return;
+    }
+    let entry = Queued {
+        server_url: server_url.to_string(),
+        event: event.to_string(),
+        fields,
+        queued_at: now_ms(),
+    };
+    let subject = entry.subject();
+    let mut queue = QUEUE.lock().unwrap();
+    queue.retain(|q| q.server_url != server_url || q.subject() != subject);
+    queue.push(entry);
+    if queue.len() > MAX_QUEUED {
+        let excess = queue.len() - MAX_QUEUED;
+        queue.drain(..excess);
+    }
+    save(&queue);
+}
+
+/// A fresh event went through; drop queued ones it makes redundant.
+pub fn superseded(server_url: &str, event: &str, fields: &Map<String, Value>) {
+    let subject = subject(event, fields);
+    let mut queue = QUEUE.lock().unwrap();
+    let before = queue.len();
+    queue.retain(|q| q.server_url != server_url || q.subject() != subject);
+    if queue.len() != before {
+        save(&queue);
+    }
+}
+
+/// Forget everything, e.g. on logout: queued events belong to the token
+/// that's going away.
+pub fn clear() {
+    let mut queue = QUEUE.lock().unwrap();
+    if !queue.is_empty() {
+        queue.clear();
+        save(&queue);
+    }
+}
+
+/// Send the queue in order. Entries for another server, stale ones and
+/// joins held back by ghost mode are dropped; a send that still gets no
+/// response stops the flush with the rest kept for next time.
+pub fn flush(app: &AppHandle) {
+    if crate::net::paused() || crate::capabilities::outdated(app) || FLUSHING.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let Some(info) = app.state::<AuthState>().0.lock().unwrap().clone() else { break };
+            let next = {
+                let now = now_ms();
+                let mut queue = QUEUE.lock().unwrap();
+                let before = queue.len();
+                queue.retain(|q| {
+                    q.server_url == info.server_url
+                        && !q.is_stale(now)
+                        && !(q.event == "join" && presence::invisible())
+                });
+                if queue.len() != before {
+                    save(&queue);
+                }
+                queue.first().cloned()
+            };
+            let Some(entry) = next else { break };
+            match presence::deliver(&info, &entry.event, entry.fields.clone()).await {
+                Err(Undelivered::Unreachable(e)) => {
+                    log::debug!("outbox flush stopped, still unreachable: {e}");
+                    break;
+                }
+                Err(Undelivered::Rejected { message: e, .. }) => log::info!("queued {} dropped: {e}", entry.event),
+                Ok(()) => {}
+            }
+            // Removed by identity: a newer event may have replaced it meanwhile.
+            let mut queue = QUEUE.lock().unwrap();
+            if let Some(i) = queue
+                .iter()
+                .position(|q| q.queued_at == entry.queued_at && q.event == entry.event && q.fields == entry.fields)
+            {
+                queue.remove(i);
+                save(&queue);
+            }
+        }
+        FLUSHING.store(false, Ordering::Relaxed);
+    });
+}
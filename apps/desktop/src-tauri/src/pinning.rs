@@ -0,0 +1,276 @@
+// Certificate pinning, per server. With a pin set for a server, native
+// connections to its host (beacons, heartbeat, event stream, the updater,
+// the pipe socket) only complete a handshake when its certificate has
+// exactly that SHA-256 fingerprint; the CA chain no longer matters, so a
+// pinned self-signed certificate works and a CA-issued impostor doesn't. The
+// handshake signature is still checked, so presenting the certificate isn't
+// enough without its key.
+//
+// Every other host (a CDN serving update bundles, another saved server) is
+// validated as usual against the bundled roots and any custom CA, so a pin
+// for one server doesn't break the rest.
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, RwLock};
+use tauri::AppHandle;
+use url::Url;
+
+use crate::error::CommandError;
+use crate::{net, prefs};
+
+/// `{ host: fingerprint hex }`. Before pins were per server this held a
+/// single fingerprint; `restore` moves that onto the active server.
+pub(crate) const PIN_PREF: &str = "pinned_cert_sha256";
+
+/// By host, as `pin_host` gives it.
+static PINS: RwLock<BTreeMap<String, [u8; 32]>> = RwLock::new(BTreeMap::new());
+static LAST_MISMATCH: Mutex<Option<Mismatch>> = Mutex::new(None);
+
+/// A handshake refused for the wrong certificate.
+#[derive(Serialize, Clone)]
+pub struct Mismatch {
+    host: String,
+    /// What the server presented, hex.
+    fingerprint: String,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Hex, any case, with or without `:` or space separators.
+fn parse_fingerprint(raw: &str) -> Result<[u8; 32], CommandError> {
+    let digits: String = raw.chars().filter(|c| !matches!(c, ':' | ' ')).collect();
+    if digits.len() != 64 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(CommandError::invalid_input("fingerprint must be 64 hex digits (a SHA-256 hash)"));
+    }
+    let mut out = [0u8; 32];
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&digits[i * 2..i * 2 + 2], 16).map_err(CommandError::invalid_input)?;
+    }
+    Ok(out)
+}
+
+/// The host a pin for `server_url` covers, in the form rustls gives the
+/// verifier: lowercase, IPv6 without brackets.
+fn pin_host(server_url: &str) -> Result<String, CommandError> {
+    let url = Url::parse(server_url.trim())
+        .map_err(|e| CommandError::invalid_input(format!("invalid server url: {e}")))?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| CommandError::invalid_input("server url has no host"))?;
+    Ok(host.trim_start_matches('[').trim_end_matches(']').to_ascii_lowercase())
+}
+
+#[derive(Debug)]
+struct PinVerifier {
+    pins: BTreeMap<String, [u8; 32]>,
+    /// Ordinary validation, for hosts without a pin.
+    fallback: Arc<WebPkiServerVerifier>,
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let host = server_name.to_str().to_ascii_lowercase();
+        let Some(pin) = self.pins.get(&host) else {
+            return self
+                .fallback
+                .verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now);
+        };
+        let got: [u8; 32] = Sha256::digest(end_entity.as_ref()).into();
+        if got == *pin {
+            return Ok(ServerCertVerified::assertion());
+        }
+        let mismatch = Mismatch {
+            host,
+            fingerprint: hex(&got),
+        };
+        log::error!(
+            "certificate pin mismatch for {}: server presented {}",
+            mismatch.host,
+            mismatch.fingerprint
+        );
+        *LAST_MISMATCH.lock().unwrap() = Some(mismatch);
+        Err(rustls::Error::General("certificate does not match the pinned fingerprint".into()))
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+/// What unpinned hosts are checked against: the same roots reqwest bundles,
+/// plus the custom CA if one is set.
+fn fallback_roots() -> RootCertStore {
+    let mut roots = RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    if let Some(pem) = net::custom_ca_pem() {
+        for cert in CertificateDer::pem_slice_iter(pem.as_bytes()).flatten() {
+            if let Err(e) = roots.add(cert) {
+                log::warn!("custom root certificate not usable with a pin set: {e}");
+            }
+        }
+    }
+    roots
+}
+
+/// TLS settings enforcing the pins, for clients to use in place of their
+/// own; `None` when no server is pinned.
+pub fn tls_config() -> Option<ClientConfig> {
+    let pins = PINS.read().unwrap().clone();
+    if pins.is_empty() {
+        return None;
+    }
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let fallback = WebPkiServerVerifier::builder_with_provider(Arc::new(fallback_roots()), provider.clone())
+        .build()
+        .map_err(|e| log::error!("pinned TLS config unavailable: {e}"))
+        .ok()?;
+    let config = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| log::error!("pinned TLS config unavailable: {e}"))
+        .ok()?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(PinVerifier { pins, fallback, provider }))
+        .with_no_client_auth();
+    Some(config)
+}
+
+fn save(app: &AppHandle, pins: &BTreeMap<String, [u8; 32]>) -> Result<(), CommandError> {
+    let saved: BTreeMap<&str, String> = pins.iter().map(|(host, pin)| (host.as_str(), hex(pin))).collect();
+    prefs::set(app, PIN_PREF, &saved)
+}
+
+/// Re-apply the persisted pins. Called from setup with `net::restore`.
+pub fn restore(app: &AppHandle) {
+    let Some(saved) = prefs::get::<Value>(app, PIN_PREF) else { return };
+    let saved: BTreeMap<String, String> = match saved {
+        // A pin from before they were per server was for the active one.
+        Value::String(raw) => {
+            let active = prefs::get::<String>(app, crate::servers::ACTIVE).or_else(|| net::server_url(app));
+            let Some(host) = active.and_then(|url| pin_host(&url).ok()) else {
+                log::warn!("dropping saved certificate pin: no server to apply it to");
+                let _ = prefs::remove(app, PIN_PREF);
+                return;
+            };
+            BTreeMap::from([(host, raw)])
+        }
+        saved => serde_json::from_value(saved).unwrap_or_default(),
+    };
+    let mut pins = BTreeMap::new();
+    for (host, raw) in saved {
+        match parse_fingerprint(&raw) {
+            Ok(pin) => {
+                pins.insert(host, pin);
+            }
+            Err(e) => log::warn!("ignoring saved certificate pin for {host}: {e}"),
+        }
+    }
+    if let Err(e) = save(app, &pins) {
+        log::warn!("failed to save migrated certificate pins: {e}");
+    }
+    *PINS.write().unwrap() = pins;
+}
+
+/// Pin `server_url`'s certificate to `sha256` (its SHA-256 fingerprint in
+/// hex), or clear its pin with `None`. Only that server's host is affected.
+/// Applies to the next connection.
+#[tauri::command]
+pub fn set_pinned_cert_fingerprint(
+    app: AppHandle,
+    server_url: String,
+    sha256: Option<String>,
+) -> Result<(), CommandError> {
+    let host = pin_host(&server_url)?;
+    let pin = sha256.as_deref().map(parse_fingerprint).transpose()?;
+    let mut pins = PINS.read().unwrap().clone();
+    match pin {
+        Some(pin) => pins.insert(host.clone(), pin),
+        None => pins.remove(&host),
+    };
+    save(&app, &pins)?;
+    *PINS.write().unwrap() = pins;
+    let mut mismatch = LAST_MISMATCH.lock().unwrap();
+    if mismatch.as_ref().is_some_and(|m| m.host == host) {
+        *mismatch = None;
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+pub struct CertPin {
+    /// Normalized hex, `None` when not pinned.
+    fingerprint: Option<String>,
+    /// The last connection refused for presenting another certificate.
+    last_mismatch: Option<Mismatch>,
+}
+
+/// The pin for `server_url`, and the last refused connection to it.
+#[tauri::command]
+pub fn get_pinned_cert_fingerprint(server_url: String) -> Result<CertPin, CommandError> {
+    let host = pin_host(&server_url)?;
+    Ok(CertPin {
+        fingerprint: PINS.read().unwrap().get(&host).map(|p| hex(p)),
+        last_mismatch: LAST_MISMATCH.lock().unwrap().clone().filter(|m| m.host == host),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_fingerprint, pin_host};
+    use crate::error::CommandError;
+
+    #[test]
+    fn pin_host_matches_rustls_server_name() {
+        assert_eq!(pin_host("https://Chat.Example.com:8443/api").unwrap(), "chat.example.com");
+        assert_eq!(pin_host("https://[::1]:3000").unwrap(), "::1");
+        assert_eq!(pin_host(" http://10.0.0.2 ").unwrap(), "10.0.0.2");
+        assert!(matches!(pin_host("not a url"), Err(CommandError::InvalidInput { .. })));
+    }
+
+    #[test]
+    fn parse_fingerprint_takes_separators_and_either_case() {
+        let plain = "ab".repeat(32);
+        let colons = vec!["AB"; 32].join(":");
+        assert_eq!(parse_fingerprint(&plain).unwrap(), [0xab; 32]);
+        assert_eq!(parse_fingerprint(&colons).unwrap(), [0xab; 32]);
+        assert!(matches!(parse_fingerprint("abcd"), Err(CommandError::InvalidInput { .. })));
+        assert!(matches!(parse_fingerprint(&"zz".repeat(32)), Err(CommandError::InvalidInput { .. })));
+    }
+}
@@ -14,6 +14,8 @@ use tokio::sync::{mpsc, Mutex as TokioMutex};
 use tokio_tungstenite::tungstenite::client::IntoClientRequest;
 use tokio_tungstenite::tungstenite::protocol::Message;
 
+use crate::error::CommandError;
+
 #[cfg(windows)]
 const CREATE_NO_WINDOW: u32 = 0x0800_0000;
 
@@ -122,6 +124,7 @@ async fn fetch_title(ytdlp_path: &std::path::Path, url: &str) -> Result<String,
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn pipe_start(
     app: AppHandle,
     state: tauri::State<'_, PipeState>,
@@ -131,12 +134,12 @@ pub async fn pipe_start(
     kind: String,        // "url" | "file"
     source: String,      // URL or absolute file path
     title_hint: Option<String>,
-) -> Result<String, String> {
+) -> Result<String, CommandError> {
     // Atomically claim the slot so concurrent invocations don't double-start.
     {
         let mut guard = state.0.lock().await;
         if guard.is_some() {
-            return Err("A pipe is already active".into());
+            return Err(CommandError::invalid_input("A pipe is already active"));
         }
         *guard = Some(ActivePipe { ffmpeg: None, ytdlp: None, stop: None });
     }
@@ -156,7 +159,7 @@ pub async fn pipe_start(
         Err(e) => {
             emit(&app, "error", None, Some(e.clone()));
             release(&state).await;
-            return Err(e);
+            return Err(CommandError::io(e));
         }
     };
     let ytdlp_path = match resolve_sidecar("yt-dlp") {
@@ -164,7 +167,7 @@ pub async fn pipe_start(
         Err(e) => {
             emit(&app, "error", None, Some(e.clone()));
             release(&state).await;
-            return Err(e);
+            return Err(CommandError::io(e));
         }
     };
 
@@ -195,7 +198,7 @@ pub async fn pipe_start(
             Err(e) => {
                 emit(&app, "error", None, Some(e.clone()));
                 release(&state).await;
-                return Err(e);
+                return Err(e.into());
             }
         }
 
@@ -222,7 +225,7 @@ pub async fn pipe_start(
                 let msg = format!("yt-dlp spawn: {e}");
                 emit(&app, "error", None, Some(msg.clone()));
                 release(&state).await;
-                return Err(msg);
+                return Err(CommandError::io(msg));
             }
         });
         ffmpeg_input = "pipe:0".into();
@@ -247,7 +250,7 @@ pub async fn pipe_start(
             emit(&app, "error", None, Some(msg.clone()));
             if let Some(mut yt) = ytdlp_for_bridge.take() { let _ = yt.kill().await; }
             release(&state).await;
-            return Err(msg);
+            return Err(CommandError::io(msg));
         }
     };
 
@@ -268,7 +271,7 @@ pub async fn pipe_start(
             let msg = "ffmpeg had no stdin handle for bridge".to_string();
             emit(&app, "error", None, Some(msg.clone()));
             release(&state).await;
-            return Err(msg);
+            return Err(CommandError::io(msg));
         };
         let app_for_bridge = app.clone();
         tokio::spawn(async move {
@@ -373,9 +376,9 @@ pub async fn pipe_start(
                 None => None,
             }
         };
-        let bad_exit = code.map_or(true, |c| c != 0);
+        let bad_exit = code != Some(0);
         if bad_exit || bytes_seen == 0 {
-            let stderr_text = String::from_utf8_lossy(&*stderr_for_decoder.lock().await).trim().to_string();
+            let stderr_text = String::from_utf8_lossy(&stderr_for_decoder.lock().await).trim().to_string();
             let tail: String = stderr_text
                 .lines()
                 .rev()
@@ -401,7 +404,7 @@ pub async fn pipe_start(
         Err(e) => {
             emit(&app, "error", None, Some(e.clone()));
             release(&state).await;
-            return Err(e);
+            return Err(CommandError::invalid_input(e));
         }
     };
 
@@ -416,7 +419,10 @@ pub async fn pipe_start(
                 return;
             }
         };
-        let (ws_stream, _resp) = match tokio_tungstenite::connect_async(req).await {
+        // A certificate pin covers this socket too.
+        let connector = crate::pinning::tls_config()
+            .map(|tls| tokio_tungstenite::Connector::Rustls(std::sync::Arc::new(tls)));
+        let (ws_stream, _resp) = match tokio_tungstenite::connect_async_tls_with_config(req, None, false, connector).await {
             Ok(s) => s,
             Err(e) => {
                 emit(&app_for_uploader, "error", None, Some(format!("ws connect: {e}")));
@@ -440,10 +446,8 @@ pub async fn pipe_start(
         let _reader = tokio::spawn(async move {
             while let Some(msg) = stream.next().await {
                 match msg {
-                    Ok(Message::Text(t)) => {
-                        if t.contains("\"busy\"") {
-                            emit(&app_for_reader, "error", None, Some("Another user is piping audio".into()));
-                        }
+                    Ok(Message::Text(t)) if t.contains("\"busy\"") => {
+                        emit(&app_for_reader, "error", None, Some("Another user is piping audio".into()));
                     }
                     Ok(Message::Close(_)) | Err(_) => break,
                     _ => {}
@@ -510,7 +514,7 @@ pub async fn pipe_start(
 #[tauri::command]
 pub async fn pipe_stop(
     state: tauri::State<'_, PipeState>,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     let mut guard = state.0.lock().await;
     if let Some(mut active) = guard.take() {
         if let Some(tx) = active.stop.take() {
@@ -548,24 +552,21 @@ fn build_ws_url(server_url: &str, token: &str, room_id: i64) -> Result<String, S
     ))
 }
 
-/// Best-effort synchronous teardown for app shutdown / window close paths.
-/// Runs on the Tauri main thread; uses Tauri's tokio runtime to drive the lock.
-pub fn force_stop_blocking(app: &AppHandle) {
+/// Best-effort teardown for app shutdown / window close paths.
+pub async fn force_stop(app: &AppHandle) {
     let Some(state) = app.try_state::<PipeState>() else { return };
-    tauri::async_runtime::block_on(async move {
-        let mut guard = state.0.lock().await;
-        if let Some(mut active) = guard.take() {
-            if let Some(tx) = active.stop.take() {
-                let _ = tx.send(()).await;
-            }
-            if let Some(arc) = active.ytdlp.take() {
-                let mut c = arc.lock().await;
-                if let Some(mut child) = c.take() { let _ = child.kill().await; }
-            }
-            if let Some(arc) = active.ffmpeg.take() {
-                let mut c = arc.lock().await;
-                if let Some(mut child) = c.take() { let _ = child.kill().await; }
-            }
+    let mut guard = state.0.lock().await;
+    if let Some(mut active) = guard.take() {
+        if let Some(tx) = active.stop.take() {
+            let _ = tx.send(()).await;
         }
-    });
+        if let Some(arc) = active.ytdlp.take() {
+            let mut c = arc.lock().await;
+            if let Some(mut child) = c.take() { let _ = child.kill().await; }
+        }
+        if let Some(arc) = active.ffmpeg.take() {
+            let mut c = arc.lock().await;
+            if let Some(mut child) = c.take() { let _ = child.kill().await; }
+        }
+    }
 }
@@ -0,0 +1,142 @@
+// Power source and battery level, so large downloads can wait for the
+// charger. Read straight from the OS (GetSystemPowerStatus, sysfs, pmset)
+// rather than through a battery crate: `battery` is unmaintained, and
+// `starship-battery` brings its own platform bindings (nix, IOKit, another
+// windows-sys) to answer the two questions asked here, which each OS answers
+// in a few lines. Also sleep and wake, where the OS announces them, so the
+// heartbeat doesn't run across a suspend.
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+#[derive(Serialize, Clone, Copy, Default)]
+pub struct PowerState {
+    /// Running off the battery. False on desktops and when unknown.
+    pub on_battery: bool,
+    /// `None` without a battery or when the OS won't say.
+    pub battery_percent: Option<u8>,
+}
+
+#[cfg(windows)]
+pub fn power_state() -> PowerState {
+    use windows_sys::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+    // SAFETY: plain-old-data out-param, only read on success.
+    let mut status: SYSTEM_POWER_STATUS = unsafe { std::mem::zeroed() };
+    if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+        return PowerState::default();
+    }
+    // BatteryFlag 128 means no system battery; 255 anywhere means unknown.
+    let has_battery = status.BatteryFlag != 128 && status.BatteryFlag != 255;
+    PowerState {
+        on_battery: has_battery && status.ACLineStatus == 0,
+        battery_percent: (has_battery && status.BatteryLifePercent <= 100)
+            .then_some(status.BatteryLifePercent),
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn power_state() -> PowerState {
+    let mut state = PowerState::default();
+    let Ok(entries) = std::fs::read_dir("/sys/class/power_supply") else {
+        return state;
+    };
+    for entry in entries.flatten() {
+        let read = |name: &str| {
+            std::fs::read_to_string(entry.path().join(name))
+                .map(|s| s.trim().to_string())
+                .unwrap_or_default()
+        };
+        if read("type") != "Battery" {
+            continue;
+        }
+        if read("status") == "Discharging" {
+            state.on_battery = true;
+        }
+        if let Ok(percent) = read("capacity").parse::<u8>() {
+            state.battery_percent = Some(state.battery_percent.map_or(percent, |p| p.min(percent)));
+        }
+    }
+    state
+}
+
+#[cfg(target_os = "macos")]
+pub fn power_state() -> PowerState {
+    // e.g. "Now drawing from 'Battery Power'\n -InternalBattery-0 (id=…)\t85%; discharging; …"
+    let Ok(output) = std::process::Command::new("pmset").args(["-g", "batt"]).output() else {
+        return PowerState::default();
+    };
+    let text = String::from_utf8_lossy(&output.stdout);
+    let battery_percent = text
+        .split(|c: char| c.is_whitespace())
+        .find_map(|word| word.strip_suffix("%;")?.parse().ok());
+    PowerState {
+        on_battery: text.contains("'Battery Power'"),
+        battery_percent,
+    }
+}
+
+#[cfg(not(any(windows, target_os = "linux", target_os = "macos")))]
+pub fn power_state() -> PowerState {
+    PowerState::default()
+}
+
+#[cfg(windows)]
+unsafe extern "system" fn on_power_event(
+    context: *const std::ffi::c_void,
+    kind: u32,
+    _setting: *const std::ffi::c_void,
+) -> u32 {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{PBT_APMRESUMEAUTOMATIC, PBT_APMSUSPEND};
+    // SAFETY: the context is the handle leaked in `watch_sleep`.
+    let app = unsafe { &*context.cast::<AppHandle>() };
+    match kind {
+        PBT_APMSUSPEND => crate::presence::suspend(app),
+        // Sent on every resume, whether or not the user is there yet.
+        PBT_APMRESUMEAUTOMATIC => crate::presence::resume(app),
+        _ => {}
+    }
+    0
+}
+
+/// Pause and resume presence around sleep, on platforms that say when it
+/// happens. Returns false elsewhere, or if registering failed, so the
+/// caller can fall back to watching the clock.
+#[cfg(windows)]
+pub fn watch_sleep(app: &AppHandle) -> bool {
+    use windows_sys::Win32::Foundation::ERROR_SUCCESS;
+    use windows_sys::Win32::System::Power::{
+        PowerRegisterSuspendResumeNotification, DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::DEVICE_NOTIFY_CALLBACK;
+
+    // Registered for the life of the process, so both are leaked.
+    let params = Box::leak(Box::new(DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS {
+        Callback: Some(on_power_event),
+        Context: Box::into_raw(Box::new(app.clone())).cast(),
+    }));
+    let mut registration = std::ptr::null_mut();
+    // SAFETY: with DEVICE_NOTIFY_CALLBACK the recipient is the subscribe
+    // parameters, which outlive the registration.
+    let status = unsafe {
+        PowerRegisterSuspendResumeNotification(
+            DEVICE_NOTIFY_CALLBACK,
+            (params as *mut DEVICE_NOTIFY_SUBSCRIBE_PARAMETERS).cast(),
+            &mut registration,
+        )
+    };
+    if status != ERROR_SUCCESS {
+        log::warn!("couldn't register for sleep notifications: error {status}");
+        return false;
+    }
+    true
+}
+
+#[cfg(not(windows))]
+pub fn watch_sleep(_app: &AppHandle) -> bool {
+    false
+}
+
+#[tauri::command]
+pub fn get_power_state() -> PowerState {
+    power_state()
+}
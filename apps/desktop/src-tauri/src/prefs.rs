@@ -0,0 +1,301 @@
+// Persisted native preferences: a flat JSON object in the app config dir,
+// cached in memory and written through on every change. Every change is
+// announced as `prefs://changed`, and the webview can keep its own keys here
+// via `get_pref` / `set_pref`. Writes go to a temp file renamed over the
+// real one, with the previous version kept as `prefs.json.bak`, so being
+// killed mid-write can cost the last change but never the whole file.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::CommandError;
+
+pub(crate) const PREFS_FILE: &str = "prefs.json";
+
+pub const CLOSE_TO_TRAY: &str = "close_to_tray";
+/// Last value set through `set_autostart`; the OS login item is the truth.
+pub const AUTOSTART: &str = "autostart";
+/// Default for `check_for_update` when it isn't given a channel.
+pub const UPDATE_CHANNEL: &str = "update_channel";
+
+/// Keys applied by their own commands. `set_pref` refuses them so a raw
+/// write can't skip the side effects (or, for devtools, the support code).
+/// Named by the owning module's const, so a renamed key can't slip out.
+const NATIVE_KEYS: &[&str] = &[
+    crate::accent::ACCENT_PREF,
+    crate::servers::ACTIVE,
+    crate::window::ALWAYS_ON_TOP,
+    crate::volume::APP_VOLUME,
+    crate::audio::PREFERRED_INPUT,
+    crate::audio::PREFERRED_OUTPUT,
+    crate::AUTO_INSTALL_PREF,
+    AUTOSTART,
+    crate::idle::AWAY_ON_BLUR_PREF,
+    crate::throttle::THROTTLE_PREF,
+    crate::presence::RATE_LIMIT_PREF,
+    CLOSE_TO_TRAY,
+    crate::window::CONTENT_PROTECTED,
+    crate::net::CUSTOM_CA,
+    crate::window::DECORATIONS,
+    crate::devtools::ENABLED,
+    crate::notify::DND,
+    crate::gpu::HARDWARE_ACCELERATION,
+    crate::presence::INVISIBLE_PREF,
+    crate::presence::LEAVE_GRACE_PREF,
+    crate::i18n::LOCALE_PREF,
+    crate::pinning::PIN_PREF,
+    crate::net::PROXY,
+    crate::ROLLBACK_PREF,
+    crate::crash::SEND_CRASH_REPORTS,
+    crate::servers::SERVERS,
+    crate::sounds::MENTION_PREF,
+    crate::sounds::MESSAGE_PREF,
+    crate::theme::THEME_PREF,
+    crate::rtc::TRANSPORT_PREF,
+    crate::download::BANDWIDTH_PREF,
+    crate::update_window::DEFERRED_PREF,
+    crate::UPDATE_BATTERY_PREF,
+    crate::update_window::WINDOW_PREF,
+    crate::net::USER_AGENT,
+    crate::vad::THRESHOLD_PREF,
+    crate::quality::QUALITY_PREF,
+    crate::zoom::ZOOM,
+];
+
+pub struct Prefs {
+    map: Mutex<Map<String, Value>>,
+    /// Off in safe mode: changes apply for the session but never hit disk,
+    /// so the user's saved file is left untouched.
+    persist: bool,
+}
+
+fn prefs_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path().app_config_dir().ok().map(|d| d.join(PREFS_FILE))
+}
+
+/// `prefs.json` with `suffix` appended.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+fn read_map(path: &Path) -> Option<Map<String, Value>> {
+    let s = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&s).ok()
+}
+
+/// The prefs at `path`, or the backup beside it when the file is missing or
+/// doesn't parse (a write cut short). Empty if neither is usable.
+pub(crate) fn read_with_backup(path: &Path) -> Map<String, Value> {
+    if let Some(map) = read_map(path) {
+        return map;
+    }
+    let backup = sibling(path, ".bak");
+    match read_map(&backup) {
+        Some(map) => {
+            if path.exists() {
+                log::warn!("{} is corrupt, restored from {}", path.display(), backup.display());
+            }
+            map
+        }
+        None => Map::new(),
+    }
+}
+
+/// Write `json` to a temp file, then swap it in: the old file becomes the
+/// backup and the temp file takes its name. Each step is a rename, so at
+/// every point one of the two holds a complete copy.
+fn write_atomic(path: &Path, json: &str) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let tmp = sibling(path, ".tmp");
+    let mut file = std::fs::File::create(&tmp)?;
+    file.write_all(json.as_bytes())?;
+    file.sync_all()?;
+    drop(file);
+    match std::fs::rename(path, sibling(path, ".bak")) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    std::fs::rename(&tmp, path)
+}
+
+/// Read the prefs file, falling back to its backup, treating neither being
+/// usable as empty.
+pub fn load(app: &AppHandle) -> Prefs {
+    let map = prefs_path(app)
+        .map(|p| read_with_backup(&p))
+        .unwrap_or_default();
+    Prefs {
+        map: Mutex::new(map),
+        persist: true,
+    }
+}
+
+/// Defaults only, nothing read or written. Used in safe mode.
+pub fn ephemeral() -> Prefs {
+    Prefs {
+        map: Mutex::new(Map::new()),
+        persist: false,
+    }
+}
+
+pub fn get<T: DeserializeOwned>(app: &AppHandle, key: &str) -> Option<T> {
+    let prefs = app.state::<Prefs>();
+    let map = prefs.map.lock().unwrap();
+    map.get(key).and_then(|v| serde_json::from_value(v.clone()).ok())
+}
+
+/// A copy of every stored key.
+pub fn all(app: &AppHandle) -> Map<String, Value> {
+    app.state::<Prefs>().map.lock().unwrap().clone()
+}
+
+pub fn set<T: Serialize>(app: &AppHandle, key: &str, value: &T) -> Result<(), CommandError> {
+    let value = serde_json::to_value(value)?;
+    let prefs = app.state::<Prefs>();
+    let mut map = prefs.map.lock().unwrap();
+    map.insert(key.to_string(), value.clone());
+    if prefs.persist {
+        write(app, &map)?;
+    }
+    drop(map);
+    let _ = app.emit("prefs://changed", Changed { key, value });
+    Ok(())
+}
+
+#[derive(Serialize, Clone)]
+struct Changed<'a> {
+    key: &'a str,
+    value: Value,
+}
+
+fn write(app: &AppHandle, map: &Map<String, Value>) -> Result<(), CommandError> {
+    let path = prefs_path(app).ok_or_else(|| CommandError::io("no app config dir"))?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_string_pretty(map)?;
+    Ok(write_atomic(&path, &json)?)
+}
+
+/// Typed view of the well-known keys, defaults filled in for anything unset.
+#[derive(Serialize)]
+pub struct Known {
+    pub close_to_tray: bool,
+    pub autostart: bool,
+    pub update_channel: Option<String>,
+    pub auto_install: bool,
+    pub update_min_battery: u8,
+    pub dnd: bool,
+    pub always_on_top: bool,
+    pub decorations: bool,
+    pub zoom: f64,
+}
+
+pub fn known(app: &AppHandle) -> Known {
+    Known {
+        close_to_tray: get(app, CLOSE_TO_TRAY).unwrap_or(false),
+        autostart: get(app, AUTOSTART).unwrap_or(false),
+        update_channel: get(app, UPDATE_CHANNEL),
+        auto_install: get(app, crate::AUTO_INSTALL_PREF).unwrap_or(false),
+        update_min_battery: get(app, crate::UPDATE_BATTERY_PREF)
+            .unwrap_or(crate::DEFAULT_UPDATE_MIN_BATTERY),
+        dnd: get(app, crate::notify::DND).unwrap_or(false),
+        always_on_top: get(app, crate::window::ALWAYS_ON_TOP).unwrap_or(false),
+        decorations: get(app, crate::window::DECORATIONS).unwrap_or(true),
+        zoom: get(app, crate::zoom::ZOOM).unwrap_or(1.0),
+    }
+}
+
+#[tauri::command]
+pub fn get_prefs(app: AppHandle) -> Known {
+    known(&app)
+}
+
+/// Whether `key` belongs to a native command rather than the webview.
+pub fn is_native(key: &str) -> bool {
+    NATIVE_KEYS.contains(&key)
+}
+
+#[tauri::command]
+pub fn get_pref(app: AppHandle, key: String) -> Option<Value> {
+    get(&app, &key)
+}
+
+/// Store `value` under `key`, or drop the key when `value` is null.
+#[tauri::command]
+pub fn set_pref(app: AppHandle, key: String, value: Value) -> Result<(), CommandError> {
+    if is_native(&key) {
+        return Err(CommandError::invalid_input(format!("{key} is set through its own command")));
+    }
+    if value.is_null() {
+        return remove(&app, &key);
+    }
+    set(&app, &key, &value)
+}
+
+pub fn remove(app: &AppHandle, key: &str) -> Result<(), CommandError> {
+    let prefs = app.state::<Prefs>();
+    let mut map = prefs.map.lock().unwrap();
+    if map.remove(key).is_none() {
+        return Ok(());
+    }
+    if prefs.persist {
+        write(app, &map)?;
+    }
+    drop(map);
+    let _ = app.emit("prefs://changed", Changed { key, value: Value::Null });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(json: &str) -> Map<String, Value> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn truncated_file_recovers_from_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(PREFS_FILE);
+        write_atomic(&path, r#"{"theme":"dark"}"#).unwrap();
+        write_atomic(&path, r#"{"theme":"light","zoom":1.25}"#).unwrap();
+        // A write cut short partway through.
+        std::fs::write(&path, r#"{"theme":"li"#).unwrap();
+        assert_eq!(read_with_backup(&path), map(r#"{"theme":"dark"}"#));
+    }
+
+    #[test]
+    fn missing_file_recovers_from_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(PREFS_FILE);
+        std::fs::write(sibling(&path, ".bak"), r#"{"zoom":1.5}"#).unwrap();
+        assert_eq!(read_with_backup(&path), map(r#"{"zoom":1.5}"#));
+    }
+
+    #[test]
+    fn intact_file_wins_over_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(PREFS_FILE);
+        write_atomic(&path, r#"{"theme":"dark"}"#).unwrap();
+        write_atomic(&path, r#"{"theme":"light"}"#).unwrap();
+        assert_eq!(read_with_backup(&path), map(r#"{"theme":"light"}"#));
+    }
+
+    #[test]
+    fn nothing_usable_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(PREFS_FILE);
+        std::fs::write(&path, "").unwrap();
+        std::fs::write(sibling(&path, ".bak"), "{").unwrap();
+        assert!(read_with_backup(&path).is_empty());
+    }
+}
@@ -0,0 +1,701 @@
+// Native presence beacons. The webview can't be trusted to deliver these on
+// close (it may already be torn down), so the token and server URL are synced
+// into Rust-side state and the requests go out through tauri-plugin-http's
+// client instead.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::capabilities::{self, Feature};
+use crate::error::CommandError;
+use crate::net::Traced;
+
+const BEACON_TIMEOUT: Duration = Duration::from_secs(2);
+pub const DEFAULT_HEARTBEAT_SECS: u64 = 30;
+const MIN_HEARTBEAT_SECS: u64 = 5;
+/// Longest leave grace the server honours (`MAX_LEAVE_GRACE_MS` in its
+/// routes/events.ts); with no pref, or 0, the leave takes effect at once.
+const MAX_LEAVE_GRACE_MS: u64 = 15_000;
+pub(crate) const LEAVE_GRACE_PREF: &str = "leave_grace_ms";
+const WAKE_POLL: Duration = Duration::from_secs(5);
+/// Wall-clock jump (beyond the poll interval) that means the machine slept.
+const WAKE_GAP: Duration = Duration::from_secs(30);
+
+pub(crate) const RATE_LIMIT_PREF: &str = "beacon_rate_limit";
+pub(crate) const INVISIBLE_PREF: &str = "invisible";
+
+/// Ghost mode: join and heartbeat beacons are held back so the server never
+/// hears "online", while the event stream stays up for incoming messages.
+static INVISIBLE: AtomicBool = AtomicBool::new(false);
+
+/// Token bucket shared by the presence beacons: `burst` requests back to
+/// back, then `per_minute` sustained. Excess calls fail with `rate_limited`
+/// instead of reaching the network, so a reconnect loop in the UI can't
+/// hammer the server. Typing pings have a bucket of their own, so a burst of
+/// typing can't starve a heartbeat.
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct RateLimit {
+    pub burst: u32,
+    pub per_minute: u32,
+}
+
+const DEFAULT_RATE_LIMIT: RateLimit = RateLimit { burst: 10, per_minute: 30 };
+/// A `typing` per channel every few seconds plus its `typing_stop`, across a
+/// couple of channels at once.
+const TYPING_RATE_LIMIT: RateLimit = RateLimit { burst: 10, per_minute: 60 };
+
+struct Bucket {
+    limit: RateLimit,
+    tokens: f64,
+    /// `None` until the first request, since `Instant` can't be built in a const.
+    refilled: Option<Instant>,
+}
+
+impl Bucket {
+    const fn new(limit: RateLimit) -> Self {
+        Bucket { limit, tokens: limit.burst as f64, refilled: None }
+    }
+
+    /// Take a token if one is available.
+    fn take(&mut self) -> bool {
+        let now = Instant::now();
+        if let Some(last) = self.refilled {
+            let refill = now.duration_since(last).as_secs_f64() * self.limit.per_minute as f64 / 60.0;
+            self.tokens = (self.tokens + refill).min(self.limit.burst as f64);
+        }
+        self.refilled = Some(now);
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+}
+
+static BUCKET: Mutex<Bucket> = Mutex::new(Bucket::new(DEFAULT_RATE_LIMIT));
+static TYPING_BUCKET: Mutex<Bucket> = Mutex::new(Bucket::new(TYPING_RATE_LIMIT));
+
+/// Take a token from `event`'s bucket if one is available.
+fn take_token(event: &str) -> bool {
+    let bucket = if matches!(event, "typing" | "typing_stop") { &TYPING_BUCKET } else { &BUCKET };
+    bucket.lock().unwrap().take()
+}
+
+fn apply_rate_limit(limit: RateLimit) {
+    let mut bucket = BUCKET.lock().unwrap();
+    bucket.limit = limit;
+    bucket.tokens = bucket.tokens.min(limit.burst as f64);
+}
+
+/// Re-apply a persisted rate limit. Called from setup.
+pub fn restore_rate_limit(app: &AppHandle) {
+    if let Some(limit) = crate::prefs::get::<RateLimit>(app, RATE_LIMIT_PREF) {
+        apply_rate_limit(limit);
+    }
+}
+
+/// Change the presence beacon rate limit (typing keeps its own); `None` goes
+/// back to the default. Returns the limit now in effect.
+#[tauri::command]
+pub fn set_beacon_rate_limit(app: AppHandle, limit: Option<RateLimit>) -> Result<RateLimit, CommandError> {
+    if let Some(l) = limit {
+        if l.burst == 0 || l.per_minute == 0 {
+            return Err(CommandError::invalid_input("rate limit must allow at least one request"));
+        }
+    }
+    crate::prefs::set(&app, RATE_LIMIT_PREF, &limit)?;
+    let limit = limit.unwrap_or(DEFAULT_RATE_LIMIT);
+    apply_rate_limit(limit);
+    Ok(limit)
+}
+
+#[derive(Clone)]
+pub struct AuthInfo {
+    pub token: String,
+    /// Normalized, see `net::normalize_server_url`.
+    pub server_url: String,
+}
+
+pub struct AuthState(pub Mutex<Option<AuthInfo>>);
+
+/// How many beacon attempts `export_presence_log` can look back over.
+const BEACON_LOG_CAP: usize = 200;
+
+#[derive(Serialize, Clone, Copy)]
+pub struct Timestamp {
+    unix_ms: u64,
+}
+
+impl Timestamp {
+    fn now() -> Self {
+        let unix_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64);
+        Timestamp { unix_ms }
+    }
+}
+
+/// One beacon attempt, successful or not.
+#[derive(Serialize, Clone)]
+pub struct BeaconRecord {
+    at: Timestamp,
+    event: String,
+    /// HTTP status, `None` if the request never got a response.
+    status: Option<u16>,
+    error: Option<String>,
+}
+
+static BEACON_LOG: Mutex<VecDeque<BeaconRecord>> = Mutex::new(VecDeque::new());
+
+fn record_beacon(event: &str, status: Option<u16>, error: Option<String>) {
+    let mut log = BEACON_LOG.lock().unwrap();
+    if log.len() == BEACON_LOG_CAP {
+        log.pop_front();
+    }
+    log.push_back(BeaconRecord {
+        at: Timestamp::now(),
+        event: event.to_string(),
+        status,
+        error,
+    });
+}
+
+/// Recent beacon attempts, oldest first. `flush` empties the buffer.
+#[tauri::command]
+pub fn export_presence_log(flush: Option<bool>) -> Vec<BeaconRecord> {
+    let mut log = BEACON_LOG.lock().unwrap();
+    if flush == Some(true) {
+        log.drain(..).collect()
+    } else {
+        log.iter().cloned().collect()
+    }
+}
+
+pub struct HeartbeatState {
+    pub interval_secs: AtomicU64,
+    pub task: Mutex<Option<JoinHandle<()>>>,
+    /// Set while a reconnect is in flight so overlapping calls collapse.
+    pub reconnecting: AtomicBool,
+    /// Set between the OS saying it's going to sleep and it waking up again,
+    /// so nothing restarts the loop in between.
+    pub suspended: AtomicBool,
+    /// When the loop last sent a ping.
+    pub last_beat: Mutex<Option<Timestamp>>,
+}
+
+#[tauri::command]
+pub fn set_auth_info(
+    app: AppHandle,
+    state: tauri::State<'_, AuthState>,
+    token: String,
+    server_url: Option<String>,
+) -> Result<(), CommandError> {
+    let server_url = match server_url {
+        Some(raw) => crate::net::store_server_url(&app, &raw)?,
+        None => crate::net::server_url(&app).ok_or_else(|| CommandError::invalid_input("no server url set"))?,
+    };
+    crate::logging::remember_token(Some(&token));
+    crate::auth::clear_expired();
+    let info = AuthInfo { token, server_url };
+    let was_empty = state.0.lock().unwrap().replace(info.clone()).is_none();
+    // The webview syncs the saved token as soon as it loads, so the first
+    // credentials we see are effectively app startup (or a fresh login).
+    if was_empty {
+        // logout stops the loop; a fresh sign-in brings it back.
+        if app.state::<HeartbeatState>().task.lock().unwrap().is_none() {
+            start_heartbeat(&app);
+        }
+        tauri::async_runtime::spawn(async move {
+            // Don't announce presence for a token the server has revoked.
+            // Anything short of a definite rejection goes ahead as before.
+            if let Err(e) = crate::auth::whoami(&info.server_url, &info.token).await {
+                if matches!(e, CommandError::Auth { .. }) {
+                    log::warn!("saved token rejected, skipping join: {e}");
+                    let _ = app.emit("auth://invalid", e);
+                    return;
+                }
+                log::info!("couldn't validate token before join: {e}");
+            }
+            if let Err(e) = capabilities::refresh(&app, &info.server_url, false).await {
+                log::info!("couldn't fetch server capabilities: {e}");
+            }
+            if let Err(e) = post_event(&info, "join").await {
+                log::warn!("join beacon failed: {e}");
+            }
+            crate::outbox::flush(&app);
+            // DND restored from prefs at startup couldn't be reported then.
+            if crate::notify::dnd_enabled(&app) {
+                let _ = crate::notify::report_dnd(&info, true).await;
+            }
+            if INVISIBLE.load(Ordering::Relaxed) {
+                let _ = report_invisible(&info, true).await;
+            }
+            // Warm the cache so joining a call doesn't wait on it.
+            if !capabilities::supports(&info.server_url, Feature::Turn) {
+                return;
+            }
+            if let Err(e) = crate::rtc::get_ice_servers(app.clone(), None).await {
+                log::debug!("ice prefetch failed: {e}");
+            }
+        });
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn clear_auth_info(app: AppHandle, state: tauri::State<'_, AuthState>) {
+    crate::logging::remember_token(None);
+    *state.0.lock().unwrap() = None;
+    crate::stream::stop(&app);
+}
+
+/// Sign out natively: leave beacon, heartbeat, event stream and outbox
+/// stopped, token dropped from the keychain and memory, tray back to
+/// offline, then `auth://logged_out`.
+/// A failed beacon doesn't block the rest; a keychain error is returned
+/// after everything else has been cleared.
+#[tauri::command]
+pub async fn logout(app: AppHandle) -> Result<(), CommandError> {
+    // leave() reads the keychain token, so it has to go out first.
+    if let Err(e) = leave(&app).await {
+        log::warn!("leave beacon on logout failed: {e}");
+    }
+    stop_heartbeat(&app);
+    crate::stream::stop(&app);
+    crate::typing::clear(&app);
+    crate::outbox::clear();
+    let deleted = crate::secrets::delete_token(crate::net::server_url(&app).as_deref());
+    crate::logging::remember_token(None);
+    *app.state::<AuthState>().0.lock().unwrap() = None;
+    crate::notify::clear_in_call(&app);
+    #[cfg(desktop)]
+    if let Err(e) = crate::tray::set_status(&app, crate::tray::TrayStatus::Offline) {
+        log::warn!("failed to reset tray on logout: {e}");
+    }
+    let _ = app.emit("auth://logged_out", ());
+    deleted.map_err(CommandError::io)
+}
+
+/// POST `{ token }` to `/api/events/<event>` on the given server.
+pub(crate) async fn post_event(info: &AuthInfo, event: &str) -> Result<(), CommandError> {
+    post_event_with(info, event, serde_json::Map::new()).await
+}
+
+/// Like `post_event`, with extra fields merged into the body. Subject to the
+/// beacon rate limit. One that never reaches the server goes to the outbox
+/// to be retried once it's back.
+pub(crate) async fn post_event_with(
+    info: &AuthInfo,
+    event: &str,
+    fields: serde_json::Map<String, serde_json::Value>,
+) -> Result<(), CommandError> {
+    // Leave is exempt: it's the last thing sent, and dropping it would leave
+    // the user showing online.
+    if event != "leave" && !take_token(event) {
+        record_beacon(event, None, Some("rate_limited".into()));
+        return Err(CommandError::RateLimited { message: "rate_limited".into() });
+    }
+    // Not queued either: it would be stale by the time the pause ends.
+    if crate::net::paused() {
+        record_beacon(event, None, Some("paused".into()));
+        return Err(CommandError::network("network_paused"));
+    }
+    if event != "leave" && capabilities::client_outdated(&info.server_url) {
+        record_beacon(event, None, Some("client_outdated".into()));
+        return Err(CommandError::Other { message: "client_outdated".into() });
+    }
+    if matches!(event, "join" | "ping") && INVISIBLE.load(Ordering::Relaxed) {
+        record_beacon(event, None, Some("invisible".into()));
+        return Ok(());
+    }
+    let feature = match event {
+        "join" => Some(Feature::JoinBeacon),
+        "ping" => Some(Feature::Heartbeat),
+        _ => None,
+    };
+    if feature.is_some_and(|f| !capabilities::supports(&info.server_url, f)) {
+        record_beacon(event, None, Some("unsupported".into()));
+        return Ok(());
+    }
+    match deliver(info, event, fields.clone()).await {
+        Ok(()) => {
+            // Anything queued for the same thing is out of date now.
+            crate::outbox::superseded(&info.server_url, event, &fields);
+            Ok(())
+        }
+        Err(Undelivered::Unreachable(e)) => {
+            crate::outbox::enqueue(&info.server_url, event, fields);
+            Err(CommandError::network(e))
+        }
+        Err(Undelivered::Rejected { status: 401 | 403, message }) => Err(CommandError::Auth { message }),
+        Err(Undelivered::Rejected { status, message }) => Err(CommandError::Server { status, message }),
+    }
+}
+
+pub(crate) enum Undelivered {
+    /// No response: offline, DNS, timeout.
+    Unreachable(String),
+    /// The server answered with an error.
+    Rejected { status: u16, message: String },
+}
+
+/// The POST itself, recorded in the beacon log but without the rate limit
+/// or outbox. A 401 gets one retry if the token can be refreshed.
+pub(crate) async fn deliver(
+    info: &AuthInfo,
+    event: &str,
+    fields: serde_json::Map<String, serde_json::Value>,
+) -> Result<(), Undelivered> {
+    let result = deliver_once(info, event, fields.clone()).await;
+    if !matches!(result, Err(Undelivered::Rejected { status: 401, .. })) {
+        return result;
+    }
+    match crate::auth::refresh_after_401(info).await {
+        Some(renewed) => deliver_once(&renewed, event, fields).await,
+        None => result,
+    }
+}
+
+async fn deliver_once(
+    info: &AuthInfo,
+    event: &str,
+    mut fields: serde_json::Map<String, serde_json::Value>,
+) -> Result<(), Undelivered> {
+    let url = format!("{}/api/events/{event}", info.server_url);
+    fields.insert("token".into(), info.token.clone().into());
+    let body = serde_json::Value::Object(fields).to_string();
+    let response = match crate::net::client() {
+        Ok(client) => client
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .body(body)
+            .timeout(BEACON_TIMEOUT)
+            .send_traced()
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e),
+    };
+    let status = response.as_ref().ok().map(|r| r.status());
+    let result = match (&response, status) {
+        (Err(e), _) => Err(Undelivered::Unreachable(e.clone())),
+        (Ok(_), Some(s)) if !s.is_success() => Err(Undelivered::Rejected {
+            status: s.as_u16(),
+            message: format!("{event} beacon rejected: {s}"),
+        }),
+        _ => Ok(()),
+    };
+    let error = match &result {
+        Err(Undelivered::Unreachable(e) | Undelivered::Rejected { message: e, .. }) => Some(e.clone()),
+        Ok(()) => None,
+    };
+    record_beacon(event, status.map(|s| s.as_u16()), error);
+    if let (Ok(response), Err(Undelivered::Rejected { .. })) = (response, &result) {
+        crate::net::record_error("POST", response).await;
+    }
+    result
+}
+
+async fn report_invisible(info: &AuthInfo, enabled: bool) -> Result<(), CommandError> {
+    let mut fields = serde_json::Map::new();
+    fields.insert("invisible".into(), enabled.into());
+    post_event_with(info, "presence", fields).await
+}
+
+/// Tell the server we're deafened (or not), so others can see it. A no-op
+/// while signed out.
+pub async fn report_deafened(app: &AppHandle, enabled: bool) -> Result<(), CommandError> {
+    let auth = app.state::<AuthState>().0.lock().unwrap().clone();
+    let Some(info) = auth else { return Ok(()) };
+    let mut fields = serde_json::Map::new();
+    fields.insert("deafened".into(), enabled.into());
+    post_event_with(&info, "presence", fields).await
+}
+
+pub fn invisible() -> bool {
+    INVISIBLE.load(Ordering::Relaxed)
+}
+
+fn apply_invisible(app: &AppHandle, enabled: bool) {
+    INVISIBLE.store(enabled, Ordering::Relaxed);
+    let _ = app.emit("presence://invisible", enabled);
+    crate::quickactions::refresh(app);
+    #[cfg(desktop)]
+    if let Err(e) = crate::tray::set_invisible(app, enabled) {
+        log::warn!("failed to update tray for ghost mode: {e}");
+    }
+}
+
+/// Re-apply persisted ghost mode. Called from setup after the tray exists,
+/// and before the first join so that never goes out.
+pub fn restore_invisible(app: &AppHandle) {
+    if crate::prefs::get::<bool>(app, INVISIBLE_PREF) == Some(true) {
+        apply_invisible(app, true);
+    }
+}
+
+/// Appear offline (or stop). Leaving ghost mode re-sends the join so other
+/// users see us come online; the leave beacon on close is unaffected.
+#[tauri::command]
+pub async fn set_invisible(app: AppHandle, enabled: bool) -> Result<(), CommandError> {
+    crate::prefs::set(&app, INVISIBLE_PREF, &enabled)?;
+    apply_invisible(&app, enabled);
+    let auth = app.state::<AuthState>().0.lock().unwrap().clone();
+    let Some(info) = auth else { return Ok(()) };
+    report_invisible(&info, enabled).await?;
+    if !enabled {
+        post_event(&info, "join").await?;
+    }
+    Ok(())
+}
+
+/// POST the leave event, with `grace_ms` when the user has overridden it.
+async fn post_leave(app: &AppHandle, info: &AuthInfo) -> Result<(), CommandError> {
+    let mut fields = serde_json::Map::new();
+    if let Some(ms) = crate::prefs::get::<u64>(app, LEAVE_GRACE_PREF) {
+        fields.insert("grace_ms".into(), ms.min(MAX_LEAVE_GRACE_MS).into());
+    }
+    post_event_with(info, "leave", fields).await
+}
+
+#[tauri::command]
+pub async fn send_leave(app: AppHandle, token: String, server_url: Option<String>) -> Result<(), CommandError> {
+    let server_url = crate::net::resolve_server_url(&app, server_url.as_deref())?;
+    post_leave(&app, &AuthInfo { token, server_url }).await
+}
+
+/// Set (or clear, for the server default) the leave grace period. Returns
+/// the value actually stored after clamping.
+#[tauri::command]
+pub fn set_leave_grace(app: AppHandle, ms: Option<u64>) -> Result<Option<u64>, CommandError> {
+    let ms = ms.map(|ms| ms.min(MAX_LEAVE_GRACE_MS));
+    crate::prefs::set(&app, LEAVE_GRACE_PREF, &ms)?;
+    Ok(ms)
+}
+
+#[tauri::command]
+pub async fn send_join_beacon(state: tauri::State<'_, AuthState>) -> Result<(), CommandError> {
+    let auth = state.0.lock().unwrap().clone();
+    let info = auth.ok_or_else(|| CommandError::auth("Not signed in"))?;
+    post_event(&info, "join").await
+}
+
+/// Fire the leave beacon for the synced credentials, if any.
+pub async fn leave(app: &AppHandle) -> Result<(), CommandError> {
+    let auth = app.state::<AuthState>().0.lock().unwrap().clone();
+    let Some(mut info) = auth else { return Ok(()) };
+    // Prefer the keychain copy: it survives a webview that never re-synced.
+    if let Ok(Some(token)) = crate::secrets::read_token(Some(&info.server_url)) {
+        info.token = token;
+    }
+    post_leave(app, &info).await
+}
+
+/// (Re)start the heartbeat loop. Any previous task is aborted first so there
+/// is only ever one loop running. Ticks without credentials are skipped, as
+/// is the first one after the machine wakes: that's overdue, and the wake
+/// path sends its own.
+pub fn start_heartbeat(app: &AppHandle) {
+    let state = app.state::<HeartbeatState>();
+    if state.suspended.load(Ordering::Relaxed) || crate::net::paused() || capabilities::outdated(app) {
+        return;
+    }
+    let task = crate::shutdown::spawn(app, |app_for_task| async move {
+        let mut last = SystemTime::now();
+        loop {
+            let state = app_for_task.state::<HeartbeatState>();
+            let mut secs = state.interval_secs.load(Ordering::Relaxed);
+            if crate::throttle::engaged() {
+                secs *= crate::throttle::HEARTBEAT_FACTOR;
+            }
+            tokio::time::sleep(Duration::from_secs(secs)).await;
+            let now = SystemTime::now();
+            let late = now.duration_since(last).unwrap_or_default() > Duration::from_secs(secs) + WAKE_GAP;
+            last = now;
+            if late || state.suspended.load(Ordering::Relaxed) {
+                continue;
+            }
+            let auth = app_for_task.state::<AuthState>().0.lock().unwrap().clone();
+            if let Some(info) = auth {
+                *state.last_beat.lock().unwrap() = Some(Timestamp::now());
+                // The server's heartbeat route is /api/events/ping.
+                let _ = post_event(&info, "ping").await;
+            }
+        }
+    });
+    let old = state.task.lock().unwrap().replace(task);
+    if let Some(old) = old {
+        old.abort();
+    }
+}
+
+pub fn stop_heartbeat(app: &AppHandle) {
+    if let Some(task) = app.state::<HeartbeatState>().task.lock().unwrap().take() {
+        task.abort();
+    }
+}
+
+/// Restart the loop if it's running, so a changed interval applies now
+/// rather than after the current sleep.
+pub fn restart_heartbeat(app: &AppHandle) {
+    if app.state::<HeartbeatState>().task.lock().unwrap().is_some() {
+        start_heartbeat(app);
+    }
+}
+
+#[tauri::command]
+pub fn set_heartbeat_interval(app: AppHandle, seconds: u64) -> u64 {
+    let secs = seconds.max(MIN_HEARTBEAT_SECS);
+    app.state::<HeartbeatState>()
+        .interval_secs
+        .store(secs, Ordering::Relaxed);
+    restart_heartbeat(&app);
+    secs
+}
+
+/// Re-announce presence after the connection went stale: join beacon, fresh
+/// heartbeat loop, and `presence://reconnecting` / `presence://online` for the
+/// UI. A call while one is already running is a no-op.
+pub async fn reconnect_now(app: &AppHandle) -> Result<(), CommandError> {
+    let state = app.state::<HeartbeatState>();
+    if state.reconnecting.swap(true, Ordering::AcqRel) {
+        return Ok(());
+    }
+    let _ = app.emit("presence://reconnecting", ());
+    let auth = app.state::<AuthState>().0.lock().unwrap().clone();
+    let result = match auth {
+        Some(info) => post_event(&info, "join").await,
+        None => Err(CommandError::auth("Not signed in")),
+    };
+    // start_heartbeat replaces any running loop, so this never duplicates.
+    start_heartbeat(app);
+    state.reconnecting.store(false, Ordering::Release);
+    if result.is_ok() {
+        let _ = app.emit("presence://online", ());
+    }
+    result
+}
+
+#[tauri::command]
+pub async fn reconnect(app: AppHandle) -> Result<(), CommandError> {
+    reconnect_now(&app).await
+}
+
+/// Between `suspend` and `resume`.
+pub fn suspended(app: &AppHandle) -> bool {
+    app.state::<HeartbeatState>().suspended.load(Ordering::Acquire)
+}
+
+/// The OS is about to sleep (or, on mobile, the app went to the background):
+/// stop the heartbeat until `resume`.
+pub fn suspend(app: &AppHandle) {
+    if app.state::<HeartbeatState>().suspended.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    log::info!("suspending, heartbeat paused");
+    stop_heartbeat(app);
+}
+
+/// Back from sleep: one fresh heartbeat and a reconnect, rather than the
+/// loop catching up on the ticks it missed.
+pub fn resume(app: &AppHandle) {
+    app.state::<HeartbeatState>().suspended.store(false, Ordering::Release);
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let auth = app.state::<AuthState>().0.lock().unwrap().clone();
+        if let Some(info) = auth {
+            *app.state::<HeartbeatState>().last_beat.lock().unwrap() = Some(Timestamp::now());
+            if let Err(e) = post_event(&info, "ping").await {
+                log::info!("heartbeat after wake failed: {e}");
+            }
+        }
+        if let Err(e) = reconnect_now(&app).await {
+            log::warn!("reconnect after wake failed: {e}");
+        }
+    });
+}
+
+/// The event stream was open when the network was paused.
+static STREAM_BEFORE_PAUSE: AtomicBool = AtomicBool::new(false);
+
+/// Go dark without quitting, or come back. Pausing sends the leave beacon
+/// first, then stops the heartbeat and the event stream and blocks every
+/// native request (see `net::client`). Resuming sends a fresh join, restarts
+/// the heartbeat and reopens the stream if it was open. Emits `net://paused`;
+/// not saved, so a restart always comes back online.
+#[tauri::command]
+pub async fn set_network_paused(app: AppHandle, paused: bool) -> Result<(), CommandError> {
+    if crate::net::paused() == paused {
+        return Ok(());
+    }
+    if paused {
+        if let Err(e) = leave(&app).await {
+            log::warn!("leave beacon before pausing failed: {e}");
+        }
+        crate::net::set_paused(true);
+        stop_heartbeat(&app);
+        STREAM_BEFORE_PAUSE.store(crate::stream::status(&app) != "closed", Ordering::Relaxed);
+        crate::stream::stop(&app);
+        log::info!("network paused");
+        let _ = app.emit("net://paused", true);
+        return Ok(());
+    }
+    crate::net::set_paused(false);
+    log::info!("network resumed");
+    let _ = app.emit("net://paused", false);
+    if STREAM_BEFORE_PAUSE.swap(false, Ordering::Relaxed) {
+        crate::stream::start(&app);
+    }
+    let result = reconnect_now(&app).await;
+    crate::outbox::flush(&app);
+    result
+}
+
+#[tauri::command]
+pub fn get_network_paused() -> bool {
+    crate::net::paused()
+}
+
+/// For platforms without a sleep notification (see `power::watch_sleep`):
+/// watch for the wall clock jumping far past our poll interval and treat
+/// that as a resume.
+pub fn start_wake_watcher(app: &AppHandle) {
+    crate::shutdown::spawn(app, |app| async move {
+        let mut last = SystemTime::now();
+        loop {
+            tokio::time::sleep(WAKE_POLL).await;
+            let now = SystemTime::now();
+            let gap = now.duration_since(last).unwrap_or_default();
+            last = now;
+            if gap > WAKE_POLL + WAKE_GAP {
+                log::info!("resumed after {}s asleep, reconnecting", gap.as_secs());
+                resume(&app);
+            }
+        }
+    });
+}
+
+#[derive(Serialize)]
+pub struct HeartbeatStatus {
+    running: bool,
+    /// Paused for sleep.
+    suspended: bool,
+    reconnecting: bool,
+    interval_secs: u64,
+    last_beat: Option<Timestamp>,
+}
+
+#[tauri::command]
+pub fn get_heartbeat_status(state: tauri::State<'_, HeartbeatState>) -> HeartbeatStatus {
+    HeartbeatStatus {
+        running: state.task.lock().unwrap().is_some(),
+        suspended: state.suspended.load(Ordering::Relaxed),
+        reconnecting: state.reconnecting.load(Ordering::Relaxed),
+        interval_secs: state.interval_secs.load(Ordering::Relaxed),
+        last_beat: *state.last_beat.lock().unwrap(),
+    }
+}
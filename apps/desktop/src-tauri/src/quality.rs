@@ -0,0 +1,132 @@
+// Outgoing video quality. The user picks a fixed preset or `auto`, which
+// follows the latency sampler: down a step as soon as the connection looks
+// worse, back up only after it has stayed good for a while, so a single
+// slow ping doesn't make the picture pump. Whatever is in effect goes out as
+// `video://quality` for the RTC code to apply to its senders.
+
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+
+use crate::error::CommandError;
+use crate::prefs;
+
+pub(crate) const QUALITY_PREF: &str = "video_quality";
+const AUTO: &str = "auto";
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct QualityPreset {
+    id: &'static str,
+    width: u32,
+    height: u32,
+    fps: u32,
+    /// Encoder ceiling for the RTC sender.
+    max_bitrate_kbps: u32,
+}
+
+/// Lowest first; auto moves along this list.
+const PRESETS: &[QualityPreset] = &[
+    QualityPreset { id: "720p30", width: 1280, height: 720, fps: 30, max_bitrate_kbps: 1_500 },
+    QualityPreset { id: "1080p30", width: 1920, height: 1080, fps: 30, max_bitrate_kbps: 3_000 },
+    QualityPreset { id: "1080p60", width: 1920, height: 1080, fps: 60, max_bitrate_kbps: 6_000 },
+];
+
+/// Average round trip each preset is allowed, lowest preset excluded.
+const MAX_LATENCY_MS: [u64; 2] = [200, 100];
+/// Good samples in a row before auto steps up (30s at the sampler's rate).
+const STEP_UP_AFTER: u32 = 6;
+
+/// Where auto currently sits in `PRESETS`, and how long it's been good.
+struct Auto {
+    level: usize,
+    good: u32,
+}
+
+/// Auto starts in the middle and finds its level from there.
+static AUTO_STATE: Mutex<Auto> = Mutex::new(Auto { level: 1, good: 0 });
+
+#[derive(Serialize, Clone)]
+pub struct QualityState {
+    /// The saved choice: a preset id or `"auto"`.
+    preset: String,
+    /// The preset being sent right now.
+    effective: QualityPreset,
+}
+
+fn saved(app: &AppHandle) -> String {
+    prefs::get::<String>(app, QUALITY_PREF)
+        .filter(|id| id == AUTO || PRESETS.iter().any(|p| p.id == id))
+        .unwrap_or_else(|| AUTO.into())
+}
+
+fn current(app: &AppHandle) -> QualityState {
+    let preset = saved(app);
+    let effective = match PRESETS.iter().find(|p| p.id == preset) {
+        Some(p) => *p,
+        None => PRESETS[AUTO_STATE.lock().unwrap().level],
+    };
+    QualityState { preset, effective }
+}
+
+/// Best level the connection supports.
+fn level_for(average_ms: Option<u64>, timed_out: bool) -> usize {
+    let Some(average) = average_ms.filter(|_| !timed_out) else { return 0 };
+    match MAX_LATENCY_MS.iter().rposition(|&max| average <= max) {
+        Some(i) => i + 1,
+        None => 0,
+    }
+}
+
+/// Fed by the latency sampler on every sample. Only moves anything while
+/// the saved choice is `auto`.
+pub fn on_latency(app: &AppHandle, average_ms: Option<u64>, timed_out: bool) {
+    if saved(app) != AUTO {
+        return;
+    }
+    let target = level_for(average_ms, timed_out);
+    let changed = {
+        let mut auto = AUTO_STATE.lock().unwrap();
+        let before = auto.level;
+        if target < auto.level {
+            auto.level = target;
+            auto.good = 0;
+        } else if target > auto.level {
+            auto.good += 1;
+            if auto.good >= STEP_UP_AFTER {
+                auto.level += 1;
+                auto.good = 0;
+            }
+        } else {
+            auto.good = 0;
+        }
+        auto.level != before
+    };
+    if changed {
+        let state = current(app);
+        log::info!("auto video quality now {}", state.effective.id);
+        let _ = app.emit("video://quality", state);
+    }
+}
+
+/// The fixed presets, lowest first. `"auto"` is accepted by
+/// `set_quality_preset` too.
+#[tauri::command]
+pub fn list_quality_presets() -> &'static [QualityPreset] {
+    PRESETS
+}
+
+#[tauri::command]
+pub fn get_quality_preset(app: AppHandle) -> QualityState {
+    current(&app)
+}
+
+#[tauri::command]
+pub fn set_quality_preset(app: AppHandle, id: String) -> Result<QualityState, CommandError> {
+    if id != AUTO && !PRESETS.iter().any(|p| p.id == id) {
+        return Err(CommandError::invalid_input(format!("unknown quality preset: {id}")));
+    }
+    prefs::set(&app, QUALITY_PREF, &id)?;
+    let state = current(&app);
+    let _ = app.emit("video://quality", state.clone());
+    Ok(state)
+}
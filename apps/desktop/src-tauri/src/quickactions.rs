@@ -0,0 +1,154 @@
+// Quick actions from the taskbar jump list (Windows). Each task relaunches
+// the exe with `--quick-action=<id>`; single-instance hands that to the
+// running app, which acts on it without focusing the window (except for
+// opening DMs). Labels follow the current mute and ghost-mode state, so the
+// list is rebuilt when either changes. Tauri doesn't expose the macOS dock
+// menu, so there's nothing there yet.
+
+use tauri::{AppHandle, Emitter};
+
+const ARG_PREFIX: &str = "--quick-action=";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Away,
+    OpenDms,
+    Mute,
+    Invisible,
+}
+
+const ACTIONS: [Action; 4] = [Action::Away, Action::OpenDms, Action::Mute, Action::Invisible];
+
+impl Action {
+    fn id(self) -> &'static str {
+        match self {
+            Action::Away => "away",
+            Action::OpenDms => "open_dms",
+            Action::Mute => "mute",
+            Action::Invisible => "invisible",
+        }
+    }
+
+    /// Label for the current state, e.g. "Unmute mic" while muted.
+    #[cfg_attr(not(windows), allow(dead_code))]
+    fn title(self, app: &AppHandle) -> String {
+        let key = match self {
+            Action::Away => "quick.away",
+            Action::OpenDms => "quick.open_dms",
+            Action::Mute if crate::shortcuts::muted(app) => "quick.unmute",
+            Action::Mute => "quick.mute",
+            Action::Invisible if crate::presence::invisible() => "quick.appear_online",
+            Action::Invisible => "quick.go_invisible",
+        };
+        crate::i18n::t(app, key)
+    }
+}
+
+/// The quick action a launch was asked to run, if any.
+pub fn from_args(argv: &[String]) -> Option<Action> {
+    let id = argv.iter().find_map(|a| a.strip_prefix(ARG_PREFIX))?;
+    ACTIONS.into_iter().find(|a| a.id() == id)
+}
+
+/// Run `action` the way the in-app control would. Status and DMs belong to
+/// the webview, so those go out as `quick-action://invoked`.
+pub fn run(app: &AppHandle, action: Action) {
+    match action {
+        Action::Away => {
+            let _ = app.emit("quick-action://invoked", action.id());
+        }
+        Action::OpenDms => {
+            crate::tray::show_main_window(app);
+            let _ = app.emit("quick-action://invoked", action.id());
+        }
+        Action::Mute => {
+            crate::shortcuts::toggle_mute(app);
+        }
+        Action::Invisible => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                let enabled = !crate::presence::invisible();
+                if let Err(e) = crate::presence::set_invisible(app, enabled).await {
+                    log::warn!("ghost mode from quick action failed: {e}");
+                }
+            });
+        }
+    }
+}
+
+/// A launch that started the app (rather than reaching it through
+/// single-instance). Only the actions that don't need the webview apply.
+pub fn run_launch_args(app: &AppHandle) {
+    let argv: Vec<String> = std::env::args().collect();
+    if let Some(action @ (Action::Mute | Action::Invisible)) = from_args(&argv) {
+        run(app, action);
+    }
+}
+
+/// Rebuild the jump list from the current state. No-op off Windows.
+pub fn refresh(app: &AppHandle) {
+    #[cfg(windows)]
+    {
+        let tasks: Vec<(String, String)> = ACTIONS
+            .into_iter()
+            .map(|a| (format!("{ARG_PREFIX}{}", a.id()), a.title(app)))
+            .collect();
+        // A fresh thread so COM can be single-threaded here, whatever the
+        // blocking pool's threads were initialised as.
+        std::thread::spawn(move || {
+            if let Err(e) = jumplist::set_tasks(&tasks) {
+                log::warn!("failed to update jump list: {e}");
+            }
+        });
+    }
+    #[cfg(not(windows))]
+    let _ = app;
+}
+
+#[cfg(windows)]
+mod jumplist {
+    use windows::core::{Interface, HSTRING};
+    use windows::Win32::Foundation::E_FAIL;
+    use windows::Win32::Storage::EnhancedStorage::PKEY_Title;
+    use windows::Win32::System::Com::StructuredStorage::{PropVariantClear, PROPVARIANT};
+    use windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED};
+    use windows::Win32::System::Variant::VT_LPWSTR;
+    use windows::Win32::UI::Shell::Common::{IObjectArray, IObjectCollection};
+    use windows::Win32::UI::Shell::PropertiesSystem::IPropertyStore;
+    use windows::Win32::UI::Shell::{
+        DestinationList, EnumerableObjectCollection, ICustomDestinationList, IShellLinkW, SHStrDupW, ShellLink,
+    };
+
+    /// Replace the jump list's Tasks with one link per `(arguments, title)`.
+    pub fn set_tasks(tasks: &[(String, String)]) -> windows::core::Result<()> {
+        let exe = std::env::current_exe().map_err(|_| windows::core::Error::from(E_FAIL))?;
+        let exe = HSTRING::from(exe.as_path());
+        // SAFETY: COM calls on this thread after initialising it; the
+        // PROPVARIANT owns its SHStrDupW string until PropVariantClear.
+        unsafe {
+            let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+            let list: ICustomDestinationList = CoCreateInstance(&DestinationList, None, CLSCTX_INPROC_SERVER)?;
+            let mut slots = 0u32;
+            let _removed: IObjectArray = list.BeginList(&mut slots)?;
+            let collection: IObjectCollection =
+                CoCreateInstance(&EnumerableObjectCollection, None, CLSCTX_INPROC_SERVER)?;
+            for (args, title) in tasks {
+                let link: IShellLinkW = CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER)?;
+                link.SetPath(&exe)?;
+                link.SetArguments(&HSTRING::from(args.as_str()))?;
+                link.SetIconLocation(&exe, 0)?;
+                // Jump list tasks show System.Title, not the description.
+                let store: IPropertyStore = link.cast()?;
+                let mut value = PROPVARIANT::default();
+                (*value.Anonymous.Anonymous).vt = VT_LPWSTR;
+                (*value.Anonymous.Anonymous).Anonymous.pwszVal = SHStrDupW(&HSTRING::from(title.as_str()))?;
+                let stored = store.SetValue(&PKEY_Title, &value).and_then(|()| store.Commit());
+                let _ = PropVariantClear(&mut value);
+                stored?;
+                collection.AddObject(&link)?;
+            }
+            list.AddUserTasks(&collection.cast::<IObjectArray>()?)?;
+            list.CommitList()
+        }
+    }
+}
@@ -0,0 +1,303 @@
+// STUN/TURN config for the webview's RTCPeerConnection, fetched from the
+// server with the synced token. TURN credentials are short-lived, so the list
+// is only cached briefly and can be refreshed on demand.
+//
+// The transport mode is for networks that block UDP: `tcp_relay` forces
+// every call through TURN over TCP/TLS, `udp_only` keeps TCP candidates out.
+// The list handed out is filtered to match, and the webview is told which
+// `iceTransportPolicy` to use through `rtc://transport`.
+//
+// `prewarm_rtc` gets everything a call needs ready ahead of it (ICE config,
+// OS media access, the audio devices to use) so joining doesn't wait on
+// each in turn.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::CommandError;
+use crate::media::{MediaKind, MediaPermissions};
+use crate::net::Traced;
+use crate::presence::AuthState;
+
+pub(crate) const TRANSPORT_PREF: &str = "transport_mode";
+const ICE_TTL: Duration = Duration::from_secs(5 * 60);
+const ICE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Prewarming refetches a cached list older than this, so the credentials
+/// outlast the call setup that follows.
+const PREWARM_REFETCH_AGE: Duration = Duration::from_secs(2 * 60);
+
+/// Shaped like the browser's `RTCIceServer`, so it can be passed straight in.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct IceServer {
+    urls: IceUrls,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    credential: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+enum IceUrls {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TransportMode {
+    /// Whatever ICE picks.
+    #[default]
+    Auto,
+    /// No TCP candidates or TURN over TCP/TLS.
+    UdpOnly,
+    /// Relay only, over TCP or TLS.
+    TcpRelay,
+}
+
+impl TransportMode {
+    /// `RTCConfiguration.iceTransportPolicy` for this mode.
+    fn ice_transport_policy(self) -> &'static str {
+        match self {
+            TransportMode::TcpRelay => "relay",
+            TransportMode::Auto | TransportMode::UdpOnly => "all",
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct TransportConfig {
+    mode: TransportMode,
+    ice_transport_policy: &'static str,
+}
+
+pub fn transport_mode(app: &AppHandle) -> TransportMode {
+    crate::prefs::get(app, TRANSPORT_PREF).unwrap_or_default()
+}
+
+fn is_tcp(url: &str) -> bool {
+    url.starts_with("turns:") || url.contains("transport=tcp")
+}
+
+/// The URLs of one server that `mode` allows, rewritten where needed.
+fn filter_urls(urls: &[String], mode: TransportMode) -> Vec<String> {
+    match mode {
+        TransportMode::Auto => urls.to_vec(),
+        TransportMode::UdpOnly => urls.iter().filter(|u| !is_tcp(u)).cloned().collect(),
+        // STUN is useless without host or srflx candidates; plain TURN is
+        // asked for over TCP.
+        TransportMode::TcpRelay => urls
+            .iter()
+            .filter_map(|u| {
+                if is_tcp(u) {
+                    Some(u.clone())
+                } else if u.starts_with("turn:") && !u.contains("transport=") {
+                    Some(format!("{u}?transport=tcp"))
+                } else {
+                    None
+                }
+            })
+            .collect(),
+    }
+}
+
+/// `servers` cut down to what `mode` allows; servers left with no URL are
+/// dropped.
+fn apply_transport(servers: Vec<IceServer>, mode: TransportMode) -> Vec<IceServer> {
+    if mode == TransportMode::Auto {
+        return servers;
+    }
+    servers
+        .into_iter()
+        .filter_map(|mut server| {
+            let urls = match &server.urls {
+                IceUrls::One(url) => filter_urls(std::slice::from_ref(url), mode),
+                IceUrls::Many(urls) => filter_urls(urls, mode),
+            };
+            if urls.is_empty() {
+                return None;
+            }
+            server.urls = IceUrls::Many(urls);
+            Some(server)
+        })
+        .collect()
+}
+
+/// `apply_transport`, failing when TCP relay leaves nothing to relay through:
+/// the call would otherwise start with no way to connect.
+fn servers_for_mode(servers: Vec<IceServer>, mode: TransportMode) -> Result<Vec<IceServer>, CommandError> {
+    let filtered = apply_transport(servers, mode);
+    if filtered.is_empty() && mode == TransportMode::TcpRelay {
+        return Err(CommandError::unreachable("the server offers no TURN over TCP or TLS"));
+    }
+    Ok(filtered)
+}
+
+/// Either a bare list or `{ "ice_servers": [...] }`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum IceResponse {
+    List(Vec<IceServer>),
+    Wrapped { ice_servers: Vec<IceServer> },
+}
+
+struct Cached {
+    server_url: String,
+    fetched: Instant,
+    servers: Vec<IceServer>,
+}
+
+#[derive(Default)]
+pub struct IceCache(Mutex<Option<Cached>>);
+
+/// The ICE servers for the current server, from cache when fresh, filtered
+/// for the transport mode. `refresh` skips the cache. Failures are
+/// `unreachable` (couldn't get a list at all) so the call UI can fall back
+/// to peer-to-peer only.
+#[tauri::command]
+pub async fn get_ice_servers(app: AppHandle, refresh: Option<bool>) -> Result<Vec<IceServer>, CommandError> {
+    let auth = app.state::<AuthState>().0.lock().unwrap().clone();
+    let info = auth.ok_or_else(|| CommandError::auth("Not signed in"))?;
+    if !crate::capabilities::supports(&info.server_url, crate::capabilities::Feature::Turn) {
+        return Err(CommandError::unreachable("this server doesn't provide ICE servers"));
+    }
+    if refresh != Some(true) {
+        let cache = app.state::<IceCache>();
+        let cache = cache.0.lock().unwrap();
+        if let Some(c) = cache.as_ref() {
+            if c.server_url == info.server_url && c.fetched.elapsed() < ICE_TTL {
+                return servers_for_mode(c.servers.clone(), transport_mode(&app));
+            }
+        }
+    }
+
+    let response = crate::net::client()
+        .map_err(CommandError::unreachable)?
+        .get(format!("{}/api/rtc/ice", info.server_url))
+        .bearer_auth(&info.token)
+        .timeout(ICE_TIMEOUT)
+        .send_traced()
+        .await
+        .map_err(CommandError::unreachable)?;
+    let status = response.status();
+    if !status.is_success() {
+        crate::net::record_error("GET", response).await;
+        return Err(CommandError::unreachable(format!("server returned {status}")));
+    }
+    let body = response.text().await.map_err(CommandError::unreachable)?;
+    let servers = match serde_json::from_str(&body)
+        .map_err(|e| CommandError::Parse { message: format!("invalid ice config: {e}") })?
+    {
+        IceResponse::List(s) | IceResponse::Wrapped { ice_servers: s } => s,
+    };
+
+    *app.state::<IceCache>().0.lock().unwrap() = Some(Cached {
+        server_url: info.server_url,
+        fetched: Instant::now(),
+        servers: servers.clone(),
+    });
+    servers_for_mode(servers, transport_mode(&app))
+}
+
+fn transport_config(mode: TransportMode) -> TransportConfig {
+    TransportConfig {
+        mode,
+        ice_transport_policy: mode.ice_transport_policy(),
+    }
+}
+
+#[tauri::command]
+pub fn get_transport_mode(app: AppHandle) -> TransportConfig {
+    transport_config(transport_mode(&app))
+}
+
+/// Save the transport mode and emit `rtc://transport` so the call layer
+/// rebuilds its configuration; a call in progress keeps its connection
+/// until it reconnects.
+#[tauri::command]
+pub fn set_transport_mode(app: AppHandle, mode: TransportMode) -> Result<TransportConfig, CommandError> {
+    crate::prefs::set(&app, TRANSPORT_PREF, &mode)?;
+    let config = transport_config(mode);
+    let _ = app.emit("rtc://transport", config.clone());
+    Ok(config)
+}
+
+fn cache_age(app: &AppHandle, server_url: &str) -> Option<Duration> {
+    let cache = app.state::<IceCache>();
+    let cache = cache.0.lock().unwrap();
+    cache
+        .as_ref()
+        .filter(|c| c.server_url == server_url)
+        .map(|c| c.fetched.elapsed())
+}
+
+#[derive(Serialize, Clone)]
+pub struct RtcReadiness {
+    /// `None` when they couldn't be fetched; see `ice_error`.
+    ice_servers: Option<Vec<IceServer>>,
+    ice_error: Option<CommandError>,
+    permissions: MediaPermissions,
+    /// Why a requested permission isn't available.
+    permission_error: Option<CommandError>,
+    /// The devices a call would open now; `None` for none found.
+    input_device: Option<String>,
+    output_device: Option<String>,
+    /// Nothing above failed.
+    ready: bool,
+}
+
+/// Get a call ready ahead of time: fetch (or refresh) the ICE servers,
+/// settle media access and resolve the audio devices, then emit the result
+/// as `rtc://ready`. `media` is what the call is likely to use; those are
+/// requested (which can prompt on macOS), while leaving it out only checks.
+#[tauri::command]
+pub async fn prewarm_rtc(app: AppHandle, media: Option<Vec<MediaKind>>) -> RtcReadiness {
+    let server_url = app.state::<AuthState>().0.lock().unwrap().as_ref().map(|i| i.server_url.clone());
+    let stale = server_url
+        .as_deref()
+        .and_then(|url| cache_age(&app, url))
+        .is_some_and(|age| age > PREWARM_REFETCH_AGE);
+    let (ice_servers, ice_error) = match get_ice_servers(app.clone(), Some(stale)).await {
+        Ok(servers) => (Some(servers), None),
+        Err(e) => (None, Some(e)),
+    };
+
+    let (permissions, permission_error) = match media {
+        Some(kinds) => match crate::media::request_media_permissions(kinds).await {
+            Ok(permissions) => (permissions, None),
+            Err(e) => (crate::media::check_media_permissions(), Some(e)),
+        },
+        None => (crate::media::check_media_permissions(), None),
+    };
+
+    // Opening the host and finding the devices is the slow part of the
+    // first capture on some backends; doing it now leaves it warm.
+    let (input_device, output_device) = if crate::environment::audio_monitors_enabled() {
+        let app = app.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            use cpal::traits::DeviceTrait;
+            (
+                crate::audio::input_device(&app).and_then(|d| d.name().ok()),
+                crate::audio::output_device(&app).and_then(|d| d.name().ok()),
+            )
+        })
+        .await
+        .unwrap_or_default()
+    } else {
+        (None, None)
+    };
+
+    let readiness = RtcReadiness {
+        ready: ice_error.is_none() && permission_error.is_none(),
+        ice_servers,
+        ice_error,
+        permissions,
+        permission_error,
+        input_device,
+        output_device,
+    };
+    let _ = app.emit("rtc://ready", readiness.clone());
+    readiness
+}
@@ -0,0 +1,52 @@
+// Recovery restart. `restart_safe_mode` drops a flag file and relaunches;
+// the next launch consumes the flag, so safe mode lasts exactly one run and
+// any normal restart leaves it.
+
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+use crate::error::CommandError;
+
+const FLAG_FILE: &str = "safe-mode";
+
+/// Whether this run is in safe mode. Managed from setup.
+pub struct SafeMode(pub bool);
+
+fn flag_path(app: &AppHandle) -> Option<PathBuf> {
+    app.path().app_config_dir().ok().map(|d| d.join(FLAG_FILE))
+}
+
+/// Check for and clear the flag. Called first thing in setup.
+pub fn take(app: &AppHandle) -> bool {
+    let Some(path) = flag_path(app) else { return false };
+    if !path.exists() {
+        return false;
+    }
+    if let Err(e) = std::fs::remove_file(&path) {
+        log::warn!("failed to clear safe mode flag: {e}");
+    }
+    true
+}
+
+/// Point the main window at the minimal recovery screen.
+pub fn load_recovery_route(app: &AppHandle) {
+    let Some(window) = app.get_webview_window("main") else { return };
+    let Ok(mut url) = window.url() else { return };
+    url.set_query(Some("safe_mode=1"));
+    let _ = window.navigate(url);
+}
+
+#[tauri::command]
+pub fn restart_safe_mode(app: AppHandle) -> Result<(), CommandError> {
+    let path = flag_path(&app).ok_or_else(|| CommandError::io("no app config dir"))?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, b"")?;
+    app.restart();
+}
+
+#[tauri::command]
+pub fn is_safe_mode(state: tauri::State<'_, SafeMode>) -> bool {
+    state.0
+}
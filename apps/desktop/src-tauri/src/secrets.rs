@@ -0,0 +1,161 @@
+// Auth token storage in the platform secret store (Keychain on macOS,
+// Credential Manager on Windows, Secret Service on Linux) so it never sits
+// in localStorage as plaintext. Each server gets its own entry; the
+// single-server entry from older versions is moved over on first read.
+// Refresh tokens get entries of their own next to the access token.
+
+use tauri::{AppHandle, Manager};
+
+use crate::error::CommandError;
+use crate::presence::AuthState;
+
+const SERVICE: &str = "com.distokoloshe.desktop";
+const TOKEN_ACCOUNT: &str = "auth_token";
+const REFRESH_ACCOUNT: &str = "refresh_token";
+
+/// `server` is a normalized server URL; `None` is the legacy entry.
+fn token_entry(server: Option<&str>) -> Result<keyring::Entry, CommandError> {
+    let account = match server {
+        Some(url) => format!("{TOKEN_ACCOUNT}:{url}"),
+        None => TOKEN_ACCOUNT.to_string(),
+    };
+    keyring::Entry::new(SERVICE, &account).map_err(describe)
+}
+
+fn refresh_entry(server: &str) -> Result<keyring::Entry, CommandError> {
+    keyring::Entry::new(SERVICE, &format!("{REFRESH_ACCOUNT}:{server}")).map_err(describe)
+}
+
+/// Map keyring errors to something the frontend can show. A missing secret
+/// store (e.g. headless Linux with no keyring daemon) gets its own kind so
+/// the UI can offer a fallback.
+fn describe(e: keyring::Error) -> CommandError {
+    match e {
+        keyring::Error::PlatformFailure(inner) | keyring::Error::NoStorageAccess(inner) => {
+            CommandError::SecretStoreUnavailable { message: inner.to_string() }
+        }
+        other => CommandError::io(other),
+    }
+}
+
+fn read_entry(server: Option<&str>) -> Result<Option<String>, CommandError> {
+    match token_entry(server)?.get_password() {
+        Ok(token) => Ok(Some(token)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(describe(e)),
+    }
+}
+
+pub fn read_token(server: Option<&str>) -> Result<Option<String>, CommandError> {
+    let token = read_entry(server)?;
+    if token.is_some() || server.is_none() {
+        return Ok(token);
+    }
+    // Adopt a pre-multi-server token for whichever server asks first.
+    let Some(legacy) = read_entry(None)? else { return Ok(None) };
+    token_entry(server)?.set_password(&legacy).map_err(describe)?;
+    delete_token(None)?;
+    Ok(Some(legacy))
+}
+
+/// Whether `server` has its own saved token. Unlike `read_token`, never
+/// adopts the legacy entry.
+pub fn has_token(server: &str) -> bool {
+    matches!(read_entry(Some(server)), Ok(Some(_)))
+}
+
+/// Also forgets the server's refresh token: it's no use without the sign-in
+/// it belongs to.
+pub fn delete_token(server: Option<&str>) -> Result<(), CommandError> {
+    if let Some(server) = server {
+        delete_refresh_token(server)?;
+    }
+    match token_entry(server)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(describe(e)),
+    }
+}
+
+/// Replace the access token after a refresh, in the keychain and for the
+/// beacons.
+pub fn store_token(app: &AppHandle, server: &str, token: &str) -> Result<(), CommandError> {
+    token_entry(Some(server))?.set_password(token).map_err(describe)?;
+    crate::logging::remember_token(Some(token));
+    let state = app.state::<AuthState>();
+    if let Some(info) = state.0.lock().unwrap().as_mut().filter(|i| i.server_url == server) {
+        info.token = token.to_string();
+    }
+    Ok(())
+}
+
+pub fn read_refresh_token(server: &str) -> Result<Option<String>, CommandError> {
+    match refresh_entry(server)?.get_password() {
+        Ok(token) => Ok(Some(token)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(describe(e)),
+    }
+}
+
+pub fn save_refresh(server: &str, token: &str) -> Result<(), CommandError> {
+    refresh_entry(server)?.set_password(token).map_err(describe)
+}
+
+pub fn delete_refresh_token(server: &str) -> Result<(), CommandError> {
+    match refresh_entry(server)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(describe(e)),
+    }
+}
+
+/// `server_url` if given, else the active server.
+fn server_for(app: &AppHandle, server_url: Option<String>) -> Result<Option<String>, CommandError> {
+    match server_url {
+        Some(raw) => crate::net::normalize_server_url(&raw).map(Some),
+        None => Ok(crate::net::server_url(app)),
+    }
+}
+
+#[tauri::command]
+pub fn save_token(
+    app: AppHandle,
+    state: tauri::State<'_, AuthState>,
+    token: String,
+    server_url: Option<String>,
+) -> Result<(), CommandError> {
+    let server = server_for(&app, server_url)?;
+    token_entry(server.as_deref())?.set_password(&token).map_err(describe)?;
+    crate::logging::remember_token(Some(&token));
+    crate::auth::clear_expired();
+    // Keep the in-memory copy used by the beacons in step with the keychain.
+    if let Some(info) = state.0.lock().unwrap().as_mut() {
+        if server.as_deref().is_none_or(|s| s == info.server_url) {
+            info.token = token;
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn load_token(app: AppHandle, server_url: Option<String>) -> Result<Option<String>, CommandError> {
+    read_token(server_for(&app, server_url)?.as_deref())
+}
+
+#[tauri::command]
+pub fn clear_token(app: AppHandle, server_url: Option<String>) -> Result<(), CommandError> {
+    delete_token(server_for(&app, server_url)?.as_deref())
+}
+
+/// Keep the refresh token from sign-in, so an expired access token can be
+/// renewed natively (see `auth::refresh_after_401`).
+#[tauri::command]
+pub fn save_refresh_token(
+    app: AppHandle,
+    refresh_token: String,
+    server_url: Option<String>,
+) -> Result<(), CommandError> {
+    let server = server_for(&app, server_url)?
+        .ok_or_else(|| CommandError::invalid_input("no server url set"))?;
+    save_refresh(&server, &refresh_token)?;
+    crate::auth::clear_expired();
+    Ok(())
+}
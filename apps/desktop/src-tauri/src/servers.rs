@@ -0,0 +1,151 @@
+// Saved servers for users on more than one distokoloshe instance. The list
+// and the active server are prefs; each server's token lives in its own
+// keychain entry (see secrets.rs), so switching doesn't mean signing in again.
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::CommandError;
+use crate::presence::{self, AuthInfo, AuthState};
+use crate::{net, prefs};
+
+pub(crate) const SERVERS: &str = "servers";
+pub(crate) const ACTIVE: &str = "active_server";
+/// Relaunch instead of switching in place, for anyone who sees state from
+/// the previous server (ICE config, caches, the event stream) linger.
+const RELAUNCH: &str = "relaunch_on_server_switch";
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SavedServer {
+    /// Normalized, see `net::normalize_server_url`.
+    pub url: String,
+    pub name: String,
+}
+
+#[derive(Serialize)]
+pub struct ServerEntry {
+    #[serde(flatten)]
+    server: SavedServer,
+    active: bool,
+    signed_in: bool,
+}
+
+fn load(app: &AppHandle) -> Vec<SavedServer> {
+    prefs::get(app, SERVERS).unwrap_or_default()
+}
+
+/// Make the last active server current again. Called from setup.
+pub fn restore(app: &AppHandle) {
+    let Some(url) = prefs::get::<String>(app, ACTIVE) else { return };
+    if let Err(e) = net::store_server_url(app, &url) {
+        log::warn!("ignoring saved active server: {e}");
+    }
+}
+
+#[tauri::command]
+pub fn list_servers(app: AppHandle) -> Vec<ServerEntry> {
+    let active = net::server_url(&app);
+    load(&app)
+        .into_iter()
+        .map(|server| ServerEntry {
+            active: active.as_deref() == Some(server.url.as_str()),
+            signed_in: crate::secrets::has_token(&server.url),
+            server,
+        })
+        .collect()
+}
+
+/// Canonicalize a server URL as typed or pasted and check a distokoloshe
+/// server answers there, for the UI to run before saving it. Returns the URL
+/// to save, or fails with `bad_scheme`, `unreachable` or
+/// `not_a_distokoloshe_server`.
+#[tauri::command]
+pub async fn normalize_server_url(url: String) -> Result<String, CommandError> {
+    let url = net::normalize_server_url(&url)?;
+    net::probe_server(&url).await?;
+    Ok(url)
+}
+
+/// Add a server, or rename it if it's already saved.
+#[tauri::command]
+pub fn add_server(app: AppHandle, url: String, name: String) -> Result<(), CommandError> {
+    let url = net::normalize_server_url(&url)?;
+    let mut servers = load(&app);
+    match servers.iter_mut().find(|s| s.url == url) {
+        Some(existing) => existing.name = name,
+        None => servers.push(SavedServer { url, name }),
+    }
+    prefs::set(&app, SERVERS, &servers)
+}
+
+/// Forget a server and its token. The active server can't be removed; switch
+/// away from it first.
+#[tauri::command]
+pub fn remove_server(app: AppHandle, url: String) -> Result<(), CommandError> {
+    let url = net::normalize_server_url(&url)?;
+    if net::server_url(&app).as_deref() == Some(url.as_str()) {
+        return Err(CommandError::invalid_input("cannot remove the active server"));
+    }
+    let mut servers = load(&app);
+    servers.retain(|s| s.url != url);
+    prefs::set(&app, SERVERS, &servers)?;
+    crate::secrets::delete_token(Some(&url))
+}
+
+/// Leave the current server, make `url` current, and join it with its saved
+/// token if there is one. Emits `server://switched` with whether the new
+/// server is signed in; if not, the UI shows its login.
+///
+/// With `relaunch` (default: the `relaunch_on_server_switch` pref) the app
+/// restarts once the leave beacon is out, and comes back up on the new
+/// server with nothing carried over; this doesn't return.
+#[tauri::command]
+pub async fn switch_server(app: AppHandle, url: String, relaunch: Option<bool>) -> Result<bool, CommandError> {
+    let url = net::normalize_server_url(&url)?;
+    if !load(&app).iter().any(|s| s.url == url) {
+        return Err(CommandError::invalid_input(format!("unknown server: {url}")));
+    }
+    if net::server_url(&app).as_deref() == Some(url.as_str()) {
+        return Ok(app.state::<AuthState>().0.lock().unwrap().is_some());
+    }
+
+    let relaunch = relaunch.unwrap_or_else(|| prefs::get(&app, RELAUNCH).unwrap_or(false));
+    // Before anything changes, so a relaunch that can't work leaves the
+    // current server as it was.
+    if relaunch {
+        crate::restart_preflight(&app).map_err(|message| CommandError::RestartFailed { message })?;
+    }
+
+    if let Err(e) = presence::leave(&app).await {
+        log::warn!("leave beacon to previous server failed: {e}");
+    }
+    presence::stop_heartbeat(&app);
+    if relaunch {
+        *app.state::<AuthState>().0.lock().unwrap() = None;
+        net::store_server_url(&app, &url)?;
+        prefs::set(&app, ACTIVE, &url)?;
+        log::info!("relaunching to switch to {url}");
+        app.restart();
+    }
+    crate::typing::clear(&app);
+    *app.state::<AuthState>().0.lock().unwrap() = None;
+    net::store_server_url(&app, &url)?;
+    prefs::set(&app, ACTIVE, &url)?;
+
+    let token = crate::secrets::read_token(Some(&url))?;
+    crate::logging::remember_token(token.as_deref());
+    let signed_in = token.is_some();
+    if let Some(token) = token {
+        let info = AuthInfo { token, server_url: url.clone() };
+        *app.state::<AuthState>().0.lock().unwrap() = Some(info.clone());
+        presence::start_heartbeat(&app);
+        if let Err(e) = presence::post_event(&info, "join").await {
+            log::warn!("join beacon to {url} failed: {e}");
+        }
+        crate::stream::restart_if_running(&app);
+    } else {
+        crate::stream::stop(&app);
+    }
+    let _ = app.emit("server://switched", serde_json::json!({ "url": url, "signed_in": signed_in }));
+    Ok(signed_in)
+}
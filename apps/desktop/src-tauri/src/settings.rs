@@ -0,0 +1,178 @@
+// Carry preferences between installs: export the prefs store to a file the
+// user picks, and merge such a file back in. Tokens live in the OS keychain
+// and never pass through here. The webview's own keys go both ways as they
+// are; of the native ones only `IMPORTED_NATIVE` does, each applied through
+// its own command so it's validated and takes effect like a change made in
+// the app. The rest (proxy, trusted CA and pinned certificates, servers,
+// update state, devtools) is never exported and is refused on import, so a
+// crafted file can't change what the app trusts or where it connects.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+use tauri_plugin_dialog::{DialogExt, FilePath};
+
+use crate::error::CommandError;
+use crate::prefs;
+
+const SCHEMA_VERSION: u32 = 1;
+
+/// Native keys carried over, all look-and-feel; see `apply_native`.
+const IMPORTED_NATIVE: &[&str] = &[
+    crate::accent::ACCENT_PREF,
+    crate::window::ALWAYS_ON_TOP,
+    crate::volume::APP_VOLUME,
+    crate::idle::AWAY_ON_BLUR_PREF,
+    crate::throttle::THROTTLE_PREF,
+    prefs::CLOSE_TO_TRAY,
+    crate::window::DECORATIONS,
+    crate::i18n::LOCALE_PREF,
+    crate::theme::THEME_PREF,
+    crate::vad::THRESHOLD_PREF,
+    crate::zoom::ZOOM,
+];
+
+fn carried(key: &str) -> bool {
+    !prefs::is_native(key) || IMPORTED_NATIVE.contains(&key)
+}
+
+#[derive(Serialize)]
+pub struct ImportReport {
+    applied: Vec<String>,
+    /// Native keys that aren't imported, or whose value its command refused.
+    skipped: Vec<String>,
+}
+
+fn value<T: DeserializeOwned>(key: &str, value: Value) -> Result<T, String> {
+    serde_json::from_value(value).map_err(|e| format!("{key}: {e}"))
+}
+
+/// Apply one of `IMPORTED_NATIVE` through its command.
+async fn apply_native(app: &AppHandle, key: &str, v: Value) -> Result<(), String> {
+    let app = app.clone();
+    match key {
+        k if k == crate::accent::ACCENT_PREF => {
+            crate::accent::set_accent_color(app, value(k, v)?).map_err(|e| e.to_string())?;
+        }
+        k if k == crate::window::ALWAYS_ON_TOP => crate::window::set_always_on_top(app, value(k, v)?)?,
+        k if k == crate::volume::APP_VOLUME => crate::volume::set_app_volume(app, value(k, v)?).await?,
+        k if k == crate::idle::AWAY_ON_BLUR_PREF => crate::idle::set_away_on_blur(app, value(k, v)?)?,
+        k if k == crate::throttle::THROTTLE_PREF => {
+            crate::throttle::set_background_throttle(app, value(k, v)?).map_err(|e| e.to_string())?;
+        }
+        k if k == prefs::CLOSE_TO_TRAY => crate::tray::set_close_to_tray(app, value(k, v)?)?,
+        k if k == crate::window::DECORATIONS => crate::window::set_decorations(app, value(k, v)?)?,
+        k if k == crate::i18n::LOCALE_PREF => {
+            crate::i18n::set_locale(app, value(k, v)?)?;
+        }
+        k if k == crate::theme::THEME_PREF => {
+            crate::theme::set_theme(app, value(k, v)?)?;
+        }
+        k if k == crate::vad::THRESHOLD_PREF => {
+            crate::vad::set_vad_threshold(app, value(k, v)?)?;
+        }
+        k if k == crate::zoom::ZOOM => {
+            crate::zoom::set_zoom(app, value(k, v)?)?;
+        }
+        _ => return Err(format!("{key} is not imported")),
+    }
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize)]
+struct SettingsFile {
+    schema: u32,
+    app_version: String,
+    /// Unix seconds.
+    exported_at: u64,
+    prefs: Map<String, Value>,
+}
+
+/// Wait for a file dialog's answer; `None` if it was cancelled.
+async fn pick(
+    rx: tokio::sync::oneshot::Receiver<Option<FilePath>>,
+) -> Result<Option<std::path::PathBuf>, String> {
+    match rx.await.map_err(|e| e.to_string())? {
+        Some(path) => path.into_path().map(Some).map_err(|e| e.to_string()),
+        None => Ok(None),
+    }
+}
+
+/// Returns `false` if the user cancelled the save dialog.
+#[tauri::command]
+pub async fn export_settings(app: AppHandle) -> Result<bool, CommandError> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .add_filter("disTokoloshe settings", &["json"])
+        .set_file_name("distokoloshe-settings.json")
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+    let Some(path) = pick(rx).await? else { return Ok(false) };
+
+    let mut entries = prefs::all(&app);
+    entries.retain(|key, _| carried(key));
+    let file = SettingsFile {
+        schema: SCHEMA_VERSION,
+        app_version: app.package_info().version.to_string(),
+        exported_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs()),
+        prefs: entries,
+    };
+    let json = serde_json::to_string_pretty(&file)?;
+    std::fs::write(&path, json)?;
+    Ok(true)
+}
+
+/// Merge a chosen settings file into the store, one `prefs://changed` per
+/// key. Returns what was applied and what was skipped, or `None` if the
+/// user cancelled.
+#[tauri::command]
+pub async fn import_settings(app: AppHandle) -> Result<Option<ImportReport>, CommandError> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .add_filter("disTokoloshe settings", &["json"])
+        .pick_file(move |path| {
+            let _ = tx.send(path);
+        });
+    let Some(path) = pick(rx).await? else { return Ok(None) };
+
+    let text = std::fs::read_to_string(&path)?;
+    let raw: Value = serde_json::from_str(&text).map_err(|e| CommandError::invalid_input(format!("not a settings file: {e}")))?;
+    // Check the version before the shape, so a newer file gets a clear error.
+    match raw.get("schema").and_then(Value::as_u64) {
+        Some(v) if v == u64::from(SCHEMA_VERSION) => {}
+        Some(v) => return Err(CommandError::invalid_input(format!("unsupported settings schema version {v}"))),
+        None => return Err(CommandError::invalid_input("not a settings file: missing schema version")),
+    }
+    let file: SettingsFile =
+        serde_json::from_value(raw).map_err(|e| CommandError::invalid_input(format!("not a settings file: {e}")))?;
+
+    let mut report = ImportReport {
+        applied: Vec::new(),
+        skipped: Vec::new(),
+    };
+    for (key, value) in file.prefs {
+        if value.is_null() {
+            continue;
+        }
+        if !prefs::is_native(&key) {
+            prefs::set(&app, &key, &value)?;
+            report.applied.push(key);
+            continue;
+        }
+        match apply_native(&app, &key, value).await {
+            Ok(()) => report.applied.push(key),
+            Err(e) => {
+                log::info!("settings import skipped {key}: {e}");
+                report.skipped.push(key);
+            }
+        }
+    }
+    Ok(Some(report))
+}
@@ -0,0 +1,418 @@
+// Native global shortcuts. Registered through tauri-plugin-global-shortcut so
+// they fire while the window is unfocused; each action is surfaced to the
+// webview as an event rather than calling into JS directly.
+//
+// The plugin can fail to start (no X server to grab keys from, say); the app
+// then runs without global shortcuts, the backend reports `unavailable` and
+// binding fails as `unsupported` instead of taking startup down.
+//
+// The saved set can be exported as a JSON profile and applied elsewhere.
+// Only actions with a bindable shortcut are in it; zoom uses fixed keys and
+// deafen has no shortcut of its own.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcut, GlobalShortcutExt, Shortcut, ShortcutState};
+
+use crate::error::CommandError;
+use crate::idle::IdleState;
+
+pub const PUSH_TO_TALK: &str = "push_to_talk";
+pub const MUTE_TOGGLE: &str = "mute_toggle";
+
+const STORE_FILE: &str = "shortcuts.json";
+const ACTIONS: &[&str] = &[PUSH_TO_TALK, MUTE_TOGGLE];
+const PROFILE_VERSION: u32 = 1;
+
+/// The plugin loaded; `global_shortcut()` panics when it didn't.
+static AVAILABLE: AtomicBool = AtomicBool::new(false);
+
+/// Currently bound shortcut per action.
+pub struct Bindings(pub Mutex<HashMap<&'static str, Shortcut>>);
+
+pub struct MuteState(pub AtomicBool);
+
+/// How global shortcuts reach us on this system.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortcutBackend {
+    /// The OS hotkey API (Windows, macOS).
+    Native,
+    /// X11 key grabs, which see every key press.
+    X11,
+    /// X11 grabs through XWayland under a Wayland compositor. Many
+    /// compositors only pass keys on while one of our windows is focused,
+    /// so push-to-talk can look broken from other apps.
+    Xwayland,
+    /// The plugin failed to load; there are no global shortcuts.
+    Unavailable,
+}
+
+fn backend() -> ShortcutBackend {
+    if !available() {
+        return ShortcutBackend::Unavailable;
+    }
+    if cfg!(not(target_os = "linux")) {
+        return ShortcutBackend::Native;
+    }
+    let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some_and(|d| !d.is_empty())
+        || std::env::var("XDG_SESSION_TYPE").is_ok_and(|t| t.eq_ignore_ascii_case("wayland"));
+    if wayland {
+        ShortcutBackend::Xwayland
+    } else {
+        ShortcutBackend::X11
+    }
+}
+
+#[tauri::command]
+pub fn get_shortcut_backend() -> ShortcutBackend {
+    backend()
+}
+
+/// Like the `From` conversion, but on Wayland a refused grab is almost always
+/// the compositor, so say that instead.
+fn registration_error(e: tauri_plugin_global_shortcut::Error) -> CommandError {
+    match CommandError::from(e) {
+        CommandError::ReservedByOs { message } | CommandError::Other { message }
+            if backend() == ShortcutBackend::Xwayland =>
+        {
+            CommandError::WaylandPortalRequired {
+                message: format!(
+                    "the Wayland compositor refused the shortcut ({message}); bind a key to \
+                     disTokoloshe in your desktop's keyboard settings instead"
+                ),
+            }
+        }
+        other => other,
+    }
+}
+
+fn parse_accelerator(accelerator: &str) -> Result<Shortcut, CommandError> {
+    accelerator
+        .parse::<Shortcut>()
+        .map_err(|e| CommandError::invalid_input(format!("invalid accelerator \"{accelerator}\": {e}")))
+}
+
+/// The keys are already bound to our `action`.
+fn taken_by(action: &str) -> CommandError {
+    CommandError::AlreadyRegistered {
+        action: Some(action.to_string()),
+        message: format!("shortcut already bound to {action}"),
+    }
+}
+
+/// Load the plugin. A failure is logged and leaves the app without global
+/// shortcuts rather than failing setup. Called from setup.
+pub fn init(app: &AppHandle) {
+    match app.plugin(tauri_plugin_global_shortcut::Builder::new().build()) {
+        Ok(()) => AVAILABLE.store(true, Ordering::Relaxed),
+        Err(e) => log::error!("global shortcuts unavailable, continuing without them: {e}"),
+    }
+}
+
+pub fn available() -> bool {
+    AVAILABLE.load(Ordering::Relaxed)
+}
+
+fn plugin(app: &AppHandle) -> Result<&GlobalShortcut<tauri::Wry>, CommandError> {
+    if !available() {
+        return Err(CommandError::unsupported("global shortcuts are unavailable on this system"));
+    }
+    Ok(app.global_shortcut())
+}
+
+/// Reject a shortcut that is already bound to a different action.
+fn check_conflict(app: &AppHandle, action: &str, shortcut: &Shortcut) -> Result<(), CommandError> {
+    let bindings = app.state::<Bindings>();
+    let bindings = bindings.0.lock().unwrap();
+    match bindings.iter().find(|(a, s)| **a != action && *s == shortcut) {
+        Some((other, _)) => Err(taken_by(other)),
+        None => Ok(()),
+    }
+}
+
+fn store_path(app: &AppHandle) -> Option<std::path::PathBuf> {
+    app.path().app_config_dir().ok().map(|d| d.join(STORE_FILE))
+}
+
+/// Saved accelerators keyed by action name.
+fn load_store(app: &AppHandle) -> HashMap<String, String> {
+    store_path(app)
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_accelerator(app: &AppHandle, action: &str, accelerator: Option<&str>) -> Result<(), CommandError> {
+    let path = store_path(app).ok_or_else(|| CommandError::io("no app config dir"))?;
+    let mut store = load_store(app);
+    match accelerator {
+        Some(a) => store.insert(action.to_string(), a.to_string()),
+        None => store.remove(action),
+    };
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_string_pretty(&store)?;
+    Ok(std::fs::write(path, json)?)
+}
+
+/// Drop whatever is bound to `action`, if anything.
+fn unbind(app: &AppHandle, action: &str) -> Result<(), CommandError> {
+    let old = app.state::<Bindings>().0.lock().unwrap().remove(action);
+    if let Some(shortcut) = old {
+        plugin(app)?.unregister(shortcut)?;
+    }
+    Ok(())
+}
+
+fn bind_push_to_talk(app: &AppHandle, accelerator: &str) -> Result<(), CommandError> {
+    let shortcut = parse_accelerator(accelerator)?;
+    check_conflict(app, PUSH_TO_TALK, &shortcut)?;
+    unbind(app, PUSH_TO_TALK)?;
+    plugin(app)?
+        .on_shortcut(shortcut, |app, _, event| {
+            let held = event.state == ShortcutState::Pressed;
+            app.state::<IdleState>().ptt_held.store(held, Ordering::Relaxed);
+            let name = if held { "ptt://down" } else { "ptt://up" };
+            let _ = app.emit(name, ());
+        })
+        .map_err(registration_error)?;
+    app.state::<Bindings>()
+        .0
+        .lock()
+        .unwrap()
+        .insert(PUSH_TO_TALK, shortcut);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn register_push_to_talk(app: AppHandle, accelerator: String) -> Result<(), CommandError> {
+    bind_push_to_talk(&app, &accelerator)?;
+    save_accelerator(&app, PUSH_TO_TALK, Some(&accelerator))
+}
+
+#[tauri::command]
+pub fn unregister_push_to_talk(app: AppHandle) -> Result<(), CommandError> {
+    unbind(&app, PUSH_TO_TALK)?;
+    save_accelerator(&app, PUSH_TO_TALK, None)
+}
+
+pub fn muted(app: &AppHandle) -> bool {
+    app.state::<MuteState>().0.load(Ordering::Relaxed)
+}
+
+/// Set the native mute flag, telling the webview if it changed.
+pub fn set_muted(app: &AppHandle, muted: bool) {
+    if app.state::<MuteState>().0.swap(muted, Ordering::Relaxed) != muted {
+        let _ = app.emit("mute://toggled", muted);
+        crate::quickactions::refresh(app);
+    }
+}
+
+/// Flip the native mute flag and tell the webview. Shared by the global
+/// shortcut and the tray menu.
+pub fn toggle_mute(app: &AppHandle) -> bool {
+    // fetch_xor returns the previous value; the new state is its inverse.
+    let muted = !app.state::<MuteState>().0.fetch_xor(true, Ordering::Relaxed);
+    let _ = app.emit("mute://toggled", muted);
+    crate::quickactions::refresh(app);
+    muted
+}
+
+fn bind_mute_toggle(app: &AppHandle, accelerator: &str) -> Result<(), CommandError> {
+    let shortcut = parse_accelerator(accelerator)?;
+    check_conflict(app, MUTE_TOGGLE, &shortcut)?;
+    unbind(app, MUTE_TOGGLE)?;
+    plugin(app)?
+        .on_shortcut(shortcut, |app, _, event| {
+            if event.state == ShortcutState::Pressed {
+                toggle_mute(app);
+            }
+        })
+        .map_err(registration_error)?;
+    app.state::<Bindings>()
+        .0
+        .lock()
+        .unwrap()
+        .insert(MUTE_TOGGLE, shortcut);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn register_mute_toggle(app: AppHandle, accelerator: String) -> Result<(), CommandError> {
+    bind_mute_toggle(&app, &accelerator)?;
+    save_accelerator(&app, MUTE_TOGGLE, Some(&accelerator))
+}
+
+#[tauri::command]
+pub fn unregister_mute_toggle(app: AppHandle) -> Result<(), CommandError> {
+    unbind(&app, MUTE_TOGGLE)?;
+    save_accelerator(&app, MUTE_TOGGLE, None)
+}
+
+#[derive(Serialize, Clone)]
+struct RestoreFailed {
+    action: String,
+    accelerator: String,
+    error: String,
+}
+
+fn bind(app: &AppHandle, action: &str, accelerator: &str) -> Result<(), CommandError> {
+    match action {
+        PUSH_TO_TALK => bind_push_to_talk(app, accelerator),
+        MUTE_TOGGLE => bind_mute_toggle(app, accelerator),
+        other => Err(format!("unknown shortcut action: {other}").into()),
+    }
+}
+
+/// Re-register persisted shortcuts. Called from setup after `init`; does
+/// nothing if the plugin didn't load.
+/// An entry that no longer binds (bad accelerator, key now taken) is skipped
+/// and reported via `shortcuts://restore_failed`; it stays in the store so a
+/// transient conflict doesn't lose the user's choice.
+pub fn restore(app: &AppHandle) {
+    if !available() {
+        return;
+    }
+    if backend() == ShortcutBackend::Xwayland {
+        log::info!("Wayland session: global shortcuts go through XWayland and may only fire while focused");
+    }
+    for (action, accelerator) in load_store(app) {
+        if let Err(error) = bind(app, &action, &accelerator) {
+            log::warn!("failed to restore {action} shortcut \"{accelerator}\": {error}");
+            let _ = app.emit(
+                "shortcuts://restore_failed",
+                RestoreFailed { action, accelerator, error: error.to_string() },
+            );
+        }
+    }
+}
+
+/// Saved accelerators keyed by action.
+#[tauri::command]
+pub fn list_shortcuts(app: AppHandle) -> HashMap<String, String> {
+    load_store(&app)
+}
+
+/// Unbind everything and forget the saved accelerators.
+#[tauri::command]
+pub fn reset_shortcuts(app: AppHandle) -> Result<(), CommandError> {
+    if available() {
+        for action in ACTIONS {
+            unbind(&app, action)?;
+        }
+    }
+    match store_path(&app) {
+        Some(path) if path.exists() => std::fs::remove_file(path).map_err(|e| e.to_string().into()),
+        _ => Ok(()),
+    }
+}
+
+/// A portable set of bindings, as written by `export_keybindings`.
+#[derive(Serialize, Deserialize)]
+struct KeybindingProfile {
+    version: u32,
+    /// Accelerator per action.
+    bindings: HashMap<String, String>,
+}
+
+#[derive(Serialize)]
+pub struct ImportReport {
+    /// Actions now bound as the profile says.
+    applied: Vec<String>,
+    /// Entries that didn't bind; those actions keep what they had.
+    failed: Vec<RestoreFailed>,
+}
+
+/// The saved bindings as a JSON profile.
+#[tauri::command]
+pub fn export_keybindings(app: AppHandle) -> Result<String, CommandError> {
+    let profile = KeybindingProfile {
+        version: PROFILE_VERSION,
+        bindings: load_store(&app),
+    };
+    Ok(serde_json::to_string_pretty(&profile).map_err(|e| e.to_string())?)
+}
+
+/// Check a profile entry without binding anything: a known action, an
+/// accelerator that parses, and no other action in the profile on the same
+/// keys.
+fn validate_entry(action: &str, accelerator: &str, profile: &HashMap<String, String>) -> Result<(), CommandError> {
+    if !ACTIONS.contains(&action) {
+        return Err(CommandError::invalid_input(format!("unknown shortcut action: {action}")));
+    }
+    let shortcut = parse_accelerator(accelerator)?;
+    let taken = profile
+        .iter()
+        .find(|(other, a)| other.as_str() != action && parse_accelerator(a).is_ok_and(|s| s == shortcut));
+    match taken {
+        Some((other, _)) => Err(taken_by(other)),
+        None => Ok(()),
+    }
+}
+
+/// Apply a profile from `export_keybindings`. The profile replaces the
+/// current set: actions it leaves out are unbound. Every entry is validated
+/// before anything changes; one that fails to validate or bind (e.g. the
+/// keys are taken by another app) is reported and its action keeps its
+/// old binding.
+#[tauri::command]
+pub fn import_keybindings(app: AppHandle, profile: String) -> Result<ImportReport, CommandError> {
+    let profile: KeybindingProfile = serde_json::from_str(&profile)
+        .map_err(|e| CommandError::invalid_input(format!("not a keybinding profile: {e}")))?;
+    if profile.version > PROFILE_VERSION {
+        return Err(CommandError::invalid_input(format!(
+            "keybinding profile version {} is newer than this app supports",
+            profile.version
+        )));
+    }
+    let mut failed = Vec::new();
+    let mut valid = Vec::new();
+    for (action, accelerator) in &profile.bindings {
+        match validate_entry(action, accelerator, &profile.bindings) {
+            Ok(()) => valid.push((action.clone(), accelerator.clone())),
+            Err(error) => failed.push(RestoreFailed {
+                action: action.clone(),
+                accelerator: accelerator.clone(),
+                error: error.to_string(),
+            }),
+        }
+    }
+
+    // Everything off first, so swapping two actions' keys doesn't conflict
+    // with itself halfway.
+    let previous = load_store(&app);
+    for action in ACTIONS {
+        unbind(&app, action)?;
+    }
+    let mut applied = Vec::new();
+    for (action, accelerator) in valid {
+        match bind(&app, &action, &accelerator) {
+            Ok(()) => {
+                save_accelerator(&app, &action, Some(&accelerator))?;
+                applied.push(action);
+            }
+            Err(error) => failed.push(RestoreFailed { action, accelerator, error: error.to_string() }),
+        }
+    }
+    for action in ACTIONS {
+        if applied.iter().any(|a| a == action) {
+            continue;
+        }
+        let in_profile = profile.bindings.contains_key(*action);
+        match previous.get(*action) {
+            // Failed entries fall back to the old keys, best effort.
+            Some(old) if in_profile => {
+                if let Err(e) = bind(&app, action, old) {
+                    log::warn!("failed to rebind {action} to \"{old}\" after import: {e}");
+                }
+            }
+            _ => save_accelerator(&app, action, None)?,
+        }
+    }
+    Ok(ImportReport { applied, failed })
+}
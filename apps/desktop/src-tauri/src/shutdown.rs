@@ -0,0 +1,204 @@
+// OS shutdown and logout. Window close and tray Quit run the full teardown,
+// but when the session ends the process can be killed before any of that is
+// scheduled, so these paths send the leave beacon synchronously instead.
+// Either way an update deferred to quit is installed last.
+//
+// Background loops are started through `spawn`, which ties them to one
+// cancellation token. Both teardown paths cancel it once the leave beacon is
+// out, so nothing is still polling or reconnecting while the runtime exits.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager};
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+
+use crate::{pipe, presence, CLOSE_BEACON_TIMEOUT};
+
+/// Upper bound on how long teardown waits for background tasks to wind down.
+const TASKS_TIMEOUT: Duration = Duration::from_millis(500);
+
+#[derive(Default)]
+pub struct Tasks {
+    cancel: CancellationToken,
+    tracker: TaskTracker,
+}
+
+impl Tasks {
+    /// `task`, tracked and cut short by `stop`.
+    fn track<Fut>(&self, task: Fut) -> impl Future<Output = ()> + Send + 'static
+    where
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let cancel = self.cancel.clone();
+        self.tracker.track_future(async move {
+            tokio::select! {
+                _ = cancel.cancelled() => {}
+                _ = task => {}
+            }
+        })
+    }
+
+    /// Cancel every tracked task and wait up to `TASKS_TIMEOUT` for them;
+    /// false if some were still running.
+    async fn stop(&self) -> bool {
+        self.cancel.cancel();
+        self.tracker.close();
+        tokio::time::timeout(TASKS_TIMEOUT, self.tracker.wait()).await.is_ok()
+    }
+}
+
+/// Spawn a background task that ends at shutdown. The app handle is passed
+/// in so callers don't need a clone of their own. Aborting the returned
+/// handle still works as before; a task spawned after shutdown never runs.
+pub fn spawn<F, Fut>(app: &AppHandle, task: F) -> JoinHandle<()>
+where
+    F: FnOnce(AppHandle) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let tasks = app.state::<Tasks>();
+    tauri::async_runtime::spawn(tasks.track(task(app.clone())))
+}
+
+/// Cancel every task from `spawn` and wait (bounded) for them to finish.
+/// Called after the leave beacon, which must not be cut short.
+pub async fn stop_tasks(app: &AppHandle) {
+    let tasks = app.state::<Tasks>();
+    if !tasks.stop().await {
+        log::warn!("{} background tasks still running at exit", tasks.tracker.len());
+    }
+}
+
+/// How many tasks from `spawn` are still running.
+pub fn running_tasks(app: &AppHandle) -> usize {
+    app.state::<Tasks>().tracker.len()
+}
+
+/// Set once teardown (full or last-chance) has begun, so only one runs.
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Claim the shutdown; false if another path already has.
+pub fn begin() -> bool {
+    !STARTED.swap(true, Ordering::AcqRel)
+}
+
+/// Whether a teardown has begun, i.e. the app is on its way out.
+pub fn started() -> bool {
+    STARTED.load(Ordering::Acquire)
+}
+
+/// Release the pipe lock, send the leave beacon and install any update
+/// deferred to quit, blocking until done or timed out. For paths with no
+/// chance to await anything.
+pub fn last_chance(app: &AppHandle) {
+    if !begin() {
+        return;
+    }
+    presence::stop_heartbeat(app);
+    tauri::async_runtime::block_on(async {
+        pipe::force_stop(app).await;
+        let _ = tokio::time::timeout(CLOSE_BEACON_TIMEOUT, presence::leave(app)).await;
+        stop_tasks(app).await;
+    });
+    crate::install_deferred_update(app);
+}
+
+/// Hook the platform session-end signal. Called from setup.
+pub fn init(app: &AppHandle) {
+    #[cfg(unix)]
+    {
+        // systemd/launchd send SIGTERM at shutdown and logout; SIGHUP when the
+        // session goes away underneath us.
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            use tokio::signal::unix::{signal, SignalKind};
+            let (Ok(mut term), Ok(mut hup)) =
+                (signal(SignalKind::terminate()), signal(SignalKind::hangup()))
+            else {
+                log::warn!("failed to install shutdown signal handlers");
+                return;
+            };
+            tokio::select! {
+                _ = term.recv() => {}
+                _ = hup.recv() => {}
+            }
+            log::info!("session ending, leaving");
+            crate::quit(&app).await;
+        });
+    }
+    #[cfg(windows)]
+    windows::subclass_main_window(app);
+}
+
+#[cfg(windows)]
+mod windows {
+    use tauri::{AppHandle, Manager};
+    use windows_sys::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+    use windows_sys::Win32::UI::Shell::{DefSubclassProc, SetWindowSubclass};
+    use windows_sys::Win32::UI::WindowsAndMessaging::WM_ENDSESSION;
+
+    const SUBCLASS_ID: usize = 1;
+
+    /// Windows only tells top-level windows about session end, so hook the
+    /// main window's message loop. The handle is leaked on purpose: it has to
+    /// outlive the window, and there is exactly one.
+    pub fn subclass_main_window(app: &AppHandle) {
+        let Some(window) = app.get_webview_window("main") else { return };
+        let Ok(hwnd) = window.hwnd() else { return };
+        let data = Box::into_raw(Box::new(app.clone())) as usize;
+        unsafe {
+            SetWindowSubclass(hwnd.0 as HWND, Some(proc), SUBCLASS_ID, data);
+        }
+    }
+
+    unsafe extern "system" fn proc(
+        hwnd: HWND,
+        msg: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+        _id: usize,
+        data: usize,
+    ) -> LRESULT {
+        // WM_QUERYENDSESSION can still be vetoed by another app, so wait for
+        // WM_ENDSESSION (wparam != 0 means the session really is ending).
+        // Windows kills us shortly after this returns.
+        if msg == WM_ENDSESSION && wparam != 0 {
+            let app = &*(data as *const AppHandle);
+            super::last_chance(app);
+        }
+        DefSubclassProc(hwnd, msg, wparam, lparam)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[tokio::test]
+    async fn stop_ends_tasks_within_timeout() {
+        let tasks = Tasks::default();
+        let handles: Vec<_> = (0..3)
+            .map(|_| tokio::spawn(tasks.track(std::future::pending())))
+            .collect();
+        tokio::spawn(tasks.track(tokio::time::sleep(Duration::from_secs(3600))));
+
+        let started = Instant::now();
+        assert!(tasks.stop().await);
+        assert!(started.elapsed() < TASKS_TIMEOUT);
+        assert!(tasks.tracker.is_empty());
+        for handle in handles {
+            handle.await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn finished_tasks_leave_nothing_to_wait_for() {
+        let tasks = Tasks::default();
+        tokio::spawn(tasks.track(async {})).await.unwrap();
+        assert!(tasks.tracker.is_empty());
+        assert!(tasks.stop().await);
+    }
+}
@@ -0,0 +1,300 @@
+// Notification sounds, picked separately for mentions and other messages.
+// The default is the platform toast sound; choosing one of the bundled
+// sounds or a WAV file of the user's own plays it here instead, through cpal
+// on the preferred output device, and the toast goes out silent.
+
+use cpal::traits::{DeviceTrait, StreamTrait};
+use cpal::{FromSample, SampleFormat, SizedSample};
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::AppHandle;
+
+use crate::error::CommandError;
+use crate::notify::Suppressed;
+use crate::prefs;
+
+pub(crate) const MESSAGE_PREF: &str = "sound_message";
+pub(crate) const MENTION_PREF: &str = "sound_mention";
+/// Leave the sound to the OS toast.
+const SYSTEM: &str = "system";
+/// No sound at all.
+const NONE: &str = "none";
+
+const BUILTIN: &[(&str, &[u8])] = &[
+    ("chime", include_bytes!("../sounds/chime.wav")),
+    ("ping", include_bytes!("../sounds/ping.wav")),
+    ("pop", include_bytes!("../sounds/pop.wav")),
+];
+
+/// Larger files are refused; a notification sound is a second or two.
+const MAX_FILE_BYTES: u64 = 5 * 1024 * 1024;
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SoundKind {
+    #[default]
+    Message,
+    Mention,
+}
+
+impl SoundKind {
+    fn pref(self) -> &'static str {
+        match self {
+            SoundKind::Message => MESSAGE_PREF,
+            SoundKind::Mention => MENTION_PREF,
+        }
+    }
+}
+
+/// What the notification path should do about sound for `kind`.
+pub enum Choice {
+    /// Ask the toast for the platform sound.
+    System,
+    /// Play these samples ourselves.
+    Play(Pcm),
+    Silent,
+}
+
+/// Decoded audio, interleaved.
+pub struct Pcm {
+    samples: Vec<f32>,
+    channels: u16,
+    rate: u32,
+}
+
+fn read_u16(b: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(b.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn read_u32(b: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(b.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// Decode a RIFF/WAVE file: 8/16/24/32-bit integer PCM or 32-bit float,
+/// any channel count and rate.
+fn decode_wav(bytes: &[u8]) -> Result<Pcm, String> {
+    if bytes.get(..4) != Some(b"RIFF") || bytes.get(8..12) != Some(b"WAVE") {
+        return Err("not a WAV file".into());
+    }
+    let mut format = None;
+    let mut data = None;
+    let mut at = 12;
+    while let (Some(id), Some(len)) = (bytes.get(at..at + 4), read_u32(bytes, at + 4)) {
+        let body = at + 8;
+        let end = body.saturating_add(len as usize).min(bytes.len());
+        match id {
+            b"fmt " => {
+                let tag = read_u16(bytes, body).ok_or("truncated fmt chunk")?;
+                let channels = read_u16(bytes, body + 2).ok_or("truncated fmt chunk")?;
+                let rate = read_u32(bytes, body + 4).ok_or("truncated fmt chunk")?;
+                let bits = read_u16(bytes, body + 14).ok_or("truncated fmt chunk")?;
+                // WAVE_FORMAT_EXTENSIBLE keeps the real tag in its sub-format.
+                let tag = match tag {
+                    0xfffe => read_u16(bytes, body + 24).ok_or("truncated fmt chunk")?,
+                    tag => tag,
+                };
+                format = Some((tag, channels, rate, bits));
+            }
+            b"data" => data = Some(&bytes[body..end]),
+            _ => {}
+        }
+        // Chunks are padded to an even length.
+        at = body.saturating_add(len as usize + (len as usize & 1));
+    }
+    let (tag, channels, rate, bits) = format.ok_or("no fmt chunk")?;
+    let data = data.ok_or("no data chunk")?;
+    if channels == 0 || rate == 0 {
+        return Err("invalid WAV format".into());
+    }
+    let samples: Vec<f32> = match (tag, bits) {
+        (1, 8) => data.iter().map(|&b| (f32::from(b) - 128.0) / 128.0).collect(),
+        (1, 16) => data
+            .chunks_exact(2)
+            .map(|c| f32::from(i16::from_le_bytes([c[0], c[1]])) / 32768.0)
+            .collect(),
+        (1, 24) => data
+            .chunks_exact(3)
+            .map(|c| (i32::from_le_bytes([0, c[0], c[1], c[2]]) >> 8) as f32 / 8_388_608.0)
+            .collect(),
+        (1, 32) => data
+            .chunks_exact(4)
+            .map(|c| i32::from_le_bytes([c[0], c[1], c[2], c[3]]) as f32 / 2_147_483_648.0)
+            .collect(),
+        (3, 32) => data
+            .chunks_exact(4)
+            .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+            .collect(),
+        _ => return Err(format!("unsupported WAV encoding (format {tag}, {bits}-bit)")),
+    };
+    if samples.is_empty() {
+        return Err("WAV file has no audio".into());
+    }
+    Ok(Pcm { samples, channels, rate })
+}
+
+fn load_file(path: &Path) -> Result<Pcm, String> {
+    let size = std::fs::metadata(path).map_err(|e| e.to_string())?.len();
+    if size > MAX_FILE_BYTES {
+        return Err("sound file is too large".into());
+    }
+    decode_wav(&std::fs::read(path).map_err(|e| e.to_string())?)
+}
+
+fn builtin(name: &str) -> Option<&'static [u8]> {
+    BUILTIN.iter().find(|(n, _)| *n == name).map(|(_, bytes)| *bytes)
+}
+
+/// The saved choice for `kind`. A user file that has since gone missing or
+/// broken falls back to the system sound.
+pub fn choice(app: &AppHandle, kind: SoundKind) -> Choice {
+    let saved = prefs::get::<String>(app, kind.pref());
+    match saved.as_deref().unwrap_or(SYSTEM) {
+        SYSTEM => Choice::System,
+        NONE => Choice::Silent,
+        name => {
+            let pcm = match builtin(name) {
+                Some(bytes) => decode_wav(bytes),
+                None => load_file(Path::new(name)),
+            };
+            match pcm {
+                Ok(pcm) => Choice::Play(pcm),
+                Err(e) => {
+                    log::warn!("notification sound {name} unusable, using the system sound: {e}");
+                    Choice::System
+                }
+            }
+        }
+    }
+}
+
+fn build<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    pcm: Arc<Pcm>,
+    done: Arc<AtomicBool>,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let out_channels = usize::from(config.channels);
+    let src_channels = usize::from(pcm.channels);
+    let frames = pcm.samples.len() / src_channels;
+    // Source frames per output frame; linear interpolation is plenty here.
+    let step = f64::from(pcm.rate) / f64::from(config.sample_rate.0);
+    let mut pos = 0.0f64;
+    device.build_output_stream(
+        config,
+        move |out: &mut [T], _| {
+            for frame in out.chunks_mut(out_channels) {
+                let i = pos as usize;
+                if i + 1 >= frames {
+                    frame.fill(T::from_sample(0.0));
+                    done.store(true, Ordering::Relaxed);
+                    continue;
+                }
+                let t = (pos - i as f64) as f32;
+                for (c, sample) in frame.iter_mut().enumerate() {
+                    let sc = c.min(src_channels - 1);
+                    let a = pcm.samples[i * src_channels + sc];
+                    let b = pcm.samples[(i + 1) * src_channels + sc];
+                    *sample = T::from_sample(a + (b - a) * t);
+                }
+                pos += step;
+            }
+        },
+        |e| log::warn!("notification sound output failed: {e}"),
+        None,
+    )
+}
+
+/// Play on the preferred output device without blocking the caller.
+pub fn play(app: &AppHandle, pcm: Pcm) {
+    let app = app.clone();
+    // cpal streams aren't `Send` everywhere; this thread owns it until the
+    // sound has finished.
+    std::thread::spawn(move || {
+        let result = (|| {
+            let device = crate::audio::output_device(&app).ok_or("no output device")?;
+            let supported = device.default_output_config().map_err(|e| e.to_string())?;
+            let config = supported.config();
+            let length = Duration::from_secs_f64(
+                pcm.samples.len() as f64 / f64::from(pcm.channels) / f64::from(pcm.rate),
+            );
+            let (pcm, done) = (Arc::new(pcm), Arc::new(AtomicBool::new(false)));
+            let stream = match supported.sample_format() {
+                SampleFormat::F32 => build::<f32>(&device, &config, pcm, done.clone()),
+                SampleFormat::I16 => build::<i16>(&device, &config, pcm, done.clone()),
+                SampleFormat::U16 => build::<u16>(&device, &config, pcm, done.clone()),
+                SampleFormat::I32 => build::<i32>(&device, &config, pcm, done.clone()),
+                f => return Err(format!("unsupported sample format {f}")),
+            }
+            .map_err(|e| e.to_string())?;
+            stream.play().map_err(|e| e.to_string())?;
+            // A little past the end so the device drains; bounded in case
+            // the callback stops being called.
+            std::thread::sleep(length);
+            let deadline = std::time::Instant::now() + Duration::from_secs(1);
+            while !done.load(Ordering::Relaxed) && std::time::Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            std::thread::sleep(Duration::from_millis(100));
+            Ok::<_, String>(())
+        })();
+        if let Err(e) = result {
+            log::warn!("failed to play notification sound: {e}");
+        }
+    });
+}
+
+/// Pick the sound for `kind`: `"system"` (the default), `"none"`, a bundled
+/// sound (`"chime"`, `"ping"`, `"pop"`), or the path of a WAV file, which is
+/// checked now so a bad pick fails here rather than at the next message.
+#[tauri::command]
+pub fn set_notification_sound(app: AppHandle, kind: SoundKind, sound: String) -> Result<(), CommandError> {
+    if ![SYSTEM, NONE].contains(&sound.as_str()) && builtin(&sound).is_none() {
+        let path = Path::new(&sound);
+        if !path.is_absolute() {
+            return Err(CommandError::invalid_input(format!("unknown sound: {sound}")));
+        }
+        load_file(path).map_err(CommandError::invalid_input)?;
+    }
+    prefs::set(&app, kind.pref(), &sound)
+}
+
+/// The sound for `kind` on its own, for messages the webview handles without
+/// a toast and for previews. Held back under DND and during a call like a
+/// toast's would be. The system choice has no sound outside a toast, so
+/// that plays the first bundled one.
+#[tauri::command]
+pub fn play_notification_sound(app: AppHandle, kind: SoundKind) -> Option<Suppressed> {
+    if crate::notify::dnd_enabled(&app) {
+        return Some(Suppressed::Dnd);
+    }
+    if crate::notify::in_call(&app) {
+        return Some(Suppressed::InCall);
+    }
+    let pcm = match choice(&app, kind) {
+        Choice::Play(pcm) => pcm,
+        Choice::Silent => return None,
+        Choice::System => match decode_wav(BUILTIN[0].1) {
+            Ok(pcm) => pcm,
+            Err(e) => {
+                log::warn!("bundled sound unusable: {e}");
+                return None;
+            }
+        },
+    };
+    play(&app, pcm);
+    None
+}
+
+/// Names accepted by `set_notification_sound` besides file paths.
+#[tauri::command]
+pub fn list_notification_sounds() -> Vec<&'static str> {
+    let mut names = vec![SYSTEM, NONE];
+    names.extend(BUILTIN.iter().map(|(n, _)| *n));
+    names
+}
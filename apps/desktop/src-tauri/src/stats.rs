@@ -0,0 +1,121 @@
+// Runtime snapshot for "it gets slow after a few hours" reports: memory,
+// uptime, background tasks, windows and connection state, sampled every
+// minute into a small ring so a leak shows as a trend rather than one
+// number. Memory is this process only; the webview renders in processes of
+// its own, so a flat line here points at the webview side.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::Ordering;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+const SAMPLE_EVERY: Duration = Duration::from_secs(60);
+/// Two hours of samples.
+const SAMPLE_CAP: usize = 120;
+
+static STARTED: OnceLock<Instant> = OnceLock::new();
+static SAMPLES: Mutex<VecDeque<RuntimeStats>> = Mutex::new(VecDeque::new());
+
+#[derive(Serialize, Clone)]
+pub struct RuntimeStats {
+    /// Unix ms when taken.
+    at: u64,
+    uptime_secs: u64,
+    /// Resident set size in bytes; `None` where the OS won't say.
+    rss_bytes: Option<u64>,
+    /// Background loops still running (see `shutdown::spawn`).
+    background_tasks: usize,
+    windows: usize,
+    /// The event stream: `connected`, `connecting` or `closed`.
+    event_stream: &'static str,
+    online: bool,
+    signed_in: bool,
+}
+
+#[cfg(windows)]
+fn rss_bytes() -> Option<u64> {
+    use windows_sys::Win32::System::ProcessStatus::{GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS};
+    use windows_sys::Win32::System::Threading::GetCurrentProcess;
+
+    // SAFETY: plain-old-data out-param sized by `cb`, only read on success.
+    let mut counters: PROCESS_MEMORY_COUNTERS = unsafe { std::mem::zeroed() };
+    counters.cb = std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32;
+    let ok = unsafe { GetProcessMemoryInfo(GetCurrentProcess(), &mut counters, counters.cb) };
+    (ok != 0).then_some(counters.WorkingSetSize as u64)
+}
+
+#[cfg(target_os = "linux")]
+fn rss_bytes() -> Option<u64> {
+    // Second field of statm is resident pages.
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+    // SAFETY: sysconf has no preconditions.
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    Some(pages * u64::try_from(page_size).ok()?)
+}
+
+#[cfg(target_os = "macos")]
+fn rss_bytes() -> Option<u64> {
+    // In KiB.
+    let output = std::process::Command::new("ps")
+        .args(["-o", "rss=", "-p", &std::process::id().to_string()])
+        .output()
+        .ok()?;
+    let kib: u64 = String::from_utf8_lossy(&output.stdout).trim().parse().ok()?;
+    Some(kib * 1024)
+}
+
+#[cfg(not(any(windows, target_os = "linux", target_os = "macos")))]
+fn rss_bytes() -> Option<u64> {
+    None
+}
+
+fn snapshot(app: &AppHandle) -> RuntimeStats {
+    let at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64);
+    RuntimeStats {
+        at,
+        uptime_secs: STARTED.get().map_or(0, |s| s.elapsed().as_secs()),
+        rss_bytes: rss_bytes(),
+        background_tasks: crate::shutdown::running_tasks(app),
+        windows: app.webview_windows().len(),
+        event_stream: crate::stream::status(app),
+        online: app.state::<crate::connectivity::Connectivity>().online.load(Ordering::Relaxed),
+        signed_in: app.state::<crate::presence::AuthState>().0.lock().unwrap().is_some(),
+    }
+}
+
+/// Note the start time and begin sampling. Called from setup.
+pub fn start(app: &AppHandle) {
+    let _ = STARTED.set(Instant::now());
+    crate::shutdown::spawn(app, |app| async move {
+        loop {
+            tokio::time::sleep(SAMPLE_EVERY).await;
+            let stats = snapshot(&app);
+            let mut samples = SAMPLES.lock().unwrap();
+            if samples.len() == SAMPLE_CAP {
+                samples.pop_front();
+            }
+            samples.push_back(stats);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_runtime_stats(app: AppHandle) -> RuntimeStats {
+    snapshot(&app)
+}
+
+/// The periodic samples, oldest first. `flush` empties the buffer.
+#[tauri::command]
+pub fn export_runtime_stats(flush: Option<bool>) -> Vec<RuntimeStats> {
+    let mut samples = SAMPLES.lock().unwrap();
+    if flush == Some(true) {
+        samples.drain(..).collect()
+    } else {
+        samples.iter().cloned().collect()
+    }
+}
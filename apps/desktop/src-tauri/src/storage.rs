@@ -0,0 +1,100 @@
+// On-disk footprint for the settings page: reveal the data directory, report
+// how much space the app is using, and clear the caches we can rebuild.
+// Preferences live in the config dir and tokens in the OS keychain, so
+// neither is touched by `clear_cache`.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_opener::OpenerExt;
+
+use crate::error::CommandError;
+
+/// Subfolders of the cache dir holding update downloads (see download.rs).
+const CACHE_SUBDIRS: &[&str] = &["updates", "update-base"];
+
+#[derive(Serialize)]
+pub struct DataSize {
+    /// Everything under the app's config, data and cache dirs.
+    total: u64,
+    /// The part `clear_cache` would free.
+    cache: u64,
+}
+
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else { return 0 };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(t) if t.is_dir() => dir_size(&entry.path()),
+            Ok(t) if t.is_file() => entry.metadata().map_or(0, |m| m.len()),
+            // Symlinks aren't followed, so nothing outside is counted.
+            _ => 0,
+        })
+        .sum()
+}
+
+/// The app's directories, without duplicates or dirs nested in another
+/// (several coincide on Windows and macOS).
+fn app_dirs(app: &AppHandle) -> Vec<PathBuf> {
+    let path = app.path();
+    let mut dirs: Vec<PathBuf> = [
+        path.app_config_dir(),
+        path.app_data_dir(),
+        path.app_local_data_dir(),
+        path.app_cache_dir(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    dirs.sort();
+    dirs.dedup_by(|d, kept| d.starts_with(kept));
+    dirs
+}
+
+fn cache_dirs(app: &AppHandle) -> Result<Vec<PathBuf>, CommandError> {
+    let root = app.path().app_cache_dir()?;
+    Ok(CACHE_SUBDIRS.iter().map(|d| root.join(d)).collect())
+}
+
+#[tauri::command]
+pub fn open_data_dir(app: AppHandle) -> Result<(), CommandError> {
+    let dir = app.path().app_data_dir()?;
+    std::fs::create_dir_all(&dir)?;
+    app.opener()
+        .open_path(dir.to_string_lossy(), None::<&str>)
+        .map_err(CommandError::io)
+}
+
+#[tauri::command]
+pub async fn get_data_dir_size(app: AppHandle) -> Result<DataSize, CommandError> {
+    let dirs = app_dirs(&app);
+    let caches = cache_dirs(&app)?;
+    // Walking the webview's profile can take a while; keep it off the
+    // async runtime.
+    tauri::async_runtime::spawn_blocking(move || DataSize {
+        total: dirs.iter().map(|d| dir_size(d)).sum(),
+        cache: caches.iter().map(|d| dir_size(d)).sum(),
+    })
+    .await
+    .map_err(CommandError::from)
+}
+
+/// Delete downloaded update bundles. Returns the bytes freed.
+#[tauri::command]
+pub async fn clear_cache(app: AppHandle) -> Result<u64, CommandError> {
+    let caches = cache_dirs(&app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut freed = 0;
+        for dir in caches {
+            let size = dir_size(&dir);
+            match std::fs::remove_dir_all(&dir) {
+                Ok(()) => freed += size,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(CommandError::io(format!("{}: {e}", dir.display()))),
+            }
+        }
+        Ok(freed)
+    })
+    .await?
+}
@@ -0,0 +1,299 @@
+// Native bridge for the server's SSE stream (`/api/events`). A webview
+// `EventSource` gets throttled or dropped while the window is hidden in the
+// tray; this keeps one connection open from Rust, reconnects with backoff,
+// and re-emits every event to the webview as `stream://event`.
+
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_http::reqwest::{header, StatusCode};
+
+use crate::error::CommandError;
+use crate::net::Traced;
+use crate::presence::AuthState;
+
+const BACKOFF_MIN: Duration = Duration::from_secs(1);
+const BACKOFF_MAX: Duration = Duration::from_secs(30);
+/// The server sends a keepalive comment every 30s; anything quieter than
+/// this is a dead connection.
+const READ_TIMEOUT: Duration = Duration::from_secs(75);
+
+#[derive(Default)]
+pub struct StreamState(Mutex<Slot>);
+
+#[derive(Default)]
+struct Slot {
+    /// Bumped by every `start` and `stop`. A task only touches the slot
+    /// while this is still the one it was started with, so one that's been
+    /// replaced can't clear its successor's handle or status.
+    generation: u64,
+    task: Option<JoinHandle<()>>,
+    /// Between `stream://connected` and the connection going away.
+    connected: bool,
+}
+
+/// Update the slot for the task started as `generation`, if it's still
+/// current.
+fn with_slot(app: &AppHandle, generation: u64, f: impl FnOnce(&mut Slot)) {
+    let state = app.state::<StreamState>();
+    let mut slot = state.0.lock().unwrap();
+    if slot.generation == generation {
+        f(&mut slot);
+    }
+}
+
+/// One dispatched SSE event, shaped like a `MessageEvent`: `data` is passed
+/// through as sent, multi-line data joined with `\n`.
+#[derive(Serialize, Clone, Default)]
+pub struct StreamEvent {
+    event: String,
+    data: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+}
+
+/// Incremental parser for the `text/event-stream` format.
+#[derive(Default)]
+struct Parser {
+    /// Bytes after the last newline. Kept undecoded so a UTF-8 character
+    /// split across chunks isn't mangled.
+    buf: Vec<u8>,
+    event: Option<String>,
+    data: Vec<String>,
+    last_id: Option<String>,
+}
+
+impl Parser {
+    /// Feed a chunk and return the events it completed.
+    fn push(&mut self, chunk: &[u8]) -> Vec<StreamEvent> {
+        self.buf.extend_from_slice(chunk);
+        let mut out = Vec::new();
+        while let Some(end) = self.buf.iter().position(|&b| b == b'\n') {
+            let bytes: Vec<u8> = self.buf.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&bytes);
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line.is_empty() {
+                if !self.data.is_empty() {
+                    out.push(StreamEvent {
+                        event: self.event.take().unwrap_or_else(|| "message".into()),
+                        data: self.data.join("\n"),
+                        id: self.last_id.clone(),
+                    });
+                }
+                self.event = None;
+                self.data.clear();
+                continue;
+            }
+            if line.starts_with(':') {
+                continue;
+            }
+            let (field, value) = line.split_once(':').unwrap_or((line, ""));
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match field {
+                "event" => self.event = Some(value.to_string()),
+                "data" => self.data.push(value.to_string()),
+                "id" => self.last_id = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        out
+    }
+}
+
+enum Ended {
+    /// Dropped after it was up; reconnect promptly.
+    Disconnected(String),
+    /// Never got going; back off before retrying.
+    Failed(String),
+    /// Signed out or the token was rejected; don't reconnect.
+    Stop,
+}
+
+async fn run_once(app: &AppHandle, generation: u64, parser: &mut Parser) -> Ended {
+    let Some(info) = app.state::<AuthState>().0.lock().unwrap().clone() else {
+        return Ended::Stop;
+    };
+    let client = match crate::net::client() {
+        Ok(c) => c,
+        Err(e) => return Ended::Failed(e),
+    };
+    let mut request = client
+        .get(format!("{}/api/events", info.server_url))
+        .bearer_auth(&info.token)
+        .header(header::ACCEPT, "text/event-stream");
+    if let Some(id) = &parser.last_id {
+        request = request.header("Last-Event-ID", id);
+    }
+    let mut response = match request.send_traced().await {
+        Ok(r) => r,
+        Err(e) => return Ended::Failed(e.to_string()),
+    };
+    match response.status() {
+        s if s.is_success() => {}
+        // Reconnect straight away with a refreshed token, if there is one.
+        StatusCode::UNAUTHORIZED => {
+            return match crate::auth::refresh_after_401(&info).await {
+                Some(_) => Ended::Disconnected("token refreshed".into()),
+                None => Ended::Stop,
+            };
+        }
+        s => {
+            crate::net::record_error("GET", response).await;
+            return Ended::Failed(format!("event stream refused: {s}"));
+        }
+    }
+    with_slot(app, generation, |slot| slot.connected = true);
+    let _ = app.emit("stream://connected", ());
+    // Anything half-read belongs to the connection that just went away.
+    parser.buf.clear();
+    parser.event = None;
+    parser.data.clear();
+    loop {
+        match tokio::time::timeout(READ_TIMEOUT, response.chunk()).await {
+            Ok(Ok(Some(chunk))) => {
+                for event in parser.push(&chunk) {
+                    let _ = app.emit("stream://event", event);
+                }
+            }
+            Ok(Ok(None)) => return Ended::Disconnected("server closed the stream".into()),
+            Ok(Err(e)) => return Ended::Disconnected(e.to_string()),
+            Err(_) => return Ended::Disconnected("no keepalive from server".into()),
+        }
+    }
+}
+
+/// Open (or reopen) the stream. Any previous connection is dropped first so
+/// there's only ever one. Not while the network is paused or the server
+/// needs a newer client.
+pub fn start(app: &AppHandle) {
+    if crate::net::paused() || crate::capabilities::outdated(app) {
+        return;
+    }
+    let state = app.state::<StreamState>();
+    // Held until the new handle is in, so nothing sees the slot in between.
+    let mut slot = state.0.lock().unwrap();
+    if let Some(old) = slot.task.take() {
+        old.abort();
+    }
+    slot.connected = false;
+    slot.generation += 1;
+    let generation = slot.generation;
+    let task = crate::shutdown::spawn(app, move |app| async move {
+        let mut parser = Parser::default();
+        let mut backoff = BACKOFF_MIN;
+        loop {
+            let ended = run_once(&app, generation, &mut parser).await;
+            with_slot(&app, generation, |slot| slot.connected = false);
+            match ended {
+                Ended::Stop => break,
+                Ended::Disconnected(e) => {
+                    log::info!("event stream dropped: {e}");
+                    let _ = app.emit("stream://disconnected", ());
+                    backoff = BACKOFF_MIN;
+                }
+                Ended::Failed(e) => log::warn!("event stream connect failed: {e}"),
+            }
+            // Back off further while the window is hidden to save battery.
+            let (min, max) = if crate::throttle::engaged() {
+                (crate::throttle::STREAM_BACKOFF_MIN, crate::throttle::STREAM_BACKOFF_MAX)
+            } else {
+                (BACKOFF_MIN, BACKOFF_MAX)
+            };
+            tokio::time::sleep(backoff.max(min)).await;
+            backoff = (backoff.max(min) * 2).min(max);
+        }
+        // Only announced by the current task; a replaced one goes quietly.
+        let mut current = false;
+        with_slot(&app, generation, |slot| {
+            slot.task = None;
+            current = true;
+        });
+        if current {
+            let _ = app.emit("stream://closed", ());
+        }
+    });
+    slot.task = Some(task);
+}
+
+pub fn stop(app: &AppHandle) {
+    let task = {
+        let state = app.state::<StreamState>();
+        let mut slot = state.0.lock().unwrap();
+        slot.generation += 1;
+        slot.connected = false;
+        slot.task.take()
+    };
+    if let Some(task) = task {
+        task.abort();
+        let _ = app.emit("stream://closed", ());
+    }
+}
+
+/// Reconnect with the current credentials if the stream is open, e.g. after
+/// switching servers.
+pub fn restart_if_running(app: &AppHandle) {
+    let running = app.state::<StreamState>().0.lock().unwrap().task.is_some();
+    if running {
+        start(app);
+    }
+}
+
+/// `"connected"`, `"connecting"` (open but between connections) or
+/// `"closed"`.
+pub fn status(app: &AppHandle) -> &'static str {
+    let state = app.state::<StreamState>();
+    let slot = state.0.lock().unwrap();
+    if slot.connected {
+        "connected"
+    } else if slot.task.is_some() {
+        "connecting"
+    } else {
+        "closed"
+    }
+}
+
+#[tauri::command]
+pub fn start_event_stream(app: AppHandle) -> Result<(), CommandError> {
+    let auth = app.state::<AuthState>().0.lock().unwrap().clone();
+    let info = auth.ok_or_else(|| CommandError::auth("Not signed in"))?;
+    if !crate::capabilities::supports(&info.server_url, crate::capabilities::Feature::Sse) {
+        return Err(CommandError::unsupported("this server has no event stream"));
+    }
+    start(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn stop_event_stream(app: AppHandle) {
+    stop(&app);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn character_split_across_chunks() {
+        let mut parser = Parser::default();
+        let message = "data: héllo\n\n".as_bytes();
+        // Cut inside the two bytes of 'é'.
+        let cut = message.iter().position(|&b| b >= 0x80).unwrap() + 1;
+        assert!(parser.push(&message[..cut]).is_empty());
+        let events = parser.push(&message[cut..]);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, "héllo");
+        assert_eq!(events[0].event, "message");
+    }
+
+    #[test]
+    fn crlf_and_multiline_data() {
+        let mut parser = Parser::default();
+        let events = parser.push(b"event: chat\r\nid: 7\r\ndata: a\r\ndata: b\r\n\r\n");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event, "chat");
+        assert_eq!(events[0].data, "a\nb");
+        assert_eq!(events[0].id.as_deref(), Some("7"));
+    }
+}
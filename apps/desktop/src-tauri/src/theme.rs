@@ -0,0 +1,114 @@
+// Light/dark theme. `system` (the default) follows the OS; `light` and
+// `dark` pin the native window chrome and tell the webview which to draw.
+// Applied from setup while the main window is still hidden, so it never
+// shows in the wrong theme first. Every change of the effective theme, from
+// the OS or from `set_theme`, goes out as `theme://changed`.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, Theme};
+
+use crate::error::CommandError;
+use crate::prefs;
+
+pub(crate) const THEME_PREF: &str = "theme";
+
+/// The OS theme as last seen. A pinned window reports its pinned theme, so
+/// this is only updated while following the OS.
+static SYSTEM_THEME: Mutex<Option<Theme>> = Mutex::new(None);
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemePref {
+    Light,
+    Dark,
+    #[default]
+    System,
+}
+
+fn name(theme: Theme) -> &'static str {
+    match theme {
+        Theme::Dark => "dark",
+        _ => "light",
+    }
+}
+
+fn saved(app: &AppHandle) -> ThemePref {
+    prefs::get::<ThemePref>(app, THEME_PREF).unwrap_or_default()
+}
+
+fn system_theme(app: &AppHandle) -> Theme {
+    if let Some(theme) = *SYSTEM_THEME.lock().unwrap() {
+        return theme;
+    }
+    app.get_webview_window("main")
+        .and_then(|w| w.theme().ok())
+        .unwrap_or(Theme::Light)
+}
+
+fn effective(app: &AppHandle) -> Theme {
+    match saved(app) {
+        ThemePref::Light => Theme::Light,
+        ThemePref::Dark => Theme::Dark,
+        ThemePref::System => system_theme(app),
+    }
+}
+
+fn apply(app: &AppHandle, pref: ThemePref) {
+    app.set_theme(match pref {
+        ThemePref::Light => Some(Theme::Light),
+        ThemePref::Dark => Some(Theme::Dark),
+        ThemePref::System => None,
+    });
+}
+
+/// Note the OS theme before anything pins it, then apply the saved choice.
+/// Called from setup.
+pub fn restore(app: &AppHandle) {
+    if let Some(theme) = app.get_webview_window("main").and_then(|w| w.theme().ok()) {
+        *SYSTEM_THEME.lock().unwrap() = Some(theme);
+    }
+    let pref = saved(app);
+    if pref != ThemePref::System {
+        apply(app, pref);
+    }
+}
+
+/// For `WindowEvent::ThemeChanged` on the main window.
+pub fn on_theme_changed(app: &AppHandle, theme: Theme) {
+    if saved(app) != ThemePref::System {
+        return;
+    }
+    *SYSTEM_THEME.lock().unwrap() = Some(theme);
+    let _ = app.emit("theme://changed", name(theme));
+}
+
+/// The OS's light/dark setting, whatever the app is pinned to.
+#[tauri::command]
+pub fn get_system_theme(app: AppHandle) -> &'static str {
+    name(system_theme(&app))
+}
+
+/// The saved choice and the theme in effect.
+#[derive(Serialize)]
+pub struct ThemeState {
+    preference: ThemePref,
+    theme: &'static str,
+}
+
+#[tauri::command]
+pub fn get_theme(app: AppHandle) -> ThemeState {
+    ThemeState {
+        preference: saved(&app),
+        theme: name(effective(&app)),
+    }
+}
+
+#[tauri::command]
+pub fn set_theme(app: AppHandle, theme: ThemePref) -> Result<ThemeState, CommandError> {
+    prefs::set(&app, THEME_PREF, &theme)?;
+    apply(&app, theme);
+    let state = get_theme(app.clone());
+    let _ = app.emit("theme://changed", state.theme);
+    Ok(state)
+}
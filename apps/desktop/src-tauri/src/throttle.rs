@@ -0,0 +1,104 @@
+// Background throttle, for people who leave the app running all day on a
+// laptop. When enabled and the main window is hidden or minimized, the
+// heartbeat slows down, the voice-activity meter and the audio device
+// watchers stop, and the event stream backs off further between reconnects.
+// Full cadence is back as soon as the window shows again. Never engages
+// during a call, which still needs the VAD gate with the window out of view.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::CommandError;
+use crate::prefs;
+
+pub(crate) const THROTTLE_PREF: &str = "background_throttle";
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Heartbeat interval multiplier while throttled (30s becomes 2 minutes).
+pub const HEARTBEAT_FACTOR: u64 = 4;
+pub const STREAM_BACKOFF_MIN: Duration = Duration::from_secs(10);
+pub const STREAM_BACKOFF_MAX: Duration = Duration::from_secs(5 * 60);
+
+static ENGAGED: AtomicBool = AtomicBool::new(false);
+/// The VAD was running when throttling stopped it, so reopen it after.
+static VAD_PAUSED: AtomicBool = AtomicBool::new(false);
+
+/// Whether background work should run at the reduced cadence right now.
+pub fn engaged() -> bool {
+    ENGAGED.load(Ordering::Relaxed)
+}
+
+fn enabled(app: &AppHandle) -> bool {
+    prefs::get(app, THROTTLE_PREF).unwrap_or(false)
+}
+
+fn main_hidden(app: &AppHandle) -> bool {
+    let Some(window) = app.get_webview_window("main") else { return false };
+    !window.is_visible().unwrap_or(true) || window.is_minimized().unwrap_or(false)
+}
+
+#[derive(Serialize, Clone)]
+pub struct ThrottleState {
+    /// The saved setting.
+    enabled: bool,
+    /// Running at the reduced cadence now.
+    engaged: bool,
+}
+
+fn state(app: &AppHandle) -> ThrottleState {
+    ThrottleState {
+        enabled: enabled(app),
+        engaged: engaged(),
+    }
+}
+
+fn set_engaged(app: &AppHandle, on: bool) {
+    if ENGAGED.swap(on, Ordering::AcqRel) == on {
+        return;
+    }
+    log::info!("background throttle {}", if on { "engaged" } else { "released" });
+    // Pick up the new interval now rather than after a long sleep.
+    crate::presence::restart_heartbeat(app);
+    if on {
+        VAD_PAUSED.store(crate::vad::pause(app), Ordering::Relaxed);
+    } else {
+        if VAD_PAUSED.swap(false, Ordering::Relaxed) {
+            crate::vad::resume(app);
+        }
+        // Don't leave a dropped stream waiting out a throttled backoff.
+        if crate::stream::status(app) == "connecting" {
+            crate::stream::restart_if_running(app);
+        }
+    }
+    let _ = app.emit("throttle://changed", state(app));
+}
+
+/// Watch the main window and engage or release the throttle to match.
+/// Called from setup.
+pub fn start(app: &AppHandle) {
+    crate::shutdown::spawn(app, |app| async move {
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let want = enabled(&app) && main_hidden(&app) && !crate::notify::in_call(&app);
+            set_engaged(&app, want);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_background_throttle(app: AppHandle) -> ThrottleState {
+    state(&app)
+}
+
+/// Turning it off releases the throttle straight away; turning it on takes
+/// effect the next time the window is hidden (within a few seconds if it
+/// already is).
+#[tauri::command]
+pub fn set_background_throttle(app: AppHandle, enabled: bool) -> Result<ThrottleState, CommandError> {
+    prefs::set(&app, THROTTLE_PREF, &enabled)?;
+    if !enabled {
+        set_engaged(&app, false);
+    }
+    Ok(state(&app))
+}
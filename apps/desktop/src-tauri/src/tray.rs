@@ -0,0 +1,257 @@
+// System tray icon: keeps the client reachable when the window is hidden and
+// mirrors presence with a coloured status dot over the app icon.
+
+use serde::Deserialize;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Mutex;
+use tauri::image::Image;
+use tauri::menu::{Menu, MenuItem};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Manager};
+
+use crate::error::CommandError;
+use crate::i18n::t;
+
+const TRAY_ID: &str = "main";
+
+/// When set, closing the main window hides it to the tray instead of quitting.
+pub struct CloseToTray(pub AtomicBool);
+
+pub struct TrayState {
+    pub status: Mutex<TrayStatus>,
+    pub unread: AtomicU32,
+    /// Do-not-disturb overrides whatever presence the webview reported.
+    pub dnd: AtomicBool,
+    /// In a voice call: drawn as a marker top-left, alongside presence.
+    pub in_call: AtomicBool,
+    /// Ghost mode; shown locally in place of the reported presence.
+    pub invisible: AtomicBool,
+    /// Colour of the in-call marker; see `accent`.
+    pub accent: Mutex<[u8; 3]>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum TrayStatus {
+    Online,
+    Away,
+    Dnd,
+    Offline,
+    Invisible,
+}
+
+impl TrayStatus {
+    fn color(self) -> [u8; 3] {
+        match self {
+            TrayStatus::Online => [0x23, 0xa5, 0x5a],
+            TrayStatus::Away => [0xf0, 0xb2, 0x32],
+            TrayStatus::Dnd => [0xda, 0x37, 0x3c],
+            TrayStatus::Offline | TrayStatus::Invisible => [0x80, 0x84, 0x8e],
+        }
+    }
+
+    /// i18n key for the tooltip.
+    fn label(self) -> &'static str {
+        match self {
+            TrayStatus::Online => "tray.status.online",
+            TrayStatus::Away => "tray.status.away",
+            TrayStatus::Dnd => "tray.status.dnd",
+            TrayStatus::Offline => "tray.status.offline",
+            TrayStatus::Invisible => "tray.status.invisible",
+        }
+    }
+}
+
+const UNREAD_COLOR: [u8; 3] = [0xed, 0x42, 0x45];
+
+/// Fill a circle of radius `r` centred on (`cx`, `cy`) in an RGBA buffer.
+fn draw_dot(rgba: &mut [u8], w: u32, h: u32, (cx, cy): (i64, i64), r: i64, [cr, cg, cb]: [u8; 3]) {
+    for y in (cy - r).max(0)..=(cy + r).min(h as i64 - 1) {
+        for x in (cx - r).max(0)..=(cx + r).min(w as i64 - 1) {
+            if (x - cx).pow(2) + (y - cy).pow(2) <= r * r {
+                let i = ((y * w as i64 + x) * 4) as usize;
+                rgba[i..i + 4].copy_from_slice(&[cr, cg, cb, 0xff]);
+            }
+        }
+    }
+}
+
+/// The app icon with a status dot bottom-right, a red marker top-right when
+/// there are unread messages and one in the accent colour top-left during a
+/// call.
+fn status_icon(app: &AppHandle, status: TrayStatus, unread: bool, in_call: bool) -> Option<Image<'static>> {
+    let base = app.default_window_icon()?;
+    let (w, h) = (base.width(), base.height());
+    let mut rgba = base.rgba().to_vec();
+    let r = (w.min(h) / 4) as i64;
+    draw_dot(&mut rgba, w, h, (w as i64 - r - 1, h as i64 - r - 1), r, status.color());
+    if unread {
+        draw_dot(&mut rgba, w, h, (w as i64 - r - 1, r), r, UNREAD_COLOR);
+    }
+    if in_call {
+        let accent = *app.state::<TrayState>().accent.lock().unwrap();
+        draw_dot(&mut rgba, w, h, (r, r), r, accent);
+    }
+    Some(Image::new(&rgba, w, h).to_owned())
+}
+
+/// Render the tray icon and tooltip from the current status and unread count.
+fn refresh_icon(app: &AppHandle) -> Result<(), String> {
+    let tray = app.tray_by_id(TRAY_ID).ok_or("tray not initialised")?;
+    let state = app.state::<TrayState>();
+    let status = if state.dnd.load(Ordering::Relaxed) {
+        TrayStatus::Dnd
+    } else if state.invisible.load(Ordering::Relaxed) {
+        TrayStatus::Invisible
+    } else {
+        *state.status.lock().unwrap()
+    };
+    // macOS shows unread on the dock badge instead.
+    let unread = cfg!(not(target_os = "macos")) && state.unread.load(Ordering::Relaxed) > 0;
+    let in_call = state.in_call.load(Ordering::Relaxed);
+    tray.set_icon(status_icon(app, status, unread, in_call))
+        .map_err(|e| e.to_string())?;
+    let call = if in_call { t(app, "tray.in_call") } else { String::new() };
+    let deafened = if crate::deafen::deafened(app) { t(app, "tray.deafened") } else { String::new() };
+    tray.set_tooltip(Some(format!("disTokoloshe — {}{call}{deafened}", t(app, status.label()))))
+        .map_err(|e| e.to_string())
+}
+
+pub fn set_dnd(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    app.state::<TrayState>().dnd.store(enabled, Ordering::Relaxed);
+    refresh_icon(app)
+}
+
+pub fn set_invisible(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    app.state::<TrayState>().invisible.store(enabled, Ordering::Relaxed);
+    refresh_icon(app)
+}
+
+/// Redraw after deafen changed; the tooltip notes it.
+pub fn deafen_changed(app: &AppHandle) -> Result<(), String> {
+    refresh_icon(app)
+}
+
+pub fn set_in_call(app: &AppHandle, in_call: bool) -> Result<(), String> {
+    app.state::<TrayState>().in_call.store(in_call, Ordering::Relaxed);
+    refresh_icon(app)
+}
+
+pub fn set_accent(app: &AppHandle, rgb: [u8; 3]) -> Result<(), String> {
+    *app.state::<TrayState>().accent.lock().unwrap() = rgb;
+    refresh_icon(app)
+}
+
+pub fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn build_menu(app: &AppHandle) -> tauri::Result<Menu<tauri::Wry>> {
+    let show = MenuItem::with_id(app, "show", t(app, "tray.show"), true, None::<&str>)?;
+    let mute = MenuItem::with_id(app, "mute", t(app, "tray.mute"), true, None::<&str>)?;
+    let recenter = MenuItem::with_id(app, "recenter", t(app, "tray.recenter"), true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", t(app, "tray.quit"), true, None::<&str>)?;
+    Menu::with_items(app, &[&show, &mute, &recenter, &quit])
+}
+
+/// Rebuild the menu and tooltip after the locale changed.
+pub fn relocalize(app: &AppHandle) -> Result<(), String> {
+    let tray = app.tray_by_id(TRAY_ID).ok_or("tray not initialised")?;
+    let menu = build_menu(app).map_err(|e| e.to_string())?;
+    tray.set_menu(Some(menu)).map_err(|e| e.to_string())?;
+    refresh_icon(app)
+}
+
+pub fn init(app: &AppHandle) -> tauri::Result<()> {
+    let menu = build_menu(app)?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("disTokoloshe")
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app, event| match event.id.as_ref() {
+            "show" => show_main_window(app),
+            "mute" => {
+                crate::shortcuts::toggle_mute(app);
+            }
+            "recenter" => {
+                if let Err(e) = crate::window::recenter(app) {
+                    log::warn!("failed to recenter window: {e}");
+                }
+            }
+            "quit" => {
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move { crate::quit(&app).await });
+            }
+            _ => {}
+        })
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                show_main_window(tray.app_handle());
+            }
+        });
+    if let Some(icon) = status_icon(app, TrayStatus::Offline, false, false) {
+        builder = builder.icon(icon);
+    }
+    builder.build(app)?;
+    Ok(())
+}
+
+pub fn set_status(app: &AppHandle, status: TrayStatus) -> Result<(), String> {
+    *app.state::<TrayState>().status.lock().unwrap() = status;
+    refresh_icon(app)
+}
+
+#[tauri::command]
+pub fn set_tray_status(app: AppHandle, status: TrayStatus) -> Result<(), CommandError> {
+    Ok(set_status(&app, status)?)
+}
+
+pub fn close_to_tray(app: &AppHandle) -> bool {
+    app.state::<CloseToTray>().0.load(Ordering::Relaxed)
+}
+
+#[tauri::command]
+pub fn set_close_to_tray(app: AppHandle, enabled: bool) -> Result<(), CommandError> {
+    app.state::<CloseToTray>().0.store(enabled, Ordering::Relaxed);
+    crate::prefs::set(&app, crate::prefs::CLOSE_TO_TRAY, &enabled)
+}
+
+/// Re-apply the persisted close-to-tray choice. Called from setup.
+pub fn restore_close_to_tray(app: &AppHandle) {
+    let enabled = crate::prefs::known(app).close_to_tray;
+    app.state::<CloseToTray>().0.store(enabled, Ordering::Relaxed);
+}
+
+#[tauri::command]
+pub fn set_badge_count(app: AppHandle, count: u32) -> Result<(), CommandError> {
+    app.state::<TrayState>().unread.store(count, Ordering::Relaxed);
+    refresh_icon(&app)?;
+    let Some(window) = app.get_webview_window("main") else {
+        return Ok(());
+    };
+    #[cfg(windows)]
+    {
+        // Windows has no numeric taskbar badge; use an overlay dot instead.
+        let overlay = (count > 0).then(|| {
+            let mut rgba = vec![0u8; 16 * 16 * 4];
+            draw_dot(&mut rgba, 16, 16, (8, 8), 7, UNREAD_COLOR);
+            Image::new(&rgba, 16, 16).to_owned()
+        });
+        window.set_overlay_icon(overlay).map_err(CommandError::from)
+    }
+    #[cfg(not(windows))]
+    {
+        let badge = (count > 0).then_some(count as i64);
+        window.set_badge_count(badge).map_err(CommandError::from)
+    }
+}
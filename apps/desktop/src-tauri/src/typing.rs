@@ -0,0 +1,80 @@
+// Typing indicators. The webview calls `set_typing` on every keystroke; the
+// debounce lives here so it keeps working when a backgrounded webview's
+// timers are throttled. At most one `typing` ping per channel per interval,
+// and a `typing_stop` once the keystrokes stop.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager};
+
+use crate::error::CommandError;
+use crate::presence::{self, AuthInfo, AuthState};
+
+const TYPING_INTERVAL: Duration = Duration::from_secs(3);
+/// Quiet time after the last keystroke before `typing_stop` goes out.
+const TYPING_IDLE: Duration = Duration::from_secs(5);
+
+struct ChannelTyping {
+    last_sent: Instant,
+    stop: JoinHandle<()>,
+}
+
+#[derive(Default)]
+pub struct TypingState(Mutex<HashMap<String, ChannelTyping>>);
+
+async fn send(info: &AuthInfo, event: &str, channel_id: &str) {
+    let mut fields = serde_json::Map::new();
+    fields.insert("channel_id".into(), channel_id.into());
+    if let Err(e) = presence::post_event_with(info, event, fields).await {
+        log::debug!("{event} for {channel_id} failed: {e}");
+    }
+}
+
+#[tauri::command]
+pub fn set_typing(app: AppHandle, channel_id: String) -> Result<(), CommandError> {
+    let auth = app.state::<AuthState>().0.lock().unwrap().clone();
+    let info = auth.ok_or_else(|| CommandError::auth("Not signed in"))?;
+    let state = app.state::<TypingState>();
+    let mut channels = state.0.lock().unwrap();
+
+    let previous = channels.remove(&channel_id);
+    let due = previous
+        .as_ref()
+        .is_none_or(|c| c.last_sent.elapsed() >= TYPING_INTERVAL);
+    let last_sent = match previous {
+        Some(c) => {
+            c.stop.abort();
+            if due { Instant::now() } else { c.last_sent }
+        }
+        None => Instant::now(),
+    };
+    if due {
+        let (info, channel_id) = (info.clone(), channel_id.clone());
+        tauri::async_runtime::spawn(async move { send(&info, "typing", &channel_id).await });
+    }
+
+    let app_for_stop = app.clone();
+    let channel_for_stop = channel_id.clone();
+    let stop = tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(TYPING_IDLE).await;
+        app_for_stop
+            .state::<TypingState>()
+            .0
+            .lock()
+            .unwrap()
+            .remove(&channel_for_stop);
+        send(&info, "typing_stop", &channel_for_stop).await;
+    });
+    channels.insert(channel_id, ChannelTyping { last_sent, stop });
+    Ok(())
+}
+
+/// Cancel every pending `typing_stop` without sending it. Called on logout,
+/// when the token they'd be sent with is being dropped anyway.
+pub fn clear(app: &AppHandle) {
+    for (_, channel) in app.state::<TypingState>().0.lock().unwrap().drain() {
+        channel.stop.abort();
+    }
+}
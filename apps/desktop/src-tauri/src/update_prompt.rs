@@ -0,0 +1,165 @@
+// Native "update available" prompt. The update UI lives in the webview, so
+// a hung webview, or one nobody can see because the app is sitting in the
+// tray, means an update is found and then nothing happens. When a check turns
+// one up and auto-install isn't handling it, the main window being hidden
+// prompts natively straight away; otherwise the webview gets `PROMPT_GRACE`
+// to show it, acknowledged with `acknowledge_update`, before the native
+// dialog stands in. A banner the user simply leaves alone is their answer,
+// so only a webview that never acknowledges gets the dialog.
+// Install goes through `install_update` like the webview's button.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::error::CommandError;
+use crate::i18n;
+
+/// How long the webview has to acknowledge an update before it's taken as
+/// hung.
+const PROMPT_GRACE: Duration = Duration::from_secs(60);
+
+/// Bumped by every arm and disarm; a pending fallback only fires if nothing
+/// has happened since it was armed.
+static ARMED: AtomicU64 = AtomicU64::new(0);
+/// A dialog is up; one at a time.
+static SHOWING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateDecision {
+    Install,
+    Later,
+}
+
+struct Showing;
+
+impl Showing {
+    fn claim() -> Result<Self, CommandError> {
+        if SHOWING.swap(true, Ordering::AcqRel) {
+            return Err(CommandError::updater("an update dialog is already open"));
+        }
+        Ok(Showing)
+    }
+}
+
+impl Drop for Showing {
+    fn drop(&mut self) {
+        SHOWING.store(false, Ordering::Release);
+    }
+}
+
+pub fn showing() -> bool {
+    SHOWING.load(Ordering::Acquire)
+}
+
+fn main_window_hidden(app: &AppHandle) -> bool {
+    app.get_webview_window("main")
+        .is_some_and(|w| !w.is_visible().unwrap_or(true))
+}
+
+/// Ask the user about the pending update. `_showing` is held until they
+/// answer.
+async fn ask(app: &AppHandle, _showing: Showing) -> Result<UpdateDecision, CommandError> {
+    let version = app
+        .state::<crate::PendingUpdate>()
+        .update
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|u| u.version.clone())
+        .ok_or_else(|| CommandError::updater("No pending update"))?;
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .message(i18n::t_with(app, "update.available.body", &[("version", &version)]))
+        .title(i18n::t(app, "update.available.title"))
+        .kind(MessageDialogKind::Info)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            i18n::t(app, "update.available.install"),
+            i18n::t(app, "update.available.later"),
+        ))
+        .show(move |install| {
+            let _ = tx.send(install);
+        });
+    // A dropped callback counts as Later.
+    Ok(if rx.await.unwrap_or(false) {
+        UpdateDecision::Install
+    } else {
+        UpdateDecision::Later
+    })
+}
+
+async fn ask_and_install(app: AppHandle, showing: Showing) -> Result<UpdateDecision, CommandError> {
+    let decision = ask(&app, showing).await?;
+    if decision == UpdateDecision::Install {
+        let state = app.state::<crate::PendingUpdate>();
+        crate::install_update(app.clone(), state, None).await?;
+    }
+    Ok(decision)
+}
+
+/// An update was just found: prompt natively now if the window is hidden,
+/// or after `PROMPT_GRACE` unless something picks the update up first.
+pub fn arm(app: &AppHandle) {
+    let generation = ARMED.fetch_add(1, Ordering::AcqRel) + 1;
+    let app = app.clone();
+    // Claimed before the check returns, so the hidden webview's own
+    // install_update finds the dialog up rather than restarting unasked.
+    let showing = main_window_hidden(&app).then(Showing::claim).and_then(Result::ok);
+    tauri::async_runtime::spawn(async move {
+        let showing = match showing {
+            Some(showing) => showing,
+            None => {
+                tokio::time::sleep(PROMPT_GRACE).await;
+                if ARMED.load(Ordering::Acquire) != generation {
+                    return;
+                }
+                log::warn!("webview didn't acknowledge the update, prompting natively");
+                match Showing::claim() {
+                    Ok(showing) => showing,
+                    Err(_) => return,
+                }
+            }
+        };
+        if let Err(e) = ask_and_install(app, showing).await {
+            log::warn!("native update prompt: {e}");
+        }
+    });
+}
+
+/// Prompt now, whatever the webview is doing. Used when the server won't
+/// talk to this version.
+pub fn prompt_now(app: &AppHandle) {
+    disarm();
+    // Already up: the answer to that one will do.
+    let Ok(showing) = Showing::claim() else { return };
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = ask_and_install(app, showing).await {
+            log::warn!("native update prompt: {e}");
+        }
+    });
+}
+
+/// The update is being handled; drop any pending fallback.
+pub fn disarm() {
+    ARMED.fetch_add(1, Ordering::AcqRel);
+}
+
+/// The webview is showing the pending update; it can take it from here.
+#[tauri::command]
+pub fn acknowledge_update() {
+    disarm();
+}
+
+/// Show the native update dialog for the pending update and return the
+/// choice. Install goes straight on to `install_update`, so like it this
+/// only returns on failure or when the install doesn't restart.
+#[tauri::command]
+pub async fn show_update_dialog(app: AppHandle) -> Result<UpdateDecision, CommandError> {
+    disarm();
+    ask_and_install(app, Showing::claim()?).await
+}
@@ -0,0 +1,189 @@
+// Maintenance window for auto-installs. With a window set, an update found
+// outside it is only noted, with the time the next window opens; once that
+// time comes it's downloaded through the install-on-quit path and applied
+// while the user is away, so nobody is restarted mid-call. If the app was
+// closed through the whole window, the next launch catches up and installs
+// as soon as the user goes idle.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+use crate::error::CommandError;
+use crate::prefs;
+
+pub(crate) const WINDOW_PREF: &str = "update_window";
+/// The update waiting for a window: `{ version, due_at }`.
+pub(crate) const DEFERRED_PREF: &str = "update_deferred";
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+/// Local hours, `start` inclusive and `end` exclusive. Wraps past midnight
+/// when `end` is before `start` (22–2).
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct UpdateWindow {
+    start_hour: u8,
+    end_hour: u8,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Deferred {
+    version: String,
+    /// Unix seconds when the window it's waiting for opens.
+    due_at: u64,
+}
+
+impl UpdateWindow {
+    fn contains(&self, hour: u8) -> bool {
+        if self.start_hour < self.end_hour {
+            (self.start_hour..self.end_hour).contains(&hour)
+        } else {
+            hour >= self.start_hour || hour < self.end_hour
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Seconds since local midnight.
+#[cfg(windows)]
+fn local_seconds_of_day() -> Option<u64> {
+    use windows_sys::Win32::System::SystemInformation::GetLocalTime;
+
+    // SAFETY: fills in a plain-old-data struct and can't fail.
+    let now = unsafe {
+        let mut now = std::mem::zeroed();
+        GetLocalTime(&mut now);
+        now
+    };
+    Some(u64::from(now.wHour) * 3600 + u64::from(now.wMinute) * 60 + u64::from(now.wSecond))
+}
+
+#[cfg(unix)]
+fn local_seconds_of_day() -> Option<u64> {
+    // SAFETY: localtime_r is the thread-safe variant and only writes `tm`.
+    let tm = unsafe {
+        let now = libc::time(std::ptr::null_mut());
+        let mut tm: libc::tm = std::mem::zeroed();
+        if libc::localtime_r(&now, &mut tm).is_null() {
+            return None;
+        }
+        tm
+    };
+    u64::try_from(tm.tm_hour * 3600 + tm.tm_min * 60 + tm.tm_sec).ok()
+}
+
+#[cfg(not(any(windows, unix)))]
+fn local_seconds_of_day() -> Option<u64> {
+    None
+}
+
+fn window(app: &AppHandle) -> Option<UpdateWindow> {
+    prefs::get(app, WINDOW_PREF)
+}
+
+fn deferred(app: &AppHandle) -> Option<Deferred> {
+    prefs::get(app, DEFERRED_PREF)
+}
+
+/// Whether an auto-install of `version` may go ahead now: there is no
+/// window, we're inside it, or it already opened since `version` was
+/// deferred (including while the app was closed).
+pub(crate) fn allows(app: &AppHandle, version: &str) -> bool {
+    let Some(window) = window(app) else { return true };
+    let Some(secs) = local_seconds_of_day() else { return true };
+    if window.contains((secs / 3600) as u8) {
+        return true;
+    }
+    deferred(app).is_some_and(|d| d.version == version && unix_now() >= d.due_at)
+}
+
+pub(crate) fn is_set(app: &AppHandle) -> bool {
+    window(app).is_some()
+}
+
+/// Hold `version` for the next window. Keeps the original due time if it's
+/// already waiting, so repeated checks don't keep pushing it back.
+pub(crate) fn defer(app: &AppHandle, version: &str) {
+    let (Some(window), Some(secs)) = (window(app), local_seconds_of_day()) else { return };
+    if deferred(app).is_some_and(|d| d.version == version) {
+        return;
+    }
+    let until_start = (u64::from(window.start_hour) * 3600 + DAY_SECS - secs) % DAY_SECS;
+    let entry = Deferred {
+        version: version.to_string(),
+        due_at: unix_now() + until_start,
+    };
+    log::info!("update {version} deferred to the maintenance window, {until_start}s from now");
+    if let Err(e) = prefs::set(app, DEFERRED_PREF, &entry) {
+        log::warn!("could not save deferred update: {e}");
+    }
+}
+
+/// Forget the deferral once its update has been applied.
+pub(crate) fn clear(app: &AppHandle) {
+    let _ = prefs::remove(app, DEFERRED_PREF);
+}
+
+/// Drive deferred updates through: start the download once the window
+/// allows it, then apply and relaunch while the user is away.
+pub fn start(app: &AppHandle) {
+    crate::shutdown::spawn(app, |app| async move {
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            if !is_set(&app) || !crate::auto_install_enabled(&app) {
+                continue;
+            }
+            let state = app.state::<crate::PendingUpdate>();
+            let Some(version) = state.update.lock().unwrap().as_ref().map(|u| u.version.clone()) else {
+                continue;
+            };
+            if !allows(&app, &version) {
+                continue;
+            }
+            if state.downloaded.lock().unwrap().is_none() {
+                crate::auto_install(app.clone(), version).await;
+                continue;
+            }
+            if !app.state::<crate::idle::IdleState>().away.load(Ordering::Relaxed) {
+                continue;
+            }
+            crate::install_unattended(&app).await;
+        }
+    });
+}
+
+/// Only auto-install between `start_hour` and `end_hour` local time (0–23,
+/// `end_hour` exclusive), or clear the window with both `None`.
+#[tauri::command]
+pub fn set_update_window(
+    app: AppHandle,
+    start_hour: Option<u8>,
+    end_hour: Option<u8>,
+) -> Result<Option<UpdateWindow>, CommandError> {
+    let window = match (start_hour, end_hour) {
+        (None, None) => None,
+        (Some(start_hour), Some(end_hour)) if start_hour < 24 && end_hour < 24 && start_hour != end_hour => {
+            Some(UpdateWindow { start_hour, end_hour })
+        }
+        _ => {
+            return Err(CommandError::invalid_input(
+                "start_hour and end_hour must both be set, differ and be 0-23",
+            ))
+        }
+    };
+    prefs::set(&app, WINDOW_PREF, &window)?;
+    // Recomputed against the new window on the next check.
+    clear(&app);
+    Ok(window)
+}
+
+#[tauri::command]
+pub fn get_update_window(app: AppHandle) -> Option<UpdateWindow> {
+    window(&app)
+}
@@ -0,0 +1,246 @@
+// Voice-activity detection for open-mic mode: a light energy meter on the
+// selected input device that tells the webview when the user starts and
+// stops talking (`vad://speaking` / `vad://silent`), for the mic indicator
+// and for gating transmission. It only listens; the call's own capture stays
+// in the webview.
+
+use cpal::traits::{DeviceTrait, StreamTrait};
+use cpal::{FromSample, SampleFormat, SizedSample};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::error::CommandError;
+use crate::prefs;
+
+pub(crate) const THRESHOLD_PREF: &str = "vad_threshold";
+/// dBFS. A quiet room sits around -60, speech into a headset mic around -30.
+const DEFAULT_THRESHOLD_DB: f32 = -45.0;
+const MIN_THRESHOLD_DB: f32 = -80.0;
+const MAX_THRESHOLD_DB: f32 = -10.0;
+/// Energy is measured over windows this long.
+const FRAME_MS: u32 = 20;
+/// Frames to stay "speaking" after the level drops (300ms), so the pauses
+/// between words don't flap the gate.
+const HANG_FRAMES: u32 = 15;
+
+pub struct VadState {
+    /// The threshold in dBFS, as `f32` bits so the audio callback can read it
+    /// without locking.
+    threshold: Arc<AtomicU32>,
+    /// Dropping the sender stops the monitor thread.
+    stop: Mutex<Option<mpsc::Sender<()>>>,
+}
+
+#[derive(Serialize, Clone)]
+struct Speaking {
+    level_db: f32,
+}
+
+fn clamp(db: f32) -> f32 {
+    db.clamp(MIN_THRESHOLD_DB, MAX_THRESHOLD_DB)
+}
+
+/// Managed from setup, after prefs.
+pub fn init(app: &AppHandle) -> VadState {
+    let db = prefs::get::<f32>(app, THRESHOLD_PREF)
+        .filter(|db| db.is_finite())
+        .map_or(DEFAULT_THRESHOLD_DB, clamp);
+    VadState {
+        threshold: Arc::new(AtomicU32::new(db.to_bits())),
+        stop: Mutex::new(None),
+    }
+}
+
+/// Running RMS over one frame at a time, with hysteresis on the way down.
+struct Meter {
+    app: AppHandle,
+    threshold: Arc<AtomicU32>,
+    /// Samples per frame, across all channels.
+    frame_len: usize,
+    sum: f64,
+    count: usize,
+    speaking: bool,
+    hang: u32,
+}
+
+impl Meter {
+    fn push(&mut self, sample: f32) {
+        self.sum += f64::from(sample) * f64::from(sample);
+        self.count += 1;
+        if self.count >= self.frame_len {
+            self.frame();
+        }
+    }
+
+    fn frame(&mut self) {
+        let rms = (self.sum / self.count as f64).sqrt();
+        self.sum = 0.0;
+        self.count = 0;
+        let level_db = if rms > 0.0 { (20.0 * rms.log10()) as f32 } else { f32::NEG_INFINITY };
+        let threshold = f32::from_bits(self.threshold.load(Ordering::Relaxed));
+        if level_db >= threshold {
+            self.hang = HANG_FRAMES;
+            if !self.speaking {
+                self.speaking = true;
+                let _ = self.app.emit("vad://speaking", Speaking { level_db });
+            }
+        } else if self.speaking {
+            if self.hang > 0 {
+                self.hang -= 1;
+            } else {
+                self.speaking = false;
+                let _ = self.app.emit("vad://silent", ());
+            }
+        }
+    }
+}
+
+fn build<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut meter: Meter,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let app = meter.app.clone();
+    device.build_input_stream(
+        config,
+        move |data: &[T], _| {
+            for &sample in data {
+                meter.push(sample.to_sample::<f32>());
+            }
+        },
+        move |e| {
+            log::warn!("voice activity input failed: {e}");
+            let _ = app.emit("vad://error", e.to_string());
+        },
+        None,
+    )
+}
+
+/// Open the input device and meter it until `stop` is dropped. `ready`
+/// gets the outcome of opening it.
+fn run(
+    app: AppHandle,
+    threshold: Arc<AtomicU32>,
+    ready: tokio::sync::oneshot::Sender<Result<(), String>>,
+    stop: mpsc::Receiver<()>,
+) {
+    let opened = (|| {
+        let device = crate::audio::input_device(&app).ok_or("no input device")?;
+        let supported = device.default_input_config().map_err(|e| e.to_string())?;
+        let config = supported.config();
+        let frame_len = (config.sample_rate.0 * FRAME_MS / 1000) as usize * usize::from(config.channels);
+        let meter = Meter {
+            app: app.clone(),
+            threshold,
+            frame_len: frame_len.max(1),
+            sum: 0.0,
+            count: 0,
+            speaking: false,
+            hang: 0,
+        };
+        let stream = match supported.sample_format() {
+            SampleFormat::F32 => build::<f32>(&device, &config, meter),
+            SampleFormat::I16 => build::<i16>(&device, &config, meter),
+            SampleFormat::U16 => build::<u16>(&device, &config, meter),
+            SampleFormat::I32 => build::<i32>(&device, &config, meter),
+            f => return Err(format!("unsupported sample format {f}")),
+        }
+        .map_err(|e| e.to_string())?;
+        stream.play().map_err(|e| e.to_string())?;
+        Ok::<_, String>(stream)
+    })();
+    let stream = match opened {
+        Ok(stream) => {
+            let _ = ready.send(Ok(()));
+            stream
+        }
+        Err(e) => {
+            let _ = ready.send(Err(e));
+            return;
+        }
+    };
+    // Blocks until the sender is dropped by `stop`.
+    let _ = stop.recv();
+    drop(stream);
+    let _ = app.emit("vad://silent", ());
+}
+
+async fn start(app: &AppHandle) -> Result<(), String> {
+    stop(app);
+    let (stop_tx, stop_rx) = mpsc::channel();
+    let (ready_tx, ready_rx) = tokio::sync::oneshot::channel();
+    let threshold = app.state::<VadState>().threshold.clone();
+    let thread_app = app.clone();
+    // cpal streams aren't `Send` on every backend, so the stream lives and
+    // dies on its own thread.
+    std::thread::spawn(move || run(thread_app, threshold, ready_tx, stop_rx));
+    ready_rx.await.map_err(|e| e.to_string())??;
+    *app.state::<VadState>().stop.lock().unwrap() = Some(stop_tx);
+    Ok(())
+}
+
+fn stop(app: &AppHandle) {
+    app.state::<VadState>().stop.lock().unwrap().take();
+}
+
+/// Re-open on the newly preferred device if the monitor is running.
+pub fn restart_if_running(app: &AppHandle) {
+    if app.state::<VadState>().stop.lock().unwrap().is_none() {
+        return;
+    }
+    resume(app);
+}
+
+/// Stop the monitor for the background throttle. Returns whether it was
+/// running, i.e. whether to `resume` it afterwards.
+pub fn pause(app: &AppHandle) -> bool {
+    app.state::<VadState>().stop.lock().unwrap().take().is_some()
+}
+
+/// (Re)open the monitor on the preferred device.
+pub fn resume(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = start(&app).await {
+            log::warn!("failed to restart voice activity monitor: {e}");
+            let _ = app.emit("vad://error", e);
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn start_vad(app: AppHandle) -> Result<(), CommandError> {
+    if !crate::environment::audio_monitors_enabled() {
+        return Err(CommandError::unsupported("no audio in a headless environment"));
+    }
+    start(&app).await.map_err(CommandError::io)
+}
+
+#[tauri::command]
+pub fn stop_vad(app: AppHandle) {
+    stop(&app);
+}
+
+/// Set the speaking threshold in dBFS, clamped to -80..-10. Returns the value
+/// actually used.
+#[tauri::command]
+pub fn set_vad_threshold(app: AppHandle, db: f32) -> Result<f32, CommandError> {
+    if !db.is_finite() {
+        return Err(CommandError::invalid_input("threshold must be a number"));
+    }
+    let db = clamp(db);
+    prefs::set(&app, THRESHOLD_PREF, &db)?;
+    app.state::<VadState>().threshold.store(db.to_bits(), Ordering::Relaxed);
+    Ok(db)
+}
+
+#[tauri::command]
+pub fn get_vad_threshold(state: tauri::State<'_, VadState>) -> f32 {
+    f32::from_bits(state.threshold.load(Ordering::Relaxed))
+}
@@ -0,0 +1,208 @@
+// The app's own volume in the OS mixer, separate from in-app gain. Windows
+// only: WASAPI has per-process audio sessions, and the webview's audio runs
+// in WebView2 child processes, so every session in our process tree is set.
+// macOS has no per-app volume; elsewhere this is `unsupported`.
+
+use tauri::AppHandle;
+
+#[cfg(windows)]
+use crate::error::CommandError;
+use crate::prefs;
+
+#[cfg(windows)]
+pub(crate) const APP_VOLUME: &str = "app_volume";
+#[cfg(windows)]
+const WATCH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3);
+
+#[cfg(windows)]
+mod wasapi {
+    use std::collections::{HashMap, HashSet};
+    use windows::core::Interface;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::Media::Audio::{
+        eMultimedia, eRender, IAudioSessionControl2, IAudioSessionManager2, IMMDeviceEnumerator,
+        ISimpleAudioVolume, MMDeviceEnumerator,
+    };
+    use windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, CLSCTX_ALL, COINIT_MULTITHREADED};
+    use windows::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W, TH32CS_SNAPPROCESS,
+    };
+
+    /// Our pid and all its descendants (the WebView2 browser, renderer and
+    /// audio processes).
+    fn process_tree() -> HashSet<u32> {
+        let mut tree = HashSet::from([std::process::id()]);
+        let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+        // SAFETY: the snapshot handle is closed below; the entry carries its
+        // own size as the API requires.
+        unsafe {
+            let Ok(snapshot) = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) else { return tree };
+            let mut entry = PROCESSENTRY32W {
+                dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32,
+                ..Default::default()
+            };
+            let mut ok = Process32FirstW(snapshot, &mut entry).is_ok();
+            while ok {
+                children.entry(entry.th32ParentProcessID).or_default().push(entry.th32ProcessID);
+                ok = Process32NextW(snapshot, &mut entry).is_ok();
+            }
+            let _ = CloseHandle(snapshot);
+        }
+        let mut stack = vec![std::process::id()];
+        while let Some(pid) = stack.pop() {
+            for &child in children.get(&pid).into_iter().flatten() {
+                if tree.insert(child) {
+                    stack.push(child);
+                }
+            }
+        }
+        tree
+    }
+
+    /// Volume controls for our sessions on the default output, keyed by pid.
+    pub fn sessions() -> windows::core::Result<Vec<(u32, ISimpleAudioVolume)>> {
+        // SAFETY: plain COM calls on this (blocking) thread; S_FALSE or
+        // RPC_E_CHANGED_MODE from CoInitializeEx still leave COM usable.
+        unsafe {
+            let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+            let enumerator: IMMDeviceEnumerator = CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)?;
+            let device = enumerator.GetDefaultAudioEndpoint(eRender, eMultimedia)?;
+            let manager: IAudioSessionManager2 = device.Activate(CLSCTX_ALL, None)?;
+            let list = manager.GetSessionEnumerator()?;
+            let tree = process_tree();
+            let mut out = Vec::new();
+            for i in 0..list.GetCount()? {
+                let control = list.GetSession(i)?;
+                let Ok(pid) = control.cast::<IAudioSessionControl2>().and_then(|c| c.GetProcessId()) else {
+                    continue;
+                };
+                if tree.contains(&pid) {
+                    out.push((pid, control.cast::<ISimpleAudioVolume>()?));
+                }
+            }
+            Ok(out)
+        }
+    }
+
+    pub fn set(volume: &ISimpleAudioVolume, level: f32) -> windows::core::Result<()> {
+        // SAFETY: a valid interface from `sessions`; null event context.
+        unsafe { volume.SetMasterVolume(level, std::ptr::null()) }
+    }
+
+    pub fn set_mute(volume: &ISimpleAudioVolume, muted: bool) -> windows::core::Result<()> {
+        // SAFETY: as above.
+        unsafe { volume.SetMute(muted, std::ptr::null()) }
+    }
+
+    pub fn get(volume: &ISimpleAudioVolume) -> windows::core::Result<f32> {
+        // SAFETY: as above.
+        unsafe { volume.GetMasterVolume() }
+    }
+}
+
+/// Set the app's mixer volume (0.0–1.0) and remember it, so sessions the
+/// webview opens later get it too.
+#[tauri::command]
+pub async fn set_app_volume(app: AppHandle, level: f64) -> Result<(), CommandError> {
+    let level = level.clamp(0.0, 1.0);
+    #[cfg(windows)]
+    {
+        prefs::set(&app, APP_VOLUME, &level)?;
+        tauri::async_runtime::spawn_blocking(move || {
+            for (_, session) in wasapi::sessions().map_err(CommandError::io)? {
+                wasapi::set(&session, level as f32).map_err(CommandError::io)?;
+            }
+            Ok(())
+        })
+        .await?
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = (app, level);
+        Err(CommandError::unsupported("app volume is only adjustable on Windows"))
+    }
+}
+
+/// The mixer volume of a live session, else the saved level (1.0 if none).
+#[tauri::command]
+pub async fn get_app_volume(app: AppHandle) -> Result<f64, CommandError> {
+    #[cfg(windows)]
+    {
+        let saved = prefs::get::<f64>(&app, APP_VOLUME).unwrap_or(1.0);
+        tauri::async_runtime::spawn_blocking(move || {
+            let sessions = wasapi::sessions().map_err(CommandError::io)?;
+            match sessions.first() {
+                Some((_, session)) => wasapi::get(session).map(f64::from).map_err(CommandError::io),
+                None => Ok(saved),
+            }
+        })
+        .await?
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = app;
+        Err(CommandError::unsupported("app volume is only adjustable on Windows"))
+    }
+}
+
+/// Mute or unmute every session we own, for deafen. Windows only; elsewhere
+/// the webview silences its own playback on `audio://deafen`.
+pub async fn set_output_muted(muted: bool) -> Result<(), String> {
+    #[cfg(windows)]
+    {
+        tauri::async_runtime::spawn_blocking(move || {
+            for (_, session) in wasapi::sessions().map_err(|e| e.to_string())? {
+                wasapi::set_mute(&session, muted).map_err(|e| e.to_string())?;
+            }
+            Ok(())
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = muted;
+        Ok(())
+    }
+}
+
+/// Apply the saved level (and deafen) to each new session as it appears (the
+/// webview only opens one once it plays sound). Sessions already seen are
+/// left alone, so changes made in the OS mixer stick.
+pub fn start_watcher(app: &AppHandle) {
+    #[cfg(windows)]
+    {
+        crate::shutdown::spawn(app, |app| async move {
+            let seen = std::sync::Arc::new(std::sync::Mutex::new(std::collections::HashSet::new()));
+            loop {
+                tokio::time::sleep(WATCH_INTERVAL).await;
+                if crate::throttle::engaged() {
+                    continue;
+                }
+                let level = prefs::get::<f64>(&app, APP_VOLUME);
+                let deafened = crate::deafen::deafened(&app);
+                if level.is_none() && !deafened {
+                    continue;
+                }
+                let seen = seen.clone();
+                let _ = tauri::async_runtime::spawn_blocking(move || {
+                    let Ok(sessions) = wasapi::sessions() else { return };
+                    let mut seen = seen.lock().unwrap();
+                    for (pid, session) in sessions {
+                        if seen.insert(pid) {
+                            if let Some(level) = level {
+                                let _ = wasapi::set(&session, level as f32);
+                            }
+                            if deafened {
+                                let _ = wasapi::set_mute(&session, true);
+                            }
+                        }
+                    }
+                })
+                .await;
+            }
+        });
+    }
+    #[cfg(not(windows))]
+    let _ = app;
+}
@@ -0,0 +1,124 @@
+// Webview runtime check. On Windows the app renders through the Evergreen
+// WebView2 runtime, and without it (or with one too old) the window comes up
+// white. `run` checks before the builder and, if it's missing, offers to
+// fetch Microsoft's bootstrapper rather than leaving a blank window. macOS
+// and Linux link their system webview, so there's nothing to find there.
+
+use serde::Serialize;
+
+/// Oldest WebView2 we support: the first release after support for
+/// Windows 7/8 ended, whose features the webview code relies on.
+#[cfg(windows)]
+const MIN_MAJOR: u32 = 110;
+/// Microsoft's Evergreen bootstrapper.
+#[cfg(windows)]
+const BOOTSTRAPPER_URL: &str = "https://go.microsoft.com/fwlink/p/?LinkId=2124703";
+
+#[derive(Serialize, Clone)]
+pub struct WebviewRuntime {
+    /// `None` if no runtime is installed (always set off Windows, where the
+    /// system webview is part of the OS).
+    version: Option<String>,
+    /// Installed but older than we support.
+    outdated: bool,
+}
+
+#[cfg(windows)]
+fn installed_version() -> Option<String> {
+    use webview2_com::Microsoft::Web::WebView2::Win32::GetAvailableCoreWebView2BrowserVersionString;
+    use windows::core::{PCWSTR, PWSTR};
+
+    let mut version = PWSTR::null();
+    // SAFETY: null folder means "the installed Evergreen runtime"; the
+    // returned string is CoTaskMem-allocated and freed by `take_pwstr`.
+    unsafe { GetAvailableCoreWebView2BrowserVersionString(PCWSTR::null(), &mut version) }.ok()?;
+    let version = webview2_com::take_pwstr(version);
+    (!version.is_empty()).then_some(version)
+}
+
+fn detect() -> WebviewRuntime {
+    #[cfg(windows)]
+    {
+        let version = installed_version();
+        let major = version
+            .as_deref()
+            .and_then(|v| v.split('.').next())
+            .and_then(|m| m.parse::<u32>().ok());
+        WebviewRuntime {
+            outdated: major.is_some_and(|m| m < MIN_MAJOR),
+            version,
+        }
+    }
+    #[cfg(not(windows))]
+    WebviewRuntime {
+        version: Some("system".into()),
+        outdated: false,
+    }
+}
+
+#[tauri::command]
+pub fn check_webview_runtime() -> WebviewRuntime {
+    detect()
+}
+
+#[cfg(windows)]
+fn message_box(text: &str, flags: u32) -> i32 {
+    use windows_sys::Win32::UI::WindowsAndMessaging::MessageBoxW;
+
+    let wide = |s: &str| s.encode_utf16().chain(Some(0)).collect::<Vec<u16>>();
+    let (text, caption) = (wide(text), wide("disTokoloshe"));
+    // SAFETY: both strings are NUL-terminated and outlive the call.
+    unsafe { MessageBoxW(std::ptr::null_mut(), text.as_ptr(), caption.as_ptr(), flags) }
+}
+
+/// Download the bootstrapper and run it, waiting for it to finish.
+#[cfg(windows)]
+fn install() -> Result<(), String> {
+    let bytes = tauri::async_runtime::block_on(async {
+        let response = tauri_plugin_http::reqwest::get(BOOTSTRAPPER_URL)
+            .await
+            .map_err(|e| e.to_string())?
+            .error_for_status()
+            .map_err(|e| e.to_string())?;
+        response.bytes().await.map_err(|e| e.to_string())
+    })?;
+    let path = std::env::temp_dir().join("MicrosoftEdgeWebview2Setup.exe");
+    std::fs::write(&path, &bytes).map_err(|e| e.to_string())?;
+    let status = std::process::Command::new(&path)
+        .arg("/install")
+        .status()
+        .map_err(|e| e.to_string())?;
+    let _ = std::fs::remove_file(&path);
+    if !status.success() {
+        return Err(format!("WebView2 setup exited with {status}"));
+    }
+    Ok(())
+}
+
+/// Called first thing in `run`. With a usable runtime this returns at once;
+/// otherwise it asks to install one, and exits if the user declines or the
+/// install doesn't take, since the app can't draw anything without it.
+pub fn ensure_runtime() {
+    #[cfg(windows)]
+    {
+        use windows_sys::Win32::UI::WindowsAndMessaging::{IDYES, MB_ICONERROR, MB_ICONWARNING, MB_OK, MB_YESNO};
+
+        let runtime = detect();
+        if runtime.version.is_some() && !runtime.outdated {
+            return;
+        }
+        let key = if runtime.outdated { "webview.outdated" } else { "webview.missing" };
+        if message_box(&crate::i18n::t_system(key), MB_YESNO | MB_ICONWARNING) != IDYES {
+            std::process::exit(1);
+        }
+        // Logging isn't up yet, so a failed install only shows as the
+        // runtime still being missing.
+        let _ = install();
+        let runtime = detect();
+        if runtime.version.is_none() || runtime.outdated {
+            let text = crate::i18n::t_system("webview.install_failed").replace("{url}", BOOTSTRAPPER_URL);
+            message_box(&text, MB_OK | MB_ICONERROR);
+            std::process::exit(1);
+        }
+    }
+}
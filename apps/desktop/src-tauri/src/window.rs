@@ -0,0 +1,439 @@
+// Window controls exposed to the webview. Main-window settings are persisted
+// so they can be re-applied on the next launch; popouts are transient
+// secondary windows (e.g. a call view) that live until closed or quit.
+
+use std::collections::HashSet;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder};
+use url::{Position, Url};
+
+use crate::error::CommandError;
+use crate::prefs;
+
+pub(crate) const ALWAYS_ON_TOP: &str = "always_on_top";
+/// Native title bar on (`true`) or the app's own chrome (`false`).
+pub(crate) const DECORATIONS: &str = "decorations";
+/// Hidden from other apps' screen capture and recording. Only Windows and
+/// macOS can exclude a window from capture.
+pub(crate) const CONTENT_PROTECTED: &str = "content_protected";
+
+/// Popout labels are namespaced so they can't collide with `main` and so
+/// the capability file can grant them access by glob.
+const POPOUT_PREFIX: &str = "popout-";
+
+/// Labels of the popout windows currently open.
+pub struct Popouts(pub Mutex<HashSet<String>>);
+
+/// FNV-1a, so the key is stable across builds (std's hasher isn't).
+#[cfg(desktop)]
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |h, b| {
+        (h ^ *b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Window-state file for the connected monitor layout, so docked and
+/// undocked setups each restore their own geometry.
+#[cfg(desktop)]
+pub fn state_filename(app: &AppHandle) -> String {
+    let mut monitors: Vec<String> = app
+        .available_monitors()
+        .unwrap_or_default()
+        .iter()
+        .map(|m| {
+            let (p, s) = (m.position(), m.size());
+            format!("{}x{}@{},{}", s.width, s.height, p.x, p.y)
+        })
+        .collect();
+    monitors.sort();
+    format!("window-state-{:016x}.json", fnv1a(monitors.join(";").as_bytes()))
+}
+
+/// Launch flag that throws away saved geometry before it's restored, for a
+/// window too small or too far off-screen to reach `reset_window_state`.
+#[cfg(desktop)]
+pub const RESET_WINDOW_ARG: &str = "--reset-window";
+
+/// Delete every saved window-state file (one per monitor layout, plus the
+/// plugin's default name from older builds). Returns how many went.
+#[cfg(desktop)]
+fn clear_saved_state(app: &AppHandle) -> Result<usize, String> {
+    let dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.to_string()),
+    };
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        let saved = (name.starts_with("window-state-") && name.ends_with(".json"))
+            || name == tauri_plugin_window_state::DEFAULT_FILENAME;
+        if saved {
+            std::fs::remove_file(entry.path()).map_err(|e| format!("{name}: {e}"))?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Handle `--reset-window`. Must run before the window-state plugin loads
+/// so it starts from nothing; returns whether to recenter once the main
+/// window is set up.
+#[cfg(desktop)]
+pub fn take_reset_arg(app: &AppHandle) -> bool {
+    if !std::env::args().any(|a| a == RESET_WINDOW_ARG) {
+        return false;
+    }
+    match clear_saved_state(app) {
+        Ok(n) => log::info!("{RESET_WINDOW_ARG}: cleared {n} saved window states"),
+        Err(e) => log::warn!("{RESET_WINDOW_ARG}: failed to clear saved window state: {e}"),
+    }
+    true
+}
+
+/// If the main window ended up entirely off-screen (its saved monitor is gone
+/// or the layout shifted), centre it on the primary monitor instead.
+#[cfg(desktop)]
+fn ensure_on_screen(window: &tauri::WebviewWindow) -> tauri::Result<()> {
+    let (pos, size) = (window.outer_position()?, window.outer_size()?);
+    let visible = window.available_monitors()?.iter().any(|m| {
+        let (mp, ms) = (m.position(), m.size());
+        pos.x < mp.x + ms.width as i32
+            && pos.x + size.width as i32 > mp.x
+            && pos.y < mp.y + ms.height as i32
+            && pos.y + size.height as i32 > mp.y
+    });
+    if visible {
+        return Ok(());
+    }
+    let Some(primary) = window.primary_monitor()? else {
+        return window.center();
+    };
+    let (mp, ms) = (primary.position(), primary.size());
+    window.set_position(tauri::PhysicalPosition {
+        x: mp.x + (ms.width as i32 - size.width as i32).max(0) / 2,
+        y: mp.y + (ms.height as i32 - size.height as i32).max(0) / 2,
+    })
+}
+
+/// The main window's size from tauri.conf.json, in logical pixels.
+#[cfg(desktop)]
+const DEFAULT_SIZE: (f64, f64) = (1100.0, 700.0);
+
+/// Put the main window back at its default size in the middle of the
+/// primary monitor's work area, shrunk to fit if the monitor is smaller.
+/// Saved geometry is ignored; the window-state plugin records the new
+/// placement when the app exits.
+#[cfg(desktop)]
+pub fn recenter(app: &AppHandle) -> Result<(), String> {
+    let window = app.get_webview_window("main").ok_or("main window not found")?;
+    crate::tray::show_main_window(app);
+    let _ = window.set_fullscreen(false);
+    let _ = window.unmaximize();
+    let Some(primary) = window.primary_monitor().map_err(|e| e.to_string())? else {
+        return window.center().map_err(|e| e.to_string());
+    };
+    let area = primary.work_area();
+    let scale = primary.scale_factor();
+    let width = ((DEFAULT_SIZE.0 * scale) as u32).min(area.size.width);
+    let height = ((DEFAULT_SIZE.1 * scale) as u32).min(area.size.height);
+    // `set_size` is the inner size; take the frame back off so the outer
+    // window still fits.
+    let (inner, outer) = (
+        window.inner_size().map_err(|e| e.to_string())?,
+        window.outer_size().map_err(|e| e.to_string())?,
+    );
+    let (frame_w, frame_h) = (
+        outer.width.saturating_sub(inner.width),
+        outer.height.saturating_sub(inner.height),
+    );
+    window
+        .set_size(tauri::PhysicalSize {
+            width: width.saturating_sub(frame_w),
+            height: height.saturating_sub(frame_h),
+        })
+        .map_err(|e| e.to_string())?;
+    window
+        .set_position(tauri::PhysicalPosition {
+            x: area.position.x + (area.size.width - width) as i32 / 2,
+            y: area.position.y + (area.size.height - height) as i32 / 2,
+        })
+        .map_err(|e| e.to_string())
+}
+
+/// For a window lost on a disconnected monitor. Also in the tray menu, since
+/// the window itself may be out of reach.
+#[tauri::command]
+pub fn recenter_window(app: AppHandle) -> Result<(), CommandError> {
+    #[cfg(desktop)]
+    recenter(&app)?;
+    #[cfg(mobile)]
+    let _ = app;
+    Ok(())
+}
+
+/// Forget the saved geometry for every monitor layout and put the main
+/// window back at its default size and position. What gets saved on exit is
+/// the new placement.
+#[tauri::command]
+pub fn reset_window_state(app: AppHandle) -> Result<(), CommandError> {
+    #[cfg(desktop)]
+    {
+        clear_saved_state(&app).map_err(CommandError::io)?;
+        recenter(&app)?;
+    }
+    #[cfg(mobile)]
+    let _ = app;
+    Ok(())
+}
+
+/// Re-apply persisted window settings. Called from setup.
+pub fn restore(app: &AppHandle) {
+    let Some(window) = app.get_webview_window("main") else { return };
+    #[cfg(desktop)]
+    if let Err(e) = ensure_on_screen(&window) {
+        log::warn!("failed to check window placement: {e}");
+    }
+    if prefs::get::<bool>(app, ALWAYS_ON_TOP) == Some(true) {
+        let _ = window.set_always_on_top(true);
+    }
+    #[cfg(desktop)]
+    if let Some(enabled) = prefs::get::<bool>(app, DECORATIONS) {
+        let _ = window.set_decorations(enabled);
+    }
+    #[cfg(any(windows, target_os = "macos"))]
+    if content_protected(app) {
+        let _ = window.set_content_protected(true);
+    }
+}
+
+#[cfg_attr(mobile, allow(dead_code))]
+pub(crate) fn content_protected(app: &AppHandle) -> bool {
+    prefs::get::<bool>(app, CONTENT_PROTECTED).unwrap_or(false)
+}
+
+/// Hide the app's windows (main and popouts) from screen sharing and
+/// recording by other apps, e.g. so a private DM doesn't show when the whole
+/// screen is shared. `unsupported` where the OS has no way to do it.
+#[tauri::command]
+pub fn set_content_protected(app: AppHandle, enabled: bool) -> Result<(), CommandError> {
+    #[cfg(any(windows, target_os = "macos"))]
+    {
+        for window in app.webview_windows().into_values() {
+            if window.label() == "main" || is_popout(window.label()) {
+                window.set_content_protected(enabled)?;
+            }
+        }
+        prefs::set(&app, CONTENT_PROTECTED, &enabled)?;
+        let _ = app.emit("window://content_protected", enabled);
+        Ok(())
+    }
+    #[cfg(not(any(windows, target_os = "macos")))]
+    {
+        let _ = (app, enabled);
+        Err(CommandError::unsupported("unsupported"))
+    }
+}
+
+#[tauri::command]
+pub fn set_always_on_top(app: AppHandle, enabled: bool) -> Result<(), CommandError> {
+    #[cfg(desktop)]
+    {
+        let window = app.get_webview_window("main").ok_or("main window not found")?;
+        window.set_always_on_top(enabled)?;
+        prefs::set(&app, ALWAYS_ON_TOP, &enabled)?;
+        let _ = app.emit("window://always_on_top", enabled);
+    }
+    #[cfg(mobile)]
+    let _ = (app, enabled);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_decorations(app: AppHandle, enabled: bool) -> Result<(), CommandError> {
+    #[cfg(desktop)]
+    {
+        let window = app.get_webview_window("main").ok_or("main window not found")?;
+        window.set_decorations(enabled)?;
+        prefs::set(&app, DECORATIONS, &enabled)?;
+        let _ = app.emit("window://decorations", enabled);
+    }
+    #[cfg(mobile)]
+    let _ = (app, enabled);
+    Ok(())
+}
+
+/// Flash the taskbar entry (Windows, Linux) or bounce the dock icon (macOS)
+/// for the main window, unless it already has focus. `critical` keeps the
+/// dock bouncing until the app is activated rather than once; Windows
+/// flashes until focused either way. Returns whether attention was requested.
+pub fn request_attention(app: &AppHandle, critical: bool) -> Result<bool, String> {
+    #[cfg(desktop)]
+    {
+        let window = app.get_webview_window("main").ok_or("main window not found")?;
+        if window.is_focused().unwrap_or(false) && window.is_visible().unwrap_or(false) {
+            return Ok(false);
+        }
+        let kind = if critical {
+            tauri::UserAttentionType::Critical
+        } else {
+            tauri::UserAttentionType::Informational
+        };
+        window.request_user_attention(Some(kind)).map_err(|e| e.to_string())?;
+        Ok(true)
+    }
+    #[cfg(mobile)]
+    {
+        let _ = (app, critical);
+        Ok(false)
+    }
+}
+
+/// Stop any flashing or bouncing. Called when the main window gains focus.
+pub fn clear_attention(app: &AppHandle) {
+    #[cfg(desktop)]
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.request_user_attention(None);
+    }
+    #[cfg(mobile)]
+    let _ = app;
+}
+
+#[tauri::command]
+pub fn request_user_attention(app: AppHandle, critical: bool) -> Result<bool, CommandError> {
+    Ok(request_attention(&app, critical)?)
+}
+
+// Buttons for the custom title bar. Each acts on the calling window, so
+// popouts can use the same chrome.
+
+#[tauri::command]
+pub fn minimize_window(window: tauri::WebviewWindow) -> Result<(), CommandError> {
+    Ok(window.minimize()?)
+}
+
+/// Toggle maximized; returns whether the window is now maximized.
+#[tauri::command]
+pub fn maximize_window(window: tauri::WebviewWindow) -> Result<bool, CommandError> {
+    let maximized = window.is_maximized()?;
+    if maximized {
+        window.unmaximize()?;
+    } else {
+        window.maximize()?;
+    }
+    Ok(!maximized)
+}
+
+/// A regular close request, so the main window still goes through the
+/// close-to-tray / leave-beacon handling in `on_window_event`.
+#[tauri::command]
+pub fn close_window(window: tauri::WebviewWindow) -> Result<(), CommandError> {
+    Ok(window.close()?)
+}
+
+fn popout_label(label: &str) -> Result<String, CommandError> {
+    if label.is_empty() || !label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(CommandError::invalid_input(format!("invalid popout label: {label}")));
+    }
+    Ok(format!("{POPOUT_PREFIX}{label}"))
+}
+
+/// `url` as an app route, or an error if it could lead anywhere else: a
+/// remote page would get the same IPC access as the app. Resolved against a
+/// stand-in base, so a route that escapes it shows up as a changed host
+/// rather than needing a pattern each.
+fn popout_route(url: &str) -> Result<String, CommandError> {
+    let not_a_route = || CommandError::invalid_input(format!("popout url must be an app route: {url}"));
+    // Backslashes are path separators to some webviews; control characters
+    // are stripped by the parser, so `/\t/evil.com` would turn into `//evil.com`.
+    if !url.starts_with('/') || url.starts_with("//") || url.contains('\\') || url.chars().any(char::is_control) {
+        return Err(not_a_route());
+    }
+    let base = Url::parse("app://popout/").expect("literal url");
+    let route = base.join(url).map_err(|_| not_a_route())?;
+    // `origin()` is opaque for a non-special scheme like this, so compare
+    // the parts.
+    if route.scheme() != base.scheme() || route.host() != base.host() || route.port() != base.port() {
+        return Err(not_a_route());
+    }
+    Ok(route[Position::BeforePath..].to_string())
+}
+
+pub fn is_popout(label: &str) -> bool {
+    label.starts_with(POPOUT_PREFIX)
+}
+
+/// Open `url` (an app route such as `/call/3`) in an always-on-top popout,
+/// or focus the existing popout with that label.
+#[tauri::command]
+pub async fn open_popout(app: AppHandle, url: String, label: String) -> Result<(), CommandError> {
+    let label = popout_label(&label)?;
+    let url = popout_route(&url)?;
+    if let Some(window) = app.get_webview_window(&label) {
+        return Ok(window.set_focus()?);
+    }
+    let mut builder = WebviewWindowBuilder::new(&app, &label, WebviewUrl::App(url.into()));
+    #[cfg(desktop)]
+    {
+        builder = builder
+            .title("disTokoloshe")
+            .inner_size(480.0, 320.0)
+            .min_inner_size(320.0, 200.0)
+            .always_on_top(true)
+            .content_protected(cfg!(any(windows, target_os = "macos")) && content_protected(&app));
+    }
+    builder.build()?;
+    app.state::<Popouts>().0.lock().unwrap().insert(label);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn close_popout(app: AppHandle, label: String) -> Result<(), CommandError> {
+    let label = popout_label(&label)?;
+    if let Some(window) = app.get_webview_window(&label) {
+        window.close()?;
+    }
+    Ok(())
+}
+
+/// Forget a popout once its window is gone.
+pub fn popout_destroyed(app: &AppHandle, label: &str) {
+    app.state::<Popouts>().0.lock().unwrap().remove(label);
+}
+
+/// Destroy every open popout. Called on quit so none outlive the main window.
+pub fn close_popouts(app: &AppHandle) {
+    let labels: Vec<String> = app.state::<Popouts>().0.lock().unwrap().drain().collect();
+    for label in labels {
+        if let Some(window) = app.get_webview_window(&label) {
+            let _ = window.destroy();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::popout_route;
+
+    #[test]
+    fn popout_route_only_allows_app_routes() {
+        assert_eq!(popout_route("/call/3").unwrap(), "/call/3");
+        assert_eq!(popout_route("/call/3?muted=1#top").unwrap(), "/call/3?muted=1#top");
+        assert_eq!(popout_route("/call/../chat/1").unwrap(), "/chat/1");
+        for url in [
+            "//evil.com/x",
+            "///evil.com",
+            "/\\evil.com",
+            "\\\\evil.com",
+            "/\t/evil.com",
+            "https://evil.com",
+            "call/3",
+            "",
+            "javascript:alert(1)",
+        ] {
+            assert!(popout_route(url).is_err(), "{url:?}");
+        }
+    }
+}
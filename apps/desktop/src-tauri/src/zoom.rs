@@ -0,0 +1,99 @@
+// Webview zoom for accessibility, persisted and restored on launch.
+// Ctrl/Cmd +, - and 0 are registered as global shortcuts only while the main
+// window is focused, so they act like window shortcuts and never steal the
+// keys from other apps.
+
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+use crate::error::CommandError;
+use crate::prefs;
+
+pub(crate) const ZOOM: &str = "zoom";
+const MIN_ZOOM: f64 = 0.5;
+const MAX_ZOOM: f64 = 3.0;
+const ZOOM_STEP: f64 = 0.1;
+
+const ZOOM_IN: &str = "CmdOrControl+Equal";
+const ZOOM_OUT: &str = "CmdOrControl+Minus";
+const ZOOM_RESET: &str = "CmdOrControl+Digit0";
+
+pub struct ZoomState(pub Mutex<f64>);
+
+fn apply(app: &AppHandle, factor: f64) -> Result<f64, String> {
+    // Round so repeated steps don't drift to 1.0999999.
+    let factor = (factor.clamp(MIN_ZOOM, MAX_ZOOM) * 100.0).round() / 100.0;
+    let window = app.get_webview_window("main").ok_or("main window not found")?;
+    window.set_zoom(factor).map_err(|e| e.to_string())?;
+    *app.state::<ZoomState>().0.lock().unwrap() = factor;
+    let _ = app.emit("zoom://changed", factor);
+    Ok(factor)
+}
+
+fn set_and_save(app: &AppHandle, factor: f64) -> Result<f64, String> {
+    let factor = apply(app, factor)?;
+    prefs::set(app, ZOOM, &factor)?;
+    Ok(factor)
+}
+
+/// Re-apply the persisted zoom. Called from setup.
+pub fn restore(app: &AppHandle) {
+    if let Some(factor) = prefs::get::<f64>(app, ZOOM) {
+        if let Err(e) = apply(app, factor) {
+            log::warn!("failed to restore zoom: {e}");
+        }
+    }
+}
+
+fn step(app: &AppHandle, accelerator: &str) {
+    let current = *app.state::<ZoomState>().0.lock().unwrap();
+    let next = match accelerator {
+        ZOOM_IN => current + ZOOM_STEP,
+        ZOOM_OUT => current - ZOOM_STEP,
+        _ => 1.0,
+    };
+    if let Err(e) = set_and_save(app, next) {
+        log::warn!("failed to change zoom: {e}");
+    }
+}
+
+/// Bind the zoom keys while the main window has focus, release them when
+/// it loses it.
+pub fn on_focus_changed(app: &AppHandle, focused: bool) {
+    if !crate::shortcuts::available() {
+        return;
+    }
+    for accelerator in [ZOOM_IN, ZOOM_OUT, ZOOM_RESET] {
+        let Ok(shortcut) = accelerator.parse::<Shortcut>() else { continue };
+        let shortcuts = app.global_shortcut();
+        if !focused {
+            let _ = shortcuts.unregister(shortcut);
+            continue;
+        }
+        if shortcuts.is_registered(shortcut) {
+            continue;
+        }
+        let result = shortcuts.on_shortcut(shortcut, move |app, _, event| {
+            if event.state == ShortcutState::Pressed {
+                step(app, accelerator);
+            }
+        });
+        if let Err(e) = result {
+            log::warn!("failed to bind {accelerator}: {e}");
+        }
+    }
+}
+
+#[tauri::command]
+pub fn set_zoom(app: AppHandle, factor: f64) -> Result<f64, CommandError> {
+    if !factor.is_finite() {
+        return Err(CommandError::invalid_input("zoom factor must be a number"));
+    }
+    Ok(set_and_save(&app, factor)?)
+}
+
+#[tauri::command]
+pub fn get_zoom(state: tauri::State<'_, ZoomState>) -> f64 {
+    *state.0.lock().unwrap()
+}
@@ -4,6 +4,10 @@ import { Login } from '@distokoloshe/ui/pages/Login';
 import { RoomPage } from '@distokoloshe/ui/pages/Room';
 import { getBaseUrl, setBaseUrl, clearStoredToken } from '@distokoloshe/ui/lib/api';
 import { ServerConfig } from './ServerConfig';
+import { SafeMode } from './SafeMode';
+
+// Set by the native side when it restarted into safe mode.
+const safeMode = new URLSearchParams(window.location.search).has('safe_mode');
 
 export function App() {
   const [serverUrl, setServerUrl] = useState(getBaseUrl());
@@ -21,6 +25,10 @@ export function App() {
     window.location.reload();
   }, []);
 
+  if (safeMode) {
+    return <SafeMode />;
+  }
+
   // First-launch: no server URL configured
   if (!serverUrl) {
     return <ServerConfig onConnect={handleServerConnect} />;
@@ -0,0 +1,22 @@
+import { relaunch } from '@tauri-apps/plugin-process';
+
+/** Minimal recovery screen shown when the app was restarted into safe mode. */
+export function SafeMode() {
+  return (
+    <div className="min-h-screen flex items-center justify-center bg-zinc-900">
+      <div className="text-center max-w-sm">
+        <h1 className="text-lg text-zinc-200 mb-2">Safe mode</h1>
+        <p className="text-sm text-zinc-500 mb-6">
+          Saved preferences, shortcuts and window layout were not loaded. Restart to return to
+          normal.
+        </p>
+        <button
+          onClick={() => relaunch()}
+          className="px-4 py-2 rounded bg-zinc-700 text-zinc-100 hover:bg-zinc-600"
+        >
+          Restart normally
+        </button>
+      </div>
+    </div>
+  );
+}
@@ -1,5 +1,6 @@
 import { useState } from 'react';
 import { Server } from 'lucide-react';
+import { isCommandError } from '@distokoloshe/ui/lib/utils';
 
 interface ServerConfigProps {
   onConnect: (url: string) => void;
@@ -14,8 +15,32 @@ export function ServerConfig({ onConnect }: ServerConfigProps) {
     e.preventDefault();
     setError(null);
 
+    if (!url.trim()) return;
+
+    // Natively: canonical URL plus a health check, without the webview's CORS
+    if ('__TAURI_INTERNALS__' in window) {
+      setTesting(true);
+      try {
+        const { invoke } = await import('@tauri-apps/api/core');
+        onConnect(await invoke<string>('normalize_server_url', { url }));
+      } catch (err) {
+        const { kind, message } = isCommandError(err) ? err : { kind: 'other', message: String(err) };
+        if (kind === 'bad_scheme') {
+          setError('The server URL must start with http:// or https://.');
+        } else if (kind === 'not_a_distokoloshe_server') {
+          setError("That address answered, but it isn't a disTokoloshe server.");
+        } else if (kind === 'unreachable') {
+          setError(`Could not reach server: ${message}`);
+        } else {
+          setError(message);
+        }
+      } finally {
+        setTesting(false);
+      }
+      return;
+    }
+
     let serverUrl = url.trim().replace(/\/+$/, '');
-    if (!serverUrl) return;
 
     // Auto-add https:// if no protocol specified
     if (!/^https?:\/\//.test(serverUrl)) {
@@ -19,6 +19,18 @@ if ('__TAURI_INTERNALS__' in window) {
       localStorage.setItem(key, v);
     }),
   ).catch(() => {});
+
+  // Relay uncaught errors to the native log so they survive without devtools
+  import('@tauri-apps/api/core').then(({ invoke }) => {
+    const relay = (message: string) =>
+      invoke('log_from_webview', { level: 'error', message }).catch(() => {});
+    window.addEventListener('error', (e) =>
+      relay(e.error instanceof Error ? e.error.stack || e.error.message : `${e.message} at ${e.filename}:${e.lineno}`),
+    );
+    window.addEventListener('unhandledrejection', (e) =>
+      relay(`unhandled rejection: ${e.reason instanceof Error ? e.reason.stack || e.reason.message : String(e.reason)}`),
+    );
+  }).catch(() => {});
 }
 
 createRoot(document.getElementById('root')!).render(
@@ -125,15 +125,14 @@ router.post('/ping', requireAuth, (req: Request, res: Response) => {
   res.json({ ok: true });
 });
 
-// POST /api/events/leave — Explicit leave signal (skips grace period)
-// Called by the client on tab close (sendBeacon) or intentional disconnect.
-router.post('/leave', requireAuth, (req: Request, res: Response) => {
-  const userId = req.user!.sub;
-
-  // Cancel any pending grace period — this is an intentional leave
-  const pending = cancelPendingDisconnect(userId);
-  const roomId = pending?.roomId ?? getUserRoomId(userId);
-
+// Upper bound for a client-requested leave grace; the same as the grace an
+// SSE drop gets, so a leave can't keep someone "present" longer than a
+// reconnect would.
+const MAX_LEAVE_GRACE_MS = 15_000;
+
+// Room leave and offline broadcasts for an explicit leave, now or once its
+// grace has run out.
+function completeLeave(userId: number, roomId: number | null) {
   // Clear room membership
   if (roomId != null) {
     setUserRoom(userId, null);
@@ -164,7 +163,31 @@ router.post('/leave', requireAuth, (req: Request, res: Response) => {
 
   // If this user was piping audio, release the lock and resume the radio.
   void releasePipeIfOwnedBy(userId);
+}
+
+// POST /api/events/leave — Explicit leave signal (skips grace period)
+// Called by the client on tab close (sendBeacon) or intentional disconnect.
+// An optional `grace_ms` (clamped to MAX_LEAVE_GRACE_MS) defers the leave
+// instead, like an SSE drop: reconnecting within it keeps the room.
+router.post('/leave', requireAuth, (req: Request, res: Response) => {
+  const userId = req.user!.sub;
+
+  // Cancel any pending grace period — this is an intentional leave
+  const pending = cancelPendingDisconnect(userId);
+  const roomId = pending?.roomId ?? getUserRoomId(userId);
+
+  const requested = req.body?.grace_ms;
+  const graceMs = typeof requested === 'number' && Number.isFinite(requested)
+    ? Math.min(Math.max(0, Math.floor(requested)), MAX_LEAVE_GRACE_MS)
+    : 0;
+
+  if (graceMs > 0) {
+    scheduleDisconnect(userId, roomId, () => completeLeave(userId, roomId), graceMs);
+    res.json({ left: true, grace_ms: graceMs });
+    return;
+  }
 
+  completeLeave(userId, roomId);
   res.json({ left: true });
 });
 
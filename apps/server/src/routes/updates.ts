@@ -9,6 +9,7 @@ const UPDATES_DIR = path.resolve(process.env.DATA_DIR || './data', 'updates');
 interface PlatformEntry {
   url: string;
   signature: string;
+  installed_sha256?: string;
 }
 
 interface UpdateManifest {
@@ -39,22 +40,9 @@ function isNewer(a: string, b: string): boolean {
   return false;
 }
 
-// GET /api/updates/:target/:arch/:current_version — Tauri-compatible update check
-// Returns update manifest or 204 if up to date
-router.get('/:target/:arch/:current_version', (req: Request, res: Response) => {
-  const { target, arch, current_version } = req.params;
-  const manifest = loadManifest();
-
-  if (!manifest) {
-    res.status(204).end();
-    return;
-  }
-
-  if (!isNewer(manifest.version, current_version)) {
-    res.status(204).end();
-    return;
-  }
-
+// Manifest entry for one platform, or 204 if there's none
+function sendPlatform(req: Request, res: Response, manifest: UpdateManifest) {
+  const { target, arch } = req.params;
   const platformKey = `${target}-${arch}`;
   const platform = manifest.platforms[platformKey];
 
@@ -77,7 +65,39 @@ router.get('/:target/:arch/:current_version', (req: Request, res: Response) => {
     pub_date: manifest.pub_date || new Date().toISOString(),
     url: downloadUrl,
     signature: platform.signature,
+    installed_sha256: platform.installed_sha256,
   });
+}
+
+// GET /api/updates/installer/:target/:arch/:current_version — the latest
+// manifest whatever the client runs. Rollback needs an installer even when
+// it's on the newest (broken) release; it installs its own signed copy.
+router.get('/installer/:target/:arch/:current_version', (req: Request, res: Response) => {
+  const manifest = loadManifest();
+  if (!manifest) {
+    res.status(204).end();
+    return;
+  }
+  sendPlatform(req, res, manifest);
+});
+
+// GET /api/updates/:target/:arch/:current_version — Tauri-compatible update check
+// Returns update manifest or 204 if up to date
+router.get('/:target/:arch/:current_version', (req: Request, res: Response) => {
+  const { current_version } = req.params;
+  const manifest = loadManifest();
+
+  if (!manifest) {
+    res.status(204).end();
+    return;
+  }
+
+  if (!isNewer(manifest.version, current_version)) {
+    res.status(204).end();
+    return;
+  }
+
+  sendPlatform(req, res, manifest);
 });
 
 // GET /api/updates/download/:filename — Serve update binary
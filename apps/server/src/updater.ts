@@ -1,3 +1,4 @@
+import crypto from 'crypto';
 import fs from 'fs';
 import path from 'path';
 import https from 'https';
@@ -20,11 +21,17 @@ interface GitHubAsset {
   size: number;
 }
 
+interface PlatformEntry {
+  url: string;
+  signature: string;
+  installed_sha256?: string;
+}
+
 interface UpdateManifest {
   version: string;
   notes: string;
   pub_date: string;
-  platforms: Record<string, { url: string; signature: string }>;
+  platforms: Record<string, PlatformEntry>;
 }
 
 function loadCurrentManifest(): UpdateManifest | null {
@@ -132,6 +139,37 @@ function downloadText(url: string, headers: Record<string, string> = {}): Promis
   });
 }
 
+function sha256File(filePath: string): Promise<string> {
+  return new Promise((resolve, reject) => {
+    const hash = crypto.createHash('sha256');
+    fs.createReadStream(filePath)
+      .on('data', (chunk) => hash.update(chunk))
+      .on('end', () => resolve(hash.digest('hex')))
+      .on('error', reject);
+  });
+}
+
+// Hash of the file the client ends up running, which it re-checks after
+// installing. An AppImage is installed as-is, so that's the asset itself;
+// other installers unpack something else, which the release can publish as
+// `<asset>.installed-sha256`. Without either the client skips the check.
+async function installedSha256(
+  asset: GitHubAsset,
+  destPath: string,
+  assets: GitHubAsset[],
+  headers: Record<string, string>,
+): Promise<string | undefined> {
+  if (asset.name.toLowerCase().endsWith('.appimage')) {
+    return sha256File(destPath);
+  }
+  const hashAsset = assets.find((a) => a.name === `${asset.name}.installed-sha256`);
+  if (!hashAsset) return undefined;
+  const text = await downloadText(hashAsset.browser_download_url, headers);
+  // sha256sum output: "<hash>  <file>"
+  const hash = text.split(/\s+/)[0].toLowerCase();
+  return /^[0-9a-f]{64}$/.test(hash) ? hash : undefined;
+}
+
 // Detect platform from asset filename
 function detectPlatform(filename: string): string | null {
   const lower = filename.toLowerCase();
@@ -185,7 +223,7 @@ async function syncFromGitHub(): Promise<void> {
     fs.mkdirSync(UPDATES_DIR, { recursive: true });
 
     // Find installer + signature pairs
-    const platforms: Record<string, { url: string; signature: string }> = {};
+    const platforms: Record<string, PlatformEntry> = {};
 
     for (const asset of release.assets) {
       // Skip signature files — they're paired with their binary
@@ -211,6 +249,7 @@ async function syncFromGitHub(): Promise<void> {
       platforms[platform] = {
         url: asset.name, // Relative filename — route builds absolute URL
         signature,
+        installed_sha256: await installedSha256(asset, destPath, release.assets, headers),
       };
 
       console.log(`[updater] ${platform} ready: ${asset.name}`);
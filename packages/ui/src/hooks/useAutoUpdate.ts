@@ -1,11 +1,17 @@
 import { useState, useEffect, useRef } from 'react';
-import { getBaseUrl } from '../lib/api';
+import { getBaseUrl, getStoredToken } from '../lib/api';
+import { errorMessage, isCommandError } from '../lib/utils';
 
 const isTauri = () => '__TAURI_INTERNALS__' in window;
 
 interface UpdateInfo {
   version: string;
   body: string | null;
+  body_html: string | null;
+  content_length: number | null;
+  pub_date: string | null;
+  mandatory: boolean;
+  mirror: string | null;
 }
 
 type UpdateStatus = 'idle' | 'checking' | 'downloading' | 'error';
@@ -49,7 +55,9 @@ export function useAutoUpdate() {
 
       try {
         const { invoke } = await import('@tauri-apps/api/core');
-        const result = await invoke<UpdateInfo | null>('check_for_update', { serverUrl });
+        const result = await invoke<UpdateInfo | null>('check_for_update', {
+          options: { serverUrl, token: getStoredToken() },
+        });
 
         if (result) {
           setUpdateInfo(result);
@@ -58,15 +66,21 @@ export function useAutoUpdate() {
             await invoke('install_update');
             // App restarts after install — this line won't normally run
           } catch (err) {
-            setError(String(err));
+            setError(errorMessage(err));
             setStatus('error');
           }
         } else {
           setStatus('idle');
         }
       } catch (err) {
-        setError(String(err));
-        setStatus('error');
+        // Connectivity blips are retried on the next interval without
+        // bothering the user; real server errors are surfaced.
+        if (isCommandError(err) && (err.kind === 'network' || err.kind === 'timeout')) {
+          setStatus('idle');
+        } else {
+          setError(errorMessage(err));
+          setStatus('error');
+        }
       } finally {
         checkingRef.current = false;
       }
@@ -1,5 +1,6 @@
 import { useCallback, useEffect, useRef, useState } from 'react';
 import { getBaseUrl, getStoredToken } from '../lib/api';
+import { errorMessage } from '../lib/utils';
 
 const isTauri = () => '__TAURI_INTERNALS__' in window;
 
@@ -92,7 +93,7 @@ export function usePipePlayer(): PipeController {
       setTitle(resolved);
     } catch (e) {
       setState('error');
-      setError(e instanceof Error ? e.message : String(e));
+      setError(errorMessage(e));
       throw e;
     }
   }, []);
@@ -124,7 +125,7 @@ export function usePipePlayer(): PipeController {
       });
     } catch (e) {
       setState('error');
-      setError(e instanceof Error ? e.message : String(e));
+      setError(errorMessage(e));
       throw e;
     }
   }, []);
@@ -32,3 +32,19 @@ export function toggleTheme(): 'dark' | 'light' {
 export function getTheme(): 'dark' | 'light' {
   return document.documentElement.classList.contains('dark') ? 'dark' : 'light';
 }
+
+/** Structured failure from the desktop app's native commands: `{ kind, message, ... }`. */
+export interface CommandError {
+  kind: string;
+  message: string;
+}
+
+export function isCommandError(err: unknown): err is CommandError {
+  return typeof err === 'object' && err !== null && 'kind' in err && 'message' in err;
+}
+
+/** Message for anything thrown: a native command error, an Error or a string. */
+export function errorMessage(err: unknown): string {
+  if (isCommandError(err)) return err.message;
+  return err instanceof Error ? err.message : String(err);
+}